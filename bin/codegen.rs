@@ -0,0 +1,22 @@
+use anyhow::{Context as _, Result};
+use dbus_sans_io::codegen::CodeGenerator;
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input = args
+        .next()
+        .context("usage: dbus-codegen <introspection.xml> [output.rs]")?;
+    let output = args.next();
+
+    let xml = std::fs::read_to_string(&input).with_context(|| format!("failed to read {input}"))?;
+    let code = CodeGenerator::new().generate_from_xml(&xml)?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, code).with_context(|| format!("failed to write {output}"))?
+        }
+        None => print!("{code}"),
+    }
+
+    Ok(())
+}
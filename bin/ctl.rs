@@ -0,0 +1,243 @@
+use anyhow::{Context as _, Result, bail};
+use dbus_sans_io::{
+    BlockingConnection, MatchRule, Message, Proxy, SignalBuilder, Value,
+    args::{parse_args, print_args},
+    introspection::{Access, Node},
+    messages::Hello,
+};
+
+const USAGE: &str = "usage: dbus-ctl <command> [args...]
+
+commands:
+    list
+    call DEST PATH IFACE MEMBER [SIGNATURE [ARGS...]]
+    emit PATH IFACE MEMBER [SIGNATURE [ARGS...]]
+    introspect DEST PATH
+    tree DEST
+    monitor [SERVICE...]
+    get-property DEST PATH IFACE PROPERTY...
+    set-property DEST PATH IFACE PROPERTY SIGNATURE ARGS...";
+
+struct Bus {
+    conn: BlockingConnection,
+}
+
+impl Bus {
+    fn session() -> Result<Self> {
+        let mut conn = BlockingConnection::session()?;
+        conn.auth()?;
+        let mut bus = Self { conn };
+        bus.call(Hello.into())?;
+        Ok(bus)
+    }
+
+    /// Sends `message` and waits for its reply, skipping anything else.
+    fn call(&mut self, mut message: Message) -> Result<Message> {
+        self.conn.send_message(&mut message)?;
+        let Message::MethodCall { serial, .. } = message else {
+            bail!("only method calls get a reply");
+        };
+        loop {
+            let reply = self.conn.read_message()?;
+            match &reply {
+                Message::MethodReturn { reply_serial, .. } if *reply_serial == serial => {
+                    return Ok(reply);
+                }
+                Message::Error {
+                    reply_serial,
+                    error_name,
+                    body,
+                    ..
+                } if *reply_serial == serial => match body.first() {
+                    Some(Value::String(message)) => bail!("{error_name}: {message}"),
+                    _ => bail!("{error_name}"),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn introspect(&mut self, destination: &str, path: &str) -> Result<Node> {
+        let proxy = Proxy::new(destination, path, "org.freedesktop.DBus.Introspectable");
+        let reply = self.call(proxy.introspect())?;
+        Node::try_from(&reply)
+    }
+}
+
+fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (command, args) = args.split_first().context(USAGE)?;
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match (command.as_str(), args.as_slice()) {
+        ("list", []) => list(),
+        ("call", [destination, path, interface, member, rest @ ..]) => {
+            let proxy = Proxy::new(*destination, *path, *interface);
+            let reply = Bus::session()?.call(proxy.call(member, values(rest)?)?)?;
+            let body = proxy.reply(member, &reply)?;
+            if !body.is_empty() {
+                println!("{}", print_args(&body));
+            }
+            Ok(())
+        }
+        ("emit", [path, interface, member, rest @ ..]) => {
+            let signal =
+                SignalBuilder::new(path.to_string(), interface.to_string(), member.to_string())
+                    .args(values(rest)?);
+            Bus::session()?.conn.send_message(&mut signal.into())
+        }
+        ("introspect", [destination, path]) => introspect(destination, path),
+        ("tree", [destination]) => {
+            let mut bus = Bus::session()?;
+            tree(&mut bus, destination, "/")
+        }
+        ("monitor", services) => monitor(services),
+        ("get-property", [destination, path, interface, properties @ ..])
+            if !properties.is_empty() =>
+        {
+            let proxy = Proxy::new(*destination, *path, *interface);
+            let mut bus = Bus::session()?;
+            for property in properties {
+                let reply = bus.call(proxy.get_property(property)?)?;
+                println!("{}", print_args(&[proxy.property_reply(&reply)?]));
+            }
+            Ok(())
+        }
+        ("set-property", [destination, path, interface, property, signature, rest @ ..]) => {
+            let proxy = Proxy::new(*destination, *path, *interface);
+            let [value] = <[Value; 1]>::try_from(parse_args(signature, rest)?)
+                .ok()
+                .context("set-property takes a single value")?;
+            Bus::session()?.call(proxy.set_property(property, value)?)?;
+            Ok(())
+        }
+        _ => bail!(USAGE),
+    }
+}
+
+/// Values of `[SIGNATURE [ARGS...]]`.
+fn values(args: &[&str]) -> Result<Vec<Value>> {
+    match args.split_first() {
+        Some((signature, args)) => parse_args(signature, args),
+        None => Ok(vec![]),
+    }
+}
+
+fn list() -> Result<()> {
+    let proxy = Proxy::new(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+    );
+    let reply = Bus::session()?.call(proxy.call("ListNames", vec![])?)?;
+    let body = proxy.reply("ListNames", &reply)?;
+    let [Value::Array(_, names)] = body.as_slice() else {
+        bail!("expected a list of names, got {reply:?}");
+    };
+    let mut names = names
+        .iter()
+        .filter_map(|name| match name {
+            Value::String(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        println!("{name}");
+    }
+    Ok(())
+}
+
+fn introspect(destination: &str, path: &str) -> Result<()> {
+    let node = Bus::session()?.introspect(destination, path)?;
+    println!(
+        "{:<40} {:<10} {:<16} {:<16} FLAGS",
+        "NAME", "TYPE", "SIGNATURE", "RESULT"
+    );
+    let signature = |types: Vec<_>| -> String {
+        let signature = types.iter().map(ToString::to_string).collect::<String>();
+        if signature.is_empty() {
+            String::from("-")
+        } else {
+            signature
+        }
+    };
+    for interface in &node.interfaces {
+        println!(
+            "{:<40} {:<10} {:<16} {:<16} -",
+            interface.name, "interface", "-", "-"
+        );
+        for method in &interface.methods {
+            println!(
+                ".{:<39} {:<10} {:<16} {:<16} -",
+                method.name,
+                "method",
+                signature(method.in_signature()),
+                signature(method.out_signature())
+            );
+        }
+        for property in &interface.properties {
+            let flags = match property.access {
+                Access::Read => "read",
+                Access::Write => "write",
+                Access::ReadWrite => "readwrite",
+            };
+            println!(
+                ".{:<39} {:<10} {:<16} {:<16} {flags}",
+                property.name,
+                "property",
+                property.complete_type.to_string(),
+                "-"
+            );
+        }
+        for signal in &interface.signals {
+            println!(
+                ".{:<39} {:<10} {:<16} {:<16} -",
+                signal.name,
+                "signal",
+                signature(signal.signature()),
+                "-"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn tree(bus: &mut Bus, destination: &str, path: &str) -> Result<()> {
+    println!("{path}");
+    let node = bus.introspect(destination, path)?;
+    for child in node
+        .children
+        .iter()
+        .filter_map(|child| child.name.as_deref())
+    {
+        let child = if path == "/" {
+            format!("/{child}")
+        } else {
+            format!("{path}/{child}")
+        };
+        tree(bus, destination, &child)?;
+    }
+    Ok(())
+}
+
+/// Prints everything sent by or to `services`, or all traffic if empty.
+fn monitor(services: &[&str]) -> Result<()> {
+    let rules = services
+        .iter()
+        .flat_map(|service| {
+            [
+                MatchRule::new().all_types().sender(service.to_string()),
+                MatchRule::new()
+                    .all_types()
+                    .destination(service.to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut bus = Bus::session()?;
+    bus.conn.become_monitor(&rules)?;
+    loop {
+        let message = bus.conn.read_message()?;
+        print!("{}", message.pretty());
+    }
+}
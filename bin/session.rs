@@ -1,17 +1,12 @@
 use anyhow::Result;
 use dbus_sans_io::{
-    Message, Value, body_is, define_sum_message, destination_is, interface_is, member_is,
-    message_is,
-    messages::{
-        AddMatch, Hello, IntrospectRequest, IntrospectResponse, NameAcquired, PropertiesChanged,
-        RequestName, ShowNotification,
-    },
-    path_is,
+    Handler, Message, MethodError, ObjectServer, Signals, Value, define_sum_message,
+    introspection::{Interface, Node},
+    messages::{AddMatch, Hello, NameAcquired, PropertiesChanged, RequestName, ShowNotification},
 };
 use std::borrow::Cow;
 
 const INTROSPECTION: &str = r#"
-<?xml version="1.0" encoding="UTF-8"?>
 <node>
     <interface name="org.me.test">
         <method name="Plus">
@@ -23,71 +18,38 @@ const INTROSPECTION: &str = r#"
 </node>
 "#;
 
-#[derive(Debug)]
-struct PlusRequest<'a> {
-    sender: Cow<'a, str>,
-    serial: u32,
-    lhs: i32,
-    rhs: i32,
-}
-
-impl<'a> TryFrom<&'a Message> for PlusRequest<'a> {
-    type Error = anyhow::Error;
-
-    fn try_from(message: &Message) -> Result<Self> {
-        message_is!(
-            message,
-            Message::MethodCall {
-                serial,
-                path,
-                member,
-                interface: Some(interface),
-                destination: Some(destination),
-                body,
-                sender: Some(sender),
-                ..
-            }
-        );
+struct Plus;
 
-        path_is!(path, "/");
-        member_is!(member, "Plus");
-        interface_is!(interface, "org.me.test");
-        destination_is!(destination, "org.me.test");
-        body_is!(body, [Value::Int32(lhs), Value::Int32(rhs)]);
+impl Handler for Plus {
+    fn introspect(&self) -> Interface {
+        let node = Node::parse(INTROSPECTION).expect("valid introspection data");
+        node.interfaces.into_iter().next().expect("one interface")
+    }
 
-        Ok(Self {
-            sender: sender.clone(),
-            serial: *serial,
-            lhs: *lhs,
-            rhs: *rhs,
-        })
+    fn call(
+        &mut self,
+        _member: &str,
+        args: &[Value],
+        _signals: &mut Signals,
+    ) -> Result<Vec<Value>, MethodError> {
+        let [Value::Int32(lhs), Value::Int32(rhs)] = args else {
+            return Err(MethodError::invalid_args("expected two integers"));
+        };
+        Ok(vec![Value::Int32(lhs.wrapping_add(*rhs))])
     }
 }
 
-struct PlusResponse<'a> {
-    req: PlusRequest<'a>,
+fn object_server() -> Result<ObjectServer> {
+    let mut server = ObjectServer::new();
+    server.add("/", Plus)?;
+    Ok(server)
 }
 
-impl<'a> PlusResponse<'a> {
-    fn new(req: PlusRequest<'a>) -> Self {
-        Self { req }
+fn on_message(server: &mut ObjectServer, message: Message) -> Vec<Message> {
+    if let Message::MethodCall { .. } = message {
+        return server.dispatch(&message);
     }
-}
 
-impl<'a> From<PlusResponse<'a>> for Message {
-    fn from(value: PlusResponse<'a>) -> Message {
-        Message::MethodReturn {
-            serial: 0,
-            reply_serial: value.req.serial,
-            destination: Some(Cow::Owned(value.req.sender.to_string())),
-            sender: None,
-            unix_fds: None,
-            body: vec![Value::Int32(value.req.lhs + value.req.rhs)],
-        }
-    }
-}
-
-fn on_message(message: Message) -> Vec<Message> {
     let Ok(message) = DBusMessage::try_from(&message) else {
         println!("Unknown: {:?}", message);
         return vec![];
@@ -100,35 +62,19 @@ fn on_message(message: Message) -> Vec<Message> {
         DBusMessage::PropertiesChanged(properties_changed) => {
             println!("{properties_changed:?}");
         }
-        DBusMessage::IntrospectRequest(introspect_req) => {
-            println!("{introspect_req:?}");
-            if introspect_req.destination == "org.me.test" && introspect_req.path == "/" {
-                let response = IntrospectResponse::new(introspect_req, INTROSPECTION).into();
-                return vec![response];
-            }
-        }
-        DBusMessage::PlusRequest(plus_req) => {
-            let response = PlusResponse::new(plus_req).into();
-            return vec![response];
-        }
     }
 
     vec![]
 }
 
-define_sum_message!(
-    DBusMessage,
-    NameAcquired,
-    PropertiesChanged,
-    IntrospectRequest,
-    PlusRequest
-);
+define_sum_message!(DBusMessage, NameAcquired, PropertiesChanged);
 
 #[cfg(feature = "blocking")]
 fn main() -> Result<()> {
     println!("Blocking version\n\n");
     use dbus_sans_io::BlockingConnection;
     let mut conn = BlockingConnection::session()?;
+    let mut server = object_server()?;
 
     conn.auth()?;
     conn.send_message(&mut Hello.into())?;
@@ -138,7 +84,7 @@ fn main() -> Result<()> {
 
     loop {
         let message = conn.read_message()?;
-        for mut reply in on_message(message) {
+        for mut reply in on_message(&mut server, message) {
             conn.send_message(&mut reply)?;
         }
     }
@@ -152,6 +98,7 @@ fn main() -> Result<()> {
     use libc::{POLLERR, POLLIN, POLLOUT, poll, pollfd};
     use std::os::fd::AsRawFd;
     let mut conn = PollConnection::session()?;
+    let mut server = object_server()?;
 
     let mut fds = [pollfd {
         fd: conn.as_raw_fd(),
@@ -178,7 +125,7 @@ fn main() -> Result<()> {
         let (readable, writable) = do_poll(&mut fds);
 
         for message in conn.poll(readable, writable)? {
-            for mut reply in on_message(message) {
+            for mut reply in on_message(&mut server, message) {
                 conn.enqueue(&mut reply)?;
             }
         }
@@ -191,8 +138,9 @@ fn main() -> Result<()> {
 
     use io_uring::IoUring;
     let mut ring = IoUring::new(10)?;
+    let mut server = object_server()?;
 
-    use dbus_sans_io::{Cqe, IoUringConnection, Sqe};
+    use dbus_sans_io::{BufRing, Cqe, IoUringConnection, Sqe};
     const SOCKET_USER_DATA: u64 = 1;
     const CONNECT_USER_DATA: u64 = 2;
     const READ_USER_DATA: u64 = 3;
@@ -205,6 +153,19 @@ fn main() -> Result<()> {
         WRITE_USER_DATA,
     );
 
+    if std::env::args().any(|arg| arg == "--buf-ring") {
+        let buf_ring = BufRing::new(0, 16, 4096);
+        unsafe {
+            ring.submitter().register_buf_ring_with_flags(
+                buf_ring.ring_addr(),
+                buf_ring.entries(),
+                buf_ring.bgid(),
+                0,
+            )?
+        };
+        conn = conn.with_buf_ring(buf_ring);
+    }
+
     conn.enqueue(&mut Hello.into())?;
     conn.enqueue(&mut ShowNotification::new("Header", "Body").into())?;
     conn.enqueue(&mut AddMatch::new(Cow::Borrowed("/org/local/PipewireDBus")).into())?;
@@ -237,6 +198,14 @@ fn main() -> Result<()> {
             } => opcode::Write::new(types::Fd(fd), buf, len)
                 .build()
                 .user_data(user_data),
+            Sqe::Writev {
+                fd,
+                iovecs,
+                len,
+                user_data,
+            } => opcode::Writev::new(types::Fd(fd), iovecs, len)
+                .build()
+                .user_data(user_data),
             Sqe::Read {
                 fd,
                 buf,
@@ -245,6 +214,16 @@ fn main() -> Result<()> {
             } => opcode::Read::new(types::Fd(fd), buf, len)
                 .build()
                 .user_data(user_data),
+            Sqe::Cancel { target, user_data } => opcode::AsyncCancel::new(target)
+                .build()
+                .user_data(user_data),
+            Sqe::RecvMulti {
+                fd,
+                buf_group,
+                user_data,
+            } => opcode::RecvMulti::new(types::Fd(fd), buf_group)
+                .build()
+                .user_data(user_data),
         }
     }
 
@@ -252,19 +231,20 @@ fn main() -> Result<()> {
         Cqe {
             user_data: cqe.user_data(),
             result: cqe.result(),
+            flags: cqe.flags(),
         }
     }
 
     loop {
-        for sqe in conn.next_sqe().into_iter().filter_map(|e| e) {
+        for sqe in conn.next_sqe().into_iter().flatten() {
             unsafe { ring.submission().push(&map_sqe(sqe))? };
         }
 
         ring.submit_and_wait(1)?;
 
         while let Some(cqe) = ring.completion().next() {
-            if let Some(message) = conn.process_cqe(map_cqe(cqe))? {
-                let replies = on_message(message);
+            for message in conn.process_cqe(map_cqe(cqe))? {
+                let replies = on_message(&mut server, message);
                 for mut reply in replies {
                     println!("Replying with {reply:?}");
                     conn.enqueue(&mut reply)?;
@@ -0,0 +1,310 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Ident, Result, parse_macro_input};
+
+mod shape;
+use shape::{Repr, Shape};
+
+/// Implements `dbus_sans_io::DBusType`.
+///
+/// Structs map to `(...)`, single-field tuple structs to their inner type,
+/// `#[dbus(dict)]` structs to `a{sv}` and fieldless enums to an integer
+/// (`#[dbus(repr = "u32")]` by default) or, with `#[dbus(string)]`, to `s`.
+///
+/// Structs without fields are rejected, and so are discriminants that don't
+/// fit in the repr:
+///
+/// ```compile_fail
+/// #[derive(dbus_sans_io::ToValue)]
+/// struct Empty {}
+/// ```
+///
+/// ```compile_fail,E0080
+/// #[derive(dbus_sans_io::ToValue)]
+/// #[dbus(repr = "u8")]
+/// enum Level {
+///     Low = 1,
+///     High = 256,
+/// }
+/// ```
+#[proc_macro_derive(DBusType, attributes(dbus))]
+pub fn derive_dbus_type(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), dbus_type)
+}
+
+/// Implements `From<T> for dbus_sans_io::Value`, see `DBusType` for the mapping.
+#[proc_macro_derive(ToValue, attributes(dbus))]
+pub fn derive_to_value(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), to_value)
+}
+
+/// Implements `TryFrom<dbus_sans_io::Value> for T`, see `DBusType` for the mapping.
+#[proc_macro_derive(FromValue, attributes(dbus))]
+pub fn derive_from_value(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), from_value)
+}
+
+fn expand(input: DeriveInput, f: fn(&DeriveInput, &Shape) -> TokenStream2) -> TokenStream {
+    let result: Result<TokenStream2> = Shape::parse(&input).map(|shape| f(&input, &shape));
+    match result {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn dbus_type(input: &DeriveInput, shape: &Shape) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match shape {
+        Shape::Struct(fields) => {
+            let types = fields.iter().map(|f| &f.ty);
+            quote! {
+                ::dbus_sans_io::CompleteType::Struct(vec![
+                    #(<#types as ::dbus_sans_io::DBusType>::complete_type()),*
+                ])
+            }
+        }
+        Shape::Newtype(ty) => quote! {
+            <#ty as ::dbus_sans_io::DBusType>::complete_type()
+        },
+        Shape::Dict(_) => quote! {
+            ::dbus_sans_io::CompleteType::Array(Box::new(
+                ::dbus_sans_io::CompleteType::DictEntry(
+                    Box::new(::dbus_sans_io::CompleteType::String),
+                    Box::new(::dbus_sans_io::CompleteType::Variant),
+                ),
+            ))
+        },
+        Shape::IntEnum(repr, _) => {
+            let variant = repr.variant();
+            quote! { ::dbus_sans_io::CompleteType::#variant }
+        }
+        Shape::StringEnum(_) => quote! { ::dbus_sans_io::CompleteType::String },
+    };
+
+    quote! {
+        impl #impl_generics ::dbus_sans_io::DBusType for #name #ty_generics #where_clause {
+            fn complete_type() -> ::dbus_sans_io::CompleteType {
+                #body
+            }
+        }
+    }
+}
+
+fn to_value(input: &DeriveInput, shape: &Shape) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match shape {
+        Shape::Struct(fields) => {
+            let members = fields.iter().map(|f| &f.member);
+            quote! {
+                ::dbus_sans_io::Value::Struct(vec![
+                    #(::core::convert::Into::<::dbus_sans_io::Value>::into(value.#members)),*
+                ])
+            }
+        }
+        Shape::Newtype(_) => quote! {
+            ::core::convert::Into::<::dbus_sans_io::Value>::into(value.0)
+        },
+        Shape::Dict(fields) => {
+            let entries = fields.iter().map(|f| {
+                let member = &f.member;
+                let key = &f.key;
+                let entry = |v: TokenStream2| {
+                    quote! {
+                        items.push(::dbus_sans_io::Value::DictEntry(
+                            Box::new(::dbus_sans_io::Value::String(String::from(#key))),
+                            Box::new(::dbus_sans_io::Value::Variant(Box::new(
+                                ::core::convert::Into::<::dbus_sans_io::Value>::into(#v),
+                            ))),
+                        ));
+                    }
+                };
+                if f.optional.is_some() {
+                    let push = entry(quote! { item });
+                    quote! {
+                        if let Some(item) = value.#member {
+                            #push
+                        }
+                    }
+                } else {
+                    entry(quote! { value.#member })
+                }
+            });
+            quote! {
+                let mut items = vec![];
+                #(#entries)*
+                ::dbus_sans_io::Value::Array(
+                    ::dbus_sans_io::CompleteType::DictEntry(
+                        Box::new(::dbus_sans_io::CompleteType::String),
+                        Box::new(::dbus_sans_io::CompleteType::Variant),
+                    ),
+                    items,
+                )
+            }
+        }
+        Shape::IntEnum(repr, variants) => {
+            let variant = repr.variant();
+            let primitive = repr.primitive();
+            let checks = discriminant_checks(name, *repr, variants);
+            quote! {
+                #checks
+                ::dbus_sans_io::Value::#variant(value as #primitive)
+            }
+        }
+        Shape::StringEnum(variants) => {
+            let idents = variants.iter().map(|(ident, _)| ident);
+            let names = variants.iter().map(|(_, name)| name);
+            quote! {
+                ::dbus_sans_io::Value::String(String::from(match value {
+                    #(#name::#idents => #names),*
+                }))
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics ::core::convert::From<#name #ty_generics> for ::dbus_sans_io::Value #where_clause {
+            fn from(value: #name #ty_generics) -> ::dbus_sans_io::Value {
+                #body
+            }
+        }
+    }
+}
+
+fn from_value(input: &DeriveInput, shape: &Shape) -> TokenStream2 {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let anyhow = quote! { ::dbus_sans_io::__private::anyhow };
+
+    let body = match shape {
+        Shape::Struct(fields) => {
+            let count = fields.len();
+            let members = fields.iter().map(|f| &f.member);
+            let bindings: Vec<_> = (0..count)
+                .map(|idx| quote::format_ident!("field{}", idx))
+                .collect();
+            quote! {
+                let ::dbus_sans_io::Value::Struct(fields) = value else {
+                    #anyhow::bail!("expected a struct for {}, got {:?}", #name_str, value);
+                };
+                let [#(#bindings),*]: [::dbus_sans_io::Value; #count] =
+                    fields.try_into().map_err(|fields: Vec<::dbus_sans_io::Value>| {
+                        #anyhow::anyhow!(
+                            "expected {} fields for {}, got {}",
+                            #count,
+                            #name_str,
+                            fields.len()
+                        )
+                    })?;
+                Ok(Self {
+                    #(#members: ::core::convert::TryFrom::try_from(#bindings)?),*
+                })
+            }
+        }
+        Shape::Newtype(_) => quote! {
+            Ok(Self(::core::convert::TryFrom::try_from(value)?))
+        },
+        Shape::Dict(fields) => {
+            let slots: Vec<_> = (0..fields.len())
+                .map(|idx| quote::format_ident!("field{}", idx))
+                .collect();
+            let slot_types = fields.iter().map(|f| f.optional.as_ref().unwrap_or(&f.ty));
+            let keys = fields.iter().map(|f| &f.key);
+            let members = fields.iter().map(|f| &f.member);
+            let inits = fields.iter().zip(&slots).map(|(f, slot)| {
+                if f.optional.is_some() {
+                    quote! { #slot }
+                } else {
+                    let key = &f.key;
+                    quote! {
+                        #slot.ok_or_else(|| #anyhow::anyhow!("{} is missing {:?}", #name_str, #key))?
+                    }
+                }
+            });
+            quote! {
+                let ::dbus_sans_io::Value::Array(_, items) = value else {
+                    #anyhow::bail!("expected a dictionary for {}, got {:?}", #name_str, value);
+                };
+                #(let mut #slots: Option<#slot_types> = None;)*
+                for item in items {
+                    let ::dbus_sans_io::Value::DictEntry(key, value) = item else {
+                        #anyhow::bail!("expected a dictionary entry, got {:?}", item);
+                    };
+                    let ::dbus_sans_io::Value::String(key) = *key else {
+                        #anyhow::bail!("expected a string key, got {:?}", key);
+                    };
+                    let ::dbus_sans_io::Value::Variant(value) = *value else {
+                        #anyhow::bail!("expected a variant value, got {:?}", value);
+                    };
+                    match key.as_str() {
+                        #(#keys => #slots = Some(::core::convert::TryFrom::try_from(*value)?),)*
+                        _ => {}
+                    }
+                }
+                Ok(Self {
+                    #(#members: #inits),*
+                })
+            }
+        }
+        Shape::IntEnum(repr, variants) => {
+            let variant = repr.variant();
+            let primitive = repr.primitive();
+            let checks = discriminant_checks(name, *repr, variants);
+            quote! {
+                #checks
+                let ::dbus_sans_io::Value::#variant(n) = value else {
+                    #anyhow::bail!("expected {} for {}, got {:?}", stringify!(#primitive), #name_str, value);
+                };
+                #(
+                    if n == Self::#variants as #primitive {
+                        return Ok(Self::#variants);
+                    }
+                )*
+                #anyhow::bail!("unknown {} value {}", #name_str, n)
+            }
+        }
+        Shape::StringEnum(variants) => {
+            let idents = variants.iter().map(|(ident, _)| ident);
+            let names = variants.iter().map(|(_, name)| name);
+            quote! {
+                let ::dbus_sans_io::Value::String(s) = value else {
+                    #anyhow::bail!("expected a string for {}, got {:?}", #name_str, value);
+                };
+                match s.as_str() {
+                    #(#names => Ok(Self::#idents),)*
+                    _ => #anyhow::bail!("unknown {} value {:?}", #name_str, s),
+                }
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics ::core::convert::TryFrom<::dbus_sans_io::Value> for #name #ty_generics #where_clause {
+            type Error = #anyhow::Error;
+
+            fn try_from(value: ::dbus_sans_io::Value) -> #anyhow::Result<Self> {
+                #body
+            }
+        }
+    }
+}
+
+/// Fails the build if a discriminant of `name` doesn't survive the cast to
+/// `repr`, which would otherwise silently truncate it.
+fn discriminant_checks(name: &Ident, repr: Repr, variants: &[Ident]) -> TokenStream2 {
+    let primitive = repr.primitive();
+    let checks = variants.iter().map(|variant| {
+        let message = format!("the discriminant of {name}::{variant} does not fit in {primitive}");
+        quote! {
+            assert!(#name::#variant as i128 == #name::#variant as #primitive as i128, #message);
+        }
+    });
+    quote! {
+        const { #(#checks)* }
+    }
+}
@@ -0,0 +1,251 @@
+use proc_macro2::Span;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr, Member,
+    PathArguments, Result, Type,
+};
+
+pub(crate) struct StructField {
+    pub(crate) member: Member,
+    pub(crate) ty: Type,
+}
+
+pub(crate) struct DictField {
+    pub(crate) member: Member,
+    pub(crate) ty: Type,
+    pub(crate) key: String,
+    /// `T` if the field is declared as `Option<T>`
+    pub(crate) optional: Option<Type>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Repr {
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+}
+
+impl Repr {
+    fn parse(s: &LitStr) -> Result<Self> {
+        match s.value().as_str() {
+            "u8" => Ok(Self::U8),
+            "i16" => Ok(Self::I16),
+            "u16" => Ok(Self::U16),
+            "i32" => Ok(Self::I32),
+            "u32" => Ok(Self::U32),
+            "i64" => Ok(Self::I64),
+            "u64" => Ok(Self::U64),
+            _ => Err(Error::new(
+                s.span(),
+                "repr must be one of u8, i16, u16, i32, u32, i64, u64",
+            )),
+        }
+    }
+
+    pub(crate) fn primitive(self) -> Ident {
+        let name = match self {
+            Self::U8 => "u8",
+            Self::I16 => "i16",
+            Self::U16 => "u16",
+            Self::I32 => "i32",
+            Self::U32 => "u32",
+            Self::I64 => "i64",
+            Self::U64 => "u64",
+        };
+        Ident::new(name, Span::call_site())
+    }
+
+    pub(crate) fn variant(self) -> Ident {
+        let name = match self {
+            Self::U8 => "Byte",
+            Self::I16 => "Int16",
+            Self::U16 => "UInt16",
+            Self::I32 => "Int32",
+            Self::U32 => "UInt32",
+            Self::I64 => "Int64",
+            Self::U64 => "UInt64",
+        };
+        Ident::new(name, Span::call_site())
+    }
+}
+
+pub(crate) enum Shape {
+    /// `(...)`
+    Struct(Vec<StructField>),
+    /// A single-field tuple struct, encoded as its inner type
+    Newtype(Type),
+    /// `a{sv}` with one entry per field
+    Dict(Vec<DictField>),
+    /// A fieldless enum encoded as its discriminant
+    IntEnum(Repr, Vec<Ident>),
+    /// A fieldless enum encoded as the name of its variant
+    StringEnum(Vec<(Ident, String)>),
+}
+
+#[derive(Default)]
+struct ContainerAttrs {
+    dict: bool,
+    string: bool,
+    repr: Option<Repr>,
+}
+
+fn container_attrs(attrs: &[Attribute]) -> Result<ContainerAttrs> {
+    let mut out = ContainerAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("dbus")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("dict") {
+                out.dict = true;
+                Ok(())
+            } else if meta.path.is_ident("string") {
+                out.string = true;
+                Ok(())
+            } else if meta.path.is_ident("repr") {
+                out.repr = Some(Repr::parse(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported dbus attribute"))
+            }
+        })?;
+    }
+    Ok(out)
+}
+
+fn rename_attr(attrs: &[Attribute]) -> Result<Option<String>> {
+    let mut out = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("dbus")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                out = Some(name.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported dbus attribute"))
+            }
+        })?;
+    }
+    Ok(out)
+}
+
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner.clone()),
+        _ => None,
+    }
+}
+
+impl Shape {
+    pub(crate) fn parse(input: &DeriveInput) -> Result<Self> {
+        let attrs = container_attrs(&input.attrs)?;
+
+        match &input.data {
+            Data::Struct(data) => {
+                if attrs.string || attrs.repr.is_some() {
+                    return Err(Error::new_spanned(
+                        &input.ident,
+                        "`string` and `repr` are only supported on enums",
+                    ));
+                }
+
+                if attrs.dict {
+                    let Fields::Named(fields) = &data.fields else {
+                        return Err(Error::new_spanned(
+                            &input.ident,
+                            "`dict` requires a struct with named fields",
+                        ));
+                    };
+                    let mut out = vec![];
+                    for field in &fields.named {
+                        let ident = field.ident.clone().expect("named field");
+                        let key = match rename_attr(&field.attrs)? {
+                            Some(key) => key,
+                            None => ident.to_string().trim_start_matches("r#").to_string(),
+                        };
+                        out.push(DictField {
+                            member: Member::Named(ident),
+                            ty: field.ty.clone(),
+                            key,
+                            optional: option_inner(&field.ty),
+                        });
+                    }
+                    return Ok(Self::Dict(out));
+                }
+
+                match &data.fields {
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                        Ok(Self::Newtype(fields.unnamed[0].ty.clone()))
+                    }
+                    Fields::Unit => Err(Error::new_spanned(
+                        &input.ident,
+                        "unit structs have no D-Bus representation",
+                    )),
+                    fields if fields.is_empty() => Err(Error::new_spanned(
+                        &input.ident,
+                        "structs without fields have no D-Bus representation",
+                    )),
+                    fields => Ok(Self::Struct(
+                        fields
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, field)| StructField {
+                                member: match &field.ident {
+                                    Some(ident) => Member::Named(ident.clone()),
+                                    None => Member::Unnamed(idx.into()),
+                                },
+                                ty: field.ty.clone(),
+                            })
+                            .collect(),
+                    )),
+                }
+            }
+
+            Data::Enum(data) => {
+                if attrs.dict {
+                    return Err(Error::new_spanned(
+                        &input.ident,
+                        "`dict` is only supported on structs",
+                    ));
+                }
+                for variant in &data.variants {
+                    if !matches!(variant.fields, Fields::Unit) {
+                        return Err(Error::new_spanned(
+                            variant,
+                            "only fieldless enums are supported",
+                        ));
+                    }
+                }
+
+                if attrs.string {
+                    let mut variants = vec![];
+                    for variant in &data.variants {
+                        let name = match rename_attr(&variant.attrs)? {
+                            Some(name) => name,
+                            None => variant.ident.to_string(),
+                        };
+                        variants.push((variant.ident.clone(), name));
+                    }
+                    Ok(Self::StringEnum(variants))
+                } else {
+                    Ok(Self::IntEnum(
+                        attrs.repr.unwrap_or(Repr::U32),
+                        data.variants.iter().map(|v| v.ident.clone()).collect(),
+                    ))
+                }
+            }
+
+            Data::Union(_) => Err(Error::new_spanned(&input.ident, "unions are not supported")),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use dbus_sans_io::{CompleteType, DBusType, FromValue, ToValue, Value};
+
+#[derive(Debug, PartialEq, DBusType, ToValue, FromValue)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: String,
+}
+
+#[derive(Debug, PartialEq, DBusType, ToValue, FromValue)]
+struct Id(u64);
+
+#[derive(Debug, PartialEq, DBusType, ToValue, FromValue)]
+#[dbus(dict)]
+struct Hints {
+    urgency: u8,
+    #[dbus(rename = "desktop-entry")]
+    desktop_entry: Option<String>,
+    #[dbus(rename = "sound-file")]
+    sound_file: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, DBusType, ToValue, FromValue)]
+#[dbus(repr = "u8")]
+enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+#[derive(Debug, PartialEq, DBusType, ToValue, FromValue)]
+#[dbus(string)]
+enum Mode {
+    Fast,
+    #[dbus(rename = "slow-mode")]
+    Slow,
+}
+
+#[test]
+fn test_struct() {
+    assert_eq!(
+        Point::complete_type(),
+        CompleteType::Struct(vec![
+            CompleteType::Int32,
+            CompleteType::Int32,
+            CompleteType::String
+        ])
+    );
+
+    let point = Point {
+        x: 1,
+        y: 2,
+        label: String::from("p"),
+    };
+    let value = Value::from(point);
+    assert_eq!(
+        value,
+        Value::Struct(vec![
+            Value::Int32(1),
+            Value::Int32(2),
+            Value::String(String::from("p"))
+        ])
+    );
+    assert_eq!(
+        Point::try_from(value).unwrap(),
+        Point {
+            x: 1,
+            y: 2,
+            label: String::from("p")
+        }
+    );
+
+    assert!(Point::try_from(Value::Struct(vec![Value::Int32(1)])).is_err());
+}
+
+#[test]
+fn test_newtype() {
+    assert_eq!(Id::complete_type(), CompleteType::UInt64);
+    assert_eq!(Value::from(Id(42)), Value::UInt64(42));
+    assert_eq!(Id::try_from(Value::UInt64(42)).unwrap(), Id(42));
+}
+
+#[test]
+fn test_dict() {
+    assert_eq!(
+        Hints::complete_type(),
+        CompleteType::Array(Box::new(CompleteType::DictEntry(
+            Box::new(CompleteType::String),
+            Box::new(CompleteType::Variant)
+        )))
+    );
+
+    let hints = Hints {
+        urgency: 2,
+        desktop_entry: Some(String::from("firefox")),
+        sound_file: None,
+    };
+    let value = Value::from(hints);
+    let Value::Array(_, items) = &value else {
+        panic!("expected an array, got {value:?}");
+    };
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[1],
+        Value::DictEntry(
+            Box::new(Value::String(String::from("desktop-entry"))),
+            Box::new(Value::Variant(Box::new(Value::String(String::from(
+                "firefox"
+            )))))
+        )
+    );
+
+    assert_eq!(
+        Hints::try_from(value).unwrap(),
+        Hints {
+            urgency: 2,
+            desktop_entry: Some(String::from("firefox")),
+            sound_file: None,
+        }
+    );
+
+    let missing = Value::Array(
+        CompleteType::DictEntry(
+            Box::new(CompleteType::String),
+            Box::new(CompleteType::Variant),
+        ),
+        vec![],
+    );
+    assert!(Hints::try_from(missing).is_err());
+}
+
+#[test]
+fn test_enums() {
+    assert_eq!(Urgency::complete_type(), CompleteType::Byte);
+    assert_eq!(Value::from(Urgency::Critical), Value::Byte(2));
+    assert_eq!(Urgency::try_from(Value::Byte(1)).unwrap(), Urgency::Normal);
+    assert!(Urgency::try_from(Value::Byte(7)).is_err());
+
+    assert_eq!(Mode::complete_type(), CompleteType::String);
+    assert_eq!(
+        Value::from(Mode::Slow),
+        Value::String(String::from("slow-mode"))
+    );
+    assert_eq!(
+        Mode::try_from(Value::String(String::from("Fast"))).unwrap(),
+        Mode::Fast
+    );
+}
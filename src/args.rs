@@ -0,0 +1,185 @@
+use crate::{
+    decoders::{DecodingBuffer, SignatureDecoder},
+    types::{CompleteType, Value},
+};
+use anyhow::{Context, Result, bail, ensure};
+use std::fmt::{self, Write as _};
+
+/// Parses command line arguments the way `busctl` does: one token per basic
+/// value, arrays as their length followed by the items, variants as their
+/// signature followed by the value, and structs and dict entries as their
+/// fields in order. `a{sv} 1 Name s me` is `{"Name": <"me">}`.
+pub fn parse_args<S: AsRef<str>>(signature: &str, args: &[S]) -> Result<Vec<Value>> {
+    let mut buf = DecodingBuffer::new(signature.as_bytes());
+    let types = SignatureDecoder::decode_signature(&mut buf)?;
+    let mut tokens = args.iter().map(AsRef::as_ref);
+    let values = types
+        .items
+        .iter()
+        .map(|complete_type| parse_value(complete_type, &mut tokens))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(extra) = tokens.next() {
+        bail!("unexpected argument {extra:?} after the values of {signature}");
+    }
+    Ok(values)
+}
+
+/// Prints `values` in the syntax of `parse_args`, preceded by their
+/// signature, with strings quoted as `busctl` does.
+pub fn print_args(values: &[Value]) -> String {
+    let mut out = values
+        .iter()
+        .map(|value| value.complete_type().to_string())
+        .collect::<String>();
+    for value in values {
+        print_value(value, &mut out).expect("writing to a String can't fail");
+    }
+    out
+}
+
+fn parse_value<'a>(
+    complete_type: &CompleteType,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<Value> {
+    let mut next = || {
+        tokens
+            .next()
+            .with_context(|| format!("missing a value of type {complete_type}"))
+    };
+    let value = match complete_type {
+        CompleteType::Byte => Value::Byte(parse_number(next()?)?),
+        CompleteType::Bool => Value::Bool(match next()? {
+            "true" | "yes" | "on" | "1" => true,
+            "false" | "no" | "off" | "0" => false,
+            other => bail!("invalid boolean {other:?}"),
+        }),
+        CompleteType::Int16 => Value::Int16(parse_number(next()?)?),
+        CompleteType::UInt16 => Value::UInt16(parse_number(next()?)?),
+        CompleteType::Int32 => Value::Int32(parse_number(next()?)?),
+        CompleteType::UInt32 => Value::UInt32(parse_number(next()?)?),
+        CompleteType::Int64 => Value::Int64(parse_number(next()?)?),
+        CompleteType::UInt64 => Value::UInt64(parse_number(next()?)?),
+        CompleteType::Double => Value::Double(parse_number(next()?)?),
+        CompleteType::UnixFD => bail!("file descriptors can't be passed as arguments"),
+        CompleteType::String => Value::String(next()?.to_string()),
+        CompleteType::ObjectPath => {
+            let path = next()?;
+            ensure!(path.starts_with('/'), "invalid object path {path:?}");
+            Value::ObjectPath(path.to_string().into())
+        }
+        CompleteType::Signature => {
+            let signature = next()?;
+            SignatureDecoder::decode_signature(&mut DecodingBuffer::new(signature.as_bytes()))?;
+            Value::Signature(signature.as_bytes().to_vec())
+        }
+        CompleteType::Struct(fields) => Value::Struct(
+            fields
+                .iter()
+                .map(|field| parse_value(field, tokens))
+                .collect::<Result<_>>()?,
+        ),
+        CompleteType::Array(item) => {
+            let len: usize = parse_number(next()?)?;
+            let items = (0..len)
+                .map(|_| parse_value(item, tokens))
+                .collect::<Result<_>>()?;
+            Value::Array((**item).clone(), items)
+        }
+        CompleteType::DictEntry(key, value) => Value::DictEntry(
+            Box::new(parse_value(key, tokens)?),
+            Box::new(parse_value(value, tokens)?),
+        ),
+        CompleteType::Variant => {
+            let inner = next()?.parse::<CompleteType>()?;
+            Value::Variant(Box::new(parse_value(&inner, tokens)?))
+        }
+    };
+    Ok(value)
+}
+
+fn parse_number<T>(token: &str) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    token
+        .parse()
+        .with_context(|| format!("invalid number {token:?}"))
+}
+
+fn print_value(value: &Value, out: &mut String) -> fmt::Result {
+    match value {
+        Value::Byte(value) => write!(out, " {value}"),
+        Value::Bool(value) => write!(out, " {value}"),
+        Value::Int16(value) => write!(out, " {value}"),
+        Value::UInt16(value) => write!(out, " {value}"),
+        Value::Int32(value) => write!(out, " {value}"),
+        Value::UInt32(value) => write!(out, " {value}"),
+        Value::Int64(value) => write!(out, " {value}"),
+        Value::UInt64(value) => write!(out, " {value}"),
+        Value::Double(value) => write!(out, " {value}"),
+        Value::UnixFD(value) => write!(out, " {value}"),
+        Value::String(value) => write!(out, " {value:?}"),
+        Value::ObjectPath(value) => write!(out, " {value:?}"),
+        Value::Signature(value) => write!(out, " {:?}", String::from_utf8_lossy(value)),
+        Value::Struct(fields) => fields.iter().try_for_each(|field| print_value(field, out)),
+        Value::Array(_, items) => {
+            write!(out, " {}", items.len())?;
+            items.iter().try_for_each(|item| print_value(item, out))
+        }
+        Value::DictEntry(key, value) => {
+            print_value(key, out)?;
+            print_value(value, out)
+        }
+        Value::Variant(value) => {
+            write!(out, " {}", value.complete_type())?;
+            print_value(value, out)
+        }
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let values = parse_args(
+        "sa{sv}(ib)ao",
+        &[
+            "me", "2", "Name", "s", "you", "Size", "ai", "2", "1", "2", "-5", "yes", "1", "/a",
+        ],
+    )
+    .unwrap();
+    assert_eq!(values[1].complete_type().to_string(), "a{sv}", "{values:?}");
+    assert_eq!(
+        values[2],
+        Value::Struct(vec![Value::Int32(-5), Value::Bool(true)])
+    );
+    assert_eq!(
+        values[3],
+        Value::Array(
+            CompleteType::ObjectPath,
+            vec![Value::ObjectPath("/a".into())]
+        )
+    );
+    assert_eq!(
+        print_args(&values),
+        r#"sa{sv}(ib)ao "me" 2 "Name" s "you" "Size" ai 2 1 2 -5 true 1 "/a""#
+    );
+
+    assert_eq!(parse_args::<&str>("", &[]).unwrap(), vec![]);
+    assert_eq!(
+        parse_args("as", &["0"]).unwrap(),
+        vec![Value::Array(CompleteType::String, vec![])]
+    );
+    for (signature, args) in [
+        ("i", &["x"][..]),
+        ("i", &[]),
+        ("i", &["1", "2"]),
+        ("y", &["256"]),
+        ("b", &["maybe"]),
+        ("o", &["a"]),
+        ("v", &["ii", "1"]),
+        ("h", &["0"]),
+        ("a", &[]),
+    ] {
+        assert!(parse_args(signature, args).is_err(), "{signature} {args:?}");
+    }
+}
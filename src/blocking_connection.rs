@@ -1,13 +1,15 @@
 use crate::{
-    encoders::MessageEncoder,
-    fsm::{AuthFSM, AuthWants, ReaderFSM, WriterFSM},
+    disconnected::{Disconnected, PendingCalls},
+    fsm::{AuthFSM, AuthWants, MAX_IOVECS, ReaderFSM, WriterFSM},
+    match_rule::MatchRule,
+    messages::BecomeMonitor,
     serial::Serial,
     session_connection,
     types::Message,
 };
-use anyhow::Result;
+use anyhow::{Result, bail, ensure};
 use std::{
-    io::{Read as _, Write as _},
+    io::{ErrorKind, IoSlice, Read as _, Write as _},
     os::{fd::FromRawFd, unix::net::UnixStream},
 };
 
@@ -18,6 +20,11 @@ pub struct BlockingConnection {
     auth: AuthFSM,
     reader: ReaderFSM,
     writer: WriterFSM,
+
+    pending: PendingCalls,
+    disconnected: Option<Disconnected>,
+    /// Set once `BecomeMonitor` succeeded, nothing can be sent afterwards
+    monitor: bool,
 }
 
 impl BlockingConnection {
@@ -29,6 +36,10 @@ impl BlockingConnection {
             auth: AuthFSM::new(),
             reader: ReaderFSM::new(),
             writer: WriterFSM::new(),
+
+            pending: PendingCalls::default(),
+            disconnected: None,
+            monitor: false,
         })
     }
 
@@ -40,19 +51,30 @@ impl BlockingConnection {
             auth: AuthFSM::new(),
             reader: ReaderFSM::new(),
             writer: WriterFSM::new(),
+
+            pending: PendingCalls::default(),
+            disconnected: None,
+            monitor: false,
         }
     }
 
     pub fn auth(&mut self) -> Result<()> {
+        self.check_connected()?;
         loop {
             match self.auth.wants() {
                 AuthWants::Read(buf) => {
-                    let len = self.stream.read(buf)?;
+                    let read = self.stream.read(buf);
+                    let Some(len) = self.check_io(read)? else {
+                        continue;
+                    };
                     self.auth.satisfy_read(len)?;
                 }
 
                 AuthWants::Write(bytes) => {
-                    let len = self.stream.write(bytes)?;
+                    let written = self.stream.write(bytes);
+                    let Some(len) = self.check_io(written)? else {
+                        continue;
+                    };
                     if let Some(_guid) = self.auth.satisfy_write(len)? {
                         return Ok(());
                     }
@@ -62,29 +84,196 @@ impl BlockingConnection {
     }
 
     pub fn send_message(&mut self, message: &mut Message) -> Result<()> {
+        self.check_connected()?;
+        ensure!(!self.monitor, "monitor connections can't send messages");
         *message.serial_mut() = self.serial.increment_and_get();
 
-        let buf = MessageEncoder::encode(message)?;
-        self.writer.enqueue(buf);
+        self.writer.enqueue_message(message)?;
+        self.pending.sent(message);
 
         loop {
-            let Some(buf) = self.writer.wants() else {
+            let mut slices = [IoSlice::new(&[]); MAX_IOVECS];
+            let count = self.writer.wants_vectored(&mut slices);
+            if count == 0 {
                 break;
-            };
-            let len = self.stream.write(buf)?;
-            self.writer.satisfy(len)?;
+            }
+            let written = self.stream.write_vectored(&slices[..count]);
+            if let Some(len) = self.check_io(written)? {
+                self.writer.satisfy(len)?;
+            }
         }
 
         Ok(())
     }
 
     pub fn read_message(&mut self) -> Result<Message> {
+        self.check_connected()?;
         loop {
-            let buf = self.reader.wants();
-            let len = self.stream.read(buf)?;
-            if let Some(message) = self.reader.satisfy(len)? {
+            if let Some(message) = self.reader.next_message()? {
+                self.pending.received(&message);
                 return Ok(message);
             }
+            let buf = self.reader.wants();
+            let read = self.stream.read(buf);
+            if let Some(len) = self.check_io(read)? {
+                self.reader.satisfy(len);
+            }
+        }
+    }
+
+    /// Turns the connection into a monitor of messages matching `rules`,
+    /// or of all of them if empty. Messages received before the reply are
+    /// dropped and `send_message` fails from then on.
+    pub fn become_monitor(&mut self, rules: &[MatchRule]) -> Result<()> {
+        let mut call = BecomeMonitor::new(rules).into();
+        self.send_message(&mut call)?;
+        let serial = call.serial();
+        loop {
+            match self.read_message()? {
+                Message::MethodReturn { reply_serial, .. } if reply_serial == serial => break,
+                Message::Error {
+                    reply_serial,
+                    error_name,
+                    body,
+                    ..
+                } if reply_serial == serial => bail!("BecomeMonitor failed: {error_name} {body:?}"),
+                _ => {}
+            }
         }
+        self.monitor = true;
+        Ok(())
     }
+
+    pub fn is_monitor(&self) -> bool {
+        self.monitor
+    }
+
+    fn check_connected(&self) -> Result<()> {
+        match &self.disconnected {
+            Some(disconnected) => Err(disconnected.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns hangups and socket errors into `Disconnected`, `None` means
+    /// the call was interrupted and should be retried.
+    fn check_io(&mut self, result: std::io::Result<usize>) -> Result<Option<usize>> {
+        let disconnected = match result {
+            Ok(0) => Disconnected::eof(),
+            Ok(len) => return Ok(Some(len)),
+            Err(err) if err.kind() == ErrorKind::Interrupted => return Ok(None),
+            Err(err) => Disconnected::from(&err),
+        };
+        let disconnected = disconnected
+            .with_unsent(self.writer.queued())
+            .with_pending(&self.pending);
+        self.disconnected = Some(disconnected.clone());
+        Err(disconnected.into())
+    }
+}
+
+#[test]
+fn test_disconnected() {
+    use crate::{DisconnectReason, encoders::MessageEncoder, messages::Hello};
+    use std::os::fd::IntoRawFd;
+
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    let mut conn = BlockingConnection::from_fd(ours.into_raw_fd());
+    conn.send_message(&mut Hello.into()).unwrap();
+    conn.send_message(&mut Hello.into()).unwrap();
+    let reply = Message::MethodReturn {
+        serial: 1,
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    theirs
+        .write_all(&MessageEncoder::encode(&reply).unwrap())
+        .unwrap();
+    // Closing with the calls unread would reset the connection instead
+    theirs.shutdown(std::net::Shutdown::Write).unwrap();
+
+    assert_eq!(conn.read_message().unwrap(), reply);
+    for _ in 0..2 {
+        let err = conn.read_message().unwrap_err();
+        let disconnected = err.downcast_ref::<Disconnected>().unwrap();
+        assert_eq!(disconnected.reason, DisconnectReason::Eof);
+        assert_eq!(disconnected.pending, vec![2]);
+    }
+
+    let (ours, theirs) = UnixStream::pair().unwrap();
+    let mut conn = BlockingConnection::from_fd(ours.into_raw_fd());
+    drop(theirs);
+
+    let err = conn.send_message(&mut Hello.into()).unwrap_err();
+    let disconnected = err.downcast_ref::<Disconnected>().unwrap();
+    let DisconnectReason::Os(errno) = disconnected.reason else {
+        panic!("expected EPIPE, got {disconnected:?}");
+    };
+    assert_eq!(
+        std::io::Error::from_raw_os_error(errno).kind(),
+        ErrorKind::BrokenPipe
+    );
+    assert_eq!(disconnected.unsent, vec![1]);
+    assert!(disconnected.pending.is_empty());
+}
+
+#[test]
+fn test_become_monitor() {
+    use crate::{encoders::MessageEncoder, messages::Hello, types::Value};
+    use std::{borrow::Cow, os::fd::IntoRawFd};
+
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    let mut conn = BlockingConnection::from_fd(ours.into_raw_fd());
+    let unrelated = Message::Signal {
+        serial: 1,
+        path: Cow::Borrowed("/"),
+        interface: Cow::Borrowed("a.b"),
+        member: Cow::Borrowed("C"),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    let reply = Message::MethodReturn {
+        serial: 2,
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    for message in [&unrelated, &reply, &unrelated] {
+        theirs
+            .write_all(&MessageEncoder::encode(message).unwrap())
+            .unwrap();
+    }
+
+    conn.become_monitor(&[MatchRule::new().all_types().sender(":1.5")])
+        .unwrap();
+    assert!(conn.is_monitor());
+    assert_eq!(conn.read_message().unwrap(), unrelated);
+    assert!(conn.send_message(&mut Hello.into()).is_err());
+
+    let mut reader = ReaderFSM::new();
+    let call = loop {
+        if let Some(message) = reader.next_message().unwrap() {
+            break message;
+        }
+        let len = theirs.read(reader.wants()).unwrap();
+        reader.satisfy(len);
+    };
+    assert_eq!(call.member(), Some("BecomeMonitor"));
+    assert_eq!(
+        call.body(),
+        [
+            Value::Array(
+                crate::types::CompleteType::String,
+                vec![Value::String("sender=':1.5'".into())]
+            ),
+            Value::UInt32(0)
+        ]
+    );
 }
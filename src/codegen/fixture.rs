@@ -0,0 +1,373 @@
+pub mod org_me_calc {
+    #![allow(dead_code, unused_imports, clippy::all)]
+    use crate::{CompleteType, Message, Value, body_is, interface_is, member_is, message_is};
+    use std::borrow::Cow;
+
+    pub const INTERFACE: &str = "org.me.Calc";
+
+    #[derive(Debug)]
+    pub struct PlusRequest<'a> {
+        pub serial: u32,
+        pub sender: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub x: i32,
+        pub y: i32,
+    }
+
+    impl<'a> TryFrom<&'a Message> for PlusRequest<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodCall { serial, path, member, interface: Some(interface), sender: Some(sender), body, .. });
+            member_is!(member, "Plus");
+            interface_is!(interface, INTERFACE);
+            body_is!(body, [Value::Int32(arg0), Value::Int32(arg1)]);
+
+            Ok(Self {
+                serial: *serial,
+                sender: Cow::Borrowed(sender.as_ref()),
+                path: Cow::Borrowed(path.as_ref()),
+                x: *arg0,
+                y: *arg1,
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct PlusResponse<'a> {
+        pub req: PlusRequest<'a>,
+        pub sum: i32,
+    }
+
+    impl<'a> PlusResponse<'a> {
+        pub fn new(req: PlusRequest<'a>, sum: i32) -> Self {
+            Self { req, sum }
+        }
+    }
+
+    impl<'a> From<PlusResponse<'a>> for Message {
+        fn from(value: PlusResponse<'a>) -> Message {
+            Message::MethodReturn {
+                serial: 0,
+                reply_serial: value.req.serial,
+                destination: Some(Cow::Owned(value.req.sender.into_owned())),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::Int32(value.sum)],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct PlusCall<'a> {
+        pub destination: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub x: i32,
+        pub y: i32,
+    }
+
+    impl<'a> PlusCall<'a> {
+        pub fn new(destination: impl Into<Cow<'a, str>>, path: impl Into<Cow<'a, str>>, x: i32, y: i32) -> Self {
+            Self { destination: destination.into(), path: path.into(), x, y }
+        }
+    }
+
+    impl<'a> From<PlusCall<'a>> for Message {
+        fn from(value: PlusCall<'a>) -> Message {
+            Message::MethodCall {
+                serial: 0,
+                path: Cow::Owned(value.path.into_owned()),
+                member: Cow::Borrowed("Plus"),
+                interface: Some(Cow::Borrowed(INTERFACE)),
+                destination: Some(Cow::Owned(value.destination.into_owned())),
+                sender: None,
+                unix_fds: None,
+                no_reply_expected: false,
+                body: vec![Value::Int32(value.x), Value::Int32(value.y)],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct PlusReply<'a> {
+        pub reply_serial: u32,
+        pub sender: Option<Cow<'a, str>>,
+        pub sum: i32,
+    }
+
+    impl<'a> TryFrom<&'a Message> for PlusReply<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodReturn { reply_serial, sender, body, .. });
+            body_is!(body, [Value::Int32(arg0)]);
+
+            Ok(Self {
+                reply_serial: *reply_serial,
+                sender: sender.as_deref().map(Cow::Borrowed),
+                sum: *arg0,
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct DescribeRequest<'a> {
+        pub serial: u32,
+        pub sender: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub path_arg: Cow<'a, str>,
+    }
+
+    impl<'a> TryFrom<&'a Message> for DescribeRequest<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodCall { serial, path, member, interface: Some(interface), sender: Some(sender), body, .. });
+            member_is!(member, "Describe");
+            interface_is!(interface, INTERFACE);
+            body_is!(body, [Value::ObjectPath(arg0)]);
+
+            Ok(Self {
+                serial: *serial,
+                sender: Cow::Borrowed(sender.as_ref()),
+                path: Cow::Borrowed(path.as_ref()),
+                path_arg: Cow::Borrowed(arg0.as_ref()),
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct DescribeResponse<'a> {
+        pub req: DescribeRequest<'a>,
+        pub name: Cow<'a, str>,
+        pub props: Cow<'a, Value>,
+    }
+
+    impl<'a> DescribeResponse<'a> {
+        pub fn new(req: DescribeRequest<'a>, name: Cow<'a, str>, props: Cow<'a, Value>) -> Self {
+            Self { req, name, props }
+        }
+    }
+
+    impl<'a> From<DescribeResponse<'a>> for Message {
+        fn from(value: DescribeResponse<'a>) -> Message {
+            Message::MethodReturn {
+                serial: 0,
+                reply_serial: value.req.serial,
+                destination: Some(Cow::Owned(value.req.sender.into_owned())),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::String(value.name.into_owned()), value.props.into_owned()],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct DescribeCall<'a> {
+        pub destination: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub path_arg: Cow<'a, str>,
+    }
+
+    impl<'a> DescribeCall<'a> {
+        pub fn new(destination: impl Into<Cow<'a, str>>, path: impl Into<Cow<'a, str>>, path_arg: Cow<'a, str>) -> Self {
+            Self { destination: destination.into(), path: path.into(), path_arg }
+        }
+    }
+
+    impl<'a> From<DescribeCall<'a>> for Message {
+        fn from(value: DescribeCall<'a>) -> Message {
+            Message::MethodCall {
+                serial: 0,
+                path: Cow::Owned(value.path.into_owned()),
+                member: Cow::Borrowed("Describe"),
+                interface: Some(Cow::Borrowed(INTERFACE)),
+                destination: Some(Cow::Owned(value.destination.into_owned())),
+                sender: None,
+                unix_fds: None,
+                no_reply_expected: false,
+                body: vec![Value::ObjectPath(Cow::Owned(value.path_arg.into_owned()))],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct DescribeReply<'a> {
+        pub reply_serial: u32,
+        pub sender: Option<Cow<'a, str>>,
+        pub name: Cow<'a, str>,
+        pub props: Cow<'a, Value>,
+    }
+
+    impl<'a> TryFrom<&'a Message> for DescribeReply<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodReturn { reply_serial, sender, body, .. });
+            body_is!(body, [Value::String(arg0), arg1]);
+            anyhow::ensure!(arg1.try_complete_type()? == "a{sv}".parse::<CompleteType>()?, "expected {} to be {}, got {:?}", "props", "a{sv}", arg1);
+
+            Ok(Self {
+                reply_serial: *reply_serial,
+                sender: sender.as_deref().map(Cow::Borrowed),
+                name: Cow::Borrowed(arg0.as_str()),
+                props: Cow::Borrowed(arg1),
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct SelfRequest<'a> {
+        pub serial: u32,
+        pub sender: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub self_: Cow<'a, str>,
+        pub super_: Cow<'a, str>,
+    }
+
+    impl<'a> TryFrom<&'a Message> for SelfRequest<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodCall { serial, path, member, interface: Some(interface), sender: Some(sender), body, .. });
+            member_is!(member, "Self");
+            interface_is!(interface, INTERFACE);
+            body_is!(body, [Value::String(arg0), Value::String(arg1)]);
+
+            Ok(Self {
+                serial: *serial,
+                sender: Cow::Borrowed(sender.as_ref()),
+                path: Cow::Borrowed(path.as_ref()),
+                self_: Cow::Borrowed(arg0.as_str()),
+                super_: Cow::Borrowed(arg1.as_str()),
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct SelfResponse<'a> {
+        pub req: SelfRequest<'a>,
+        pub crate_: Cow<'a, str>,
+    }
+
+    impl<'a> SelfResponse<'a> {
+        pub fn new(req: SelfRequest<'a>, crate_: Cow<'a, str>) -> Self {
+            Self { req, crate_ }
+        }
+    }
+
+    impl<'a> From<SelfResponse<'a>> for Message {
+        fn from(value: SelfResponse<'a>) -> Message {
+            Message::MethodReturn {
+                serial: 0,
+                reply_serial: value.req.serial,
+                destination: Some(Cow::Owned(value.req.sender.into_owned())),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::String(value.crate_.into_owned())],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct SelfCall<'a> {
+        pub destination: Cow<'a, str>,
+        pub path: Cow<'a, str>,
+        pub self_: Cow<'a, str>,
+        pub super_: Cow<'a, str>,
+    }
+
+    impl<'a> SelfCall<'a> {
+        pub fn new(destination: impl Into<Cow<'a, str>>, path: impl Into<Cow<'a, str>>, self_: Cow<'a, str>, super_: Cow<'a, str>) -> Self {
+            Self { destination: destination.into(), path: path.into(), self_, super_ }
+        }
+    }
+
+    impl<'a> From<SelfCall<'a>> for Message {
+        fn from(value: SelfCall<'a>) -> Message {
+            Message::MethodCall {
+                serial: 0,
+                path: Cow::Owned(value.path.into_owned()),
+                member: Cow::Borrowed("Self"),
+                interface: Some(Cow::Borrowed(INTERFACE)),
+                destination: Some(Cow::Owned(value.destination.into_owned())),
+                sender: None,
+                unix_fds: None,
+                no_reply_expected: false,
+                body: vec![Value::String(value.self_.into_owned()), Value::String(value.super_.into_owned())],
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct SelfReply<'a> {
+        pub reply_serial: u32,
+        pub sender: Option<Cow<'a, str>>,
+        pub crate_: Cow<'a, str>,
+    }
+
+    impl<'a> TryFrom<&'a Message> for SelfReply<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::MethodReturn { reply_serial, sender, body, .. });
+            body_is!(body, [Value::String(arg0)]);
+
+            Ok(Self {
+                reply_serial: *reply_serial,
+                sender: sender.as_deref().map(Cow::Borrowed),
+                crate_: Cow::Borrowed(arg0.as_str()),
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct ChangedSignal<'a> {
+        pub path: Cow<'a, str>,
+        pub sender: Option<Cow<'a, str>>,
+        pub destination: Option<Cow<'a, str>>,
+        pub changes: Cow<'a, Value>,
+        pub arg1: bool,
+    }
+
+    impl<'a> ChangedSignal<'a> {
+        pub fn new(path: impl Into<Cow<'a, str>>, changes: Cow<'a, Value>, arg1: bool) -> Self {
+            Self { path: path.into(), sender: None, destination: None, changes, arg1 }
+        }
+    }
+
+    impl<'a> TryFrom<&'a Message> for ChangedSignal<'a> {
+        type Error = anyhow::Error;
+
+        fn try_from(message: &'a Message) -> anyhow::Result<Self> {
+            message_is!(message, Message::Signal { path, interface, member, sender, destination, body, .. });
+            member_is!(member, "Changed");
+            interface_is!(interface, INTERFACE);
+            body_is!(body, [arg0, Value::Bool(arg1)]);
+            anyhow::ensure!(arg0.try_complete_type()? == "a{sv}".parse::<CompleteType>()?, "expected {} to be {}, got {:?}", "changes", "a{sv}", arg0);
+
+            Ok(Self {
+                path: Cow::Borrowed(path.as_ref()),
+                sender: sender.as_deref().map(Cow::Borrowed),
+                destination: destination.as_deref().map(Cow::Borrowed),
+                changes: Cow::Borrowed(arg0),
+                arg1: *arg1,
+            })
+        }
+    }
+
+    impl<'a> From<ChangedSignal<'a>> for Message {
+        fn from(value: ChangedSignal<'a>) -> Message {
+            Message::Signal {
+                serial: 0,
+                path: Cow::Owned(value.path.into_owned()),
+                interface: Cow::Borrowed(INTERFACE),
+                member: Cow::Borrowed("Changed"),
+                destination: value.destination.map(|d| Cow::Owned(d.into_owned())),
+                sender: None,
+                unix_fds: None,
+                body: vec![value.changes.into_owned(), Value::Bool(value.arg1)],
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod",
+    "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+    "try", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Keywords that can't be raw identifiers either.
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+fn words(name: &str) -> Vec<String> {
+    let mut out = vec![];
+    let mut current = String::new();
+    let mut prev_is_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                out.push(std::mem::take(&mut current));
+            }
+            prev_is_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_is_lower && !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        }
+        prev_is_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+fn escape(ident: String) -> String {
+    if ident.is_empty() {
+        String::from("_")
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{ident}")
+    } else if PATH_KEYWORDS.contains(&ident.as_str()) {
+        format!("{ident}_")
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{ident}")
+    } else {
+        ident
+    }
+}
+
+pub(crate) fn snake_case(name: &str) -> String {
+    let words: Vec<String> = words(name).iter().map(|w| w.to_lowercase()).collect();
+    escape(words.join("_"))
+}
+
+/// `name` in PascalCase followed by `suffix`, escaped as a whole so that
+/// `Self` only needs it without a suffix.
+pub(crate) fn pascal_case(name: &str, suffix: &str) -> String {
+    let words: Vec<String> = words(name)
+        .iter()
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    escape(words.concat() + suffix)
+}
+
+#[test]
+fn test_names() {
+    assert_eq!(snake_case("GetManagedObjects"), "get_managed_objects");
+    assert_eq!(snake_case("org.freedesktop.DBus"), "org_freedesktop_dbus");
+    assert_eq!(snake_case("type"), "r#type");
+    assert_eq!(snake_case("2d"), "_2d");
+    assert_eq!(snake_case("self"), "self_");
+    assert_eq!(snake_case("Crate"), "crate_");
+    assert_eq!(snake_case("super"), "super_");
+    assert_eq!(pascal_case("Plus", ""), "Plus");
+    assert_eq!(pascal_case("self", ""), "Self_");
+    assert_eq!(pascal_case("self", "Request"), "SelfRequest");
+    assert_eq!(pascal_case("2d", "Call"), "_2dCall");
+    assert_eq!(
+        pascal_case("name_owner_changed", "Signal"),
+        "NameOwnerChangedSignal"
+    );
+}
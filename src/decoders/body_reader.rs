@@ -0,0 +1,179 @@
+use crate::{
+    decoders::{DecodingBuffer, ValueDecoder},
+    types::{CompleteType, ValueRef},
+};
+use anyhow::{Context as _, Result, bail};
+
+/// A cursor over a message body, or over the items of a container inside it,
+/// that follows the signature and decodes one value at a time.
+///
+/// `enter` steps into a container and returns a cursor over its items, the
+/// outer cursor moves past it without decoding its contents.
+#[derive(Debug, Clone)]
+pub struct BodyReader<'buf> {
+    buf: DecodingBuffer<'buf>,
+    items: Items,
+}
+
+#[derive(Debug, Clone)]
+enum Items {
+    /// Body arguments, struct fields or the key and value of a dict entry
+    Fields {
+        types: Vec<CompleteType>,
+        idx: usize,
+    },
+    /// Array items up to the end position of the array
+    Array { item_type: CompleteType, end: usize },
+}
+
+impl<'buf> BodyReader<'buf> {
+    pub(crate) fn new(bytes: &'buf [u8], types: Vec<CompleteType>) -> Self {
+        Self {
+            buf: DecodingBuffer::new(bytes),
+            items: Items::Fields { types, idx: 0 },
+        }
+    }
+
+    /// Type of the next item, `None` once all items have been read.
+    pub fn peek_type(&self) -> Option<&CompleteType> {
+        match &self.items {
+            Items::Fields { types, idx } => types.get(*idx),
+            Items::Array { item_type, end } => (self.buf.pos() < *end).then_some(item_type),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.peek_type().is_none()
+    }
+
+    fn advance(&mut self) -> Result<CompleteType> {
+        let complete_type = self.peek_type().cloned().context("no more items")?;
+        if let Items::Fields { idx, .. } = &mut self.items {
+            *idx += 1;
+        }
+        Ok(complete_type)
+    }
+
+    /// Decodes the next item, `None` once all items have been read.
+    pub fn next_value(&mut self) -> Result<Option<ValueRef<'buf>>> {
+        if self.is_done() {
+            return Ok(None);
+        }
+        let complete_type = self.advance()?;
+        ValueDecoder::decode_value_ref(&mut self.buf, &complete_type).map(Some)
+    }
+
+    /// Moves past the next item without decoding it.
+    pub fn skip_item(&mut self) -> Result<()> {
+        let complete_type = self.advance()?;
+        ValueDecoder::skip(&mut self.buf, &complete_type)
+    }
+
+    /// Reads the next item, which must be a string or an object path.
+    pub fn read_str(&mut self) -> Result<&'buf str> {
+        match self.advance()? {
+            CompleteType::String => ValueDecoder::decode_string(&mut self.buf),
+            CompleteType::ObjectPath => ValueDecoder::decode_object_path(&mut self.buf),
+            other => bail!("expected a string, got {other}"),
+        }
+    }
+
+    /// Returns a cursor over the items of the next item, which must be an
+    /// array, struct, dict entry or variant.
+    pub fn enter(&mut self) -> Result<BodyReader<'buf>> {
+        let complete_type = self.advance()?;
+        let mut buf = self.buf.clone();
+        let items = match &complete_type {
+            CompleteType::Array(item_type) => {
+                let byte_len = ValueDecoder::decode_u32(&mut buf)? as usize;
+                buf.align(item_type.alignment())?;
+                Items::Array {
+                    item_type: (**item_type).clone(),
+                    end: buf.pos() + byte_len,
+                }
+            }
+            CompleteType::Struct(field_types) => {
+                buf.align(8)?;
+                Items::Fields {
+                    types: field_types.clone(),
+                    idx: 0,
+                }
+            }
+            CompleteType::DictEntry(key_type, value_type) => {
+                buf.align(8)?;
+                Items::Fields {
+                    types: vec![(**key_type).clone(), (**value_type).clone()],
+                    idx: 0,
+                }
+            }
+            CompleteType::Variant => Items::Fields {
+                types: vec![ValueDecoder::decode_complete_type(&mut buf)?],
+                idx: 0,
+            },
+            other => bail!("cannot enter {other}"),
+        };
+        ValueDecoder::skip(&mut self.buf, &complete_type)?;
+        Ok(BodyReader { buf, items })
+    }
+}
+
+impl<'buf> Iterator for BodyReader<'buf> {
+    type Item = Result<ValueRef<'buf>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_value().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BodyReader;
+    use crate::{
+        encoders::{EncodingBuffer, ValueEncoder},
+        types::{CompleteType, Value, ValueRef, Variant},
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_body_reader() {
+        let body = [
+            Value::String(String::from("org.me.Player")),
+            Value::from(HashMap::from([(
+                String::from("Volume"),
+                Variant(Value::Double(0.5)),
+            )])),
+            Value::from(vec![String::from("a"), String::from("b")]),
+        ];
+        let mut buf = EncodingBuffer::new();
+        for value in &body {
+            ValueEncoder::encode_value(&mut buf, value);
+        }
+        let bytes = buf.done();
+        let types = body.iter().map(Value::complete_type).collect();
+
+        let mut reader = BodyReader::new(&bytes, types);
+        assert_eq!(reader.read_str().unwrap(), "org.me.Player");
+
+        let mut dict = reader.enter().unwrap();
+        let mut entry = dict.enter().unwrap();
+        assert_eq!(entry.read_str().unwrap(), "Volume");
+        let mut variant = entry.enter().unwrap();
+        assert_eq!(variant.next_value().unwrap(), Some(ValueRef::Double(0.5)));
+        assert!(entry.is_done());
+        assert!(dict.is_done());
+
+        assert_eq!(
+            reader.peek_type(),
+            Some(&CompleteType::Array(Box::new(CompleteType::String)))
+        );
+        let items: Vec<_> = reader.enter().unwrap().map(Result::unwrap).collect();
+        assert_eq!(items, vec![ValueRef::String("a"), ValueRef::String("b")]);
+        assert!(reader.next_value().unwrap().is_none());
+        assert!(reader.skip_item().is_err());
+
+        let mut reader = BodyReader::new(&bytes, body.iter().map(Value::complete_type).collect());
+        reader.skip_item().unwrap();
+        reader.skip_item().unwrap();
+        assert_eq!(reader.count(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 
+#[derive(Clone)]
 pub(crate) struct DecodingBuffer<'a> {
     buf: &'a [u8],
     pos: usize,
@@ -102,7 +103,7 @@ impl<'a> DecodingBuffer<'a> {
         ]))
     }
 
-    pub(crate) fn next_n(&mut self, count: usize) -> Result<&[u8]> {
+    pub(crate) fn next_n(&mut self, count: usize) -> Result<&'a [u8]> {
         let bytes = self.buf.get(self.pos..self.pos + count).context("EOF")?;
         self.pos += count;
         Ok(bytes)
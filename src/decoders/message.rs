@@ -1,14 +1,21 @@
 use crate::{
-    decoders::{DecodingBuffer, HeaderDecoder, ValueDecoder, signature::SignatureDecoder},
-    types::{CompleteType, Header, HeaderFieldName, Message, MessageType, Value},
+    decoders::{DecodingBuffer, HeaderDecoder, ValueDecoder},
+    types::{
+        BodyRef, CompleteType, Flags, Header, HeaderFieldName, Message, MessageRef, MessageType,
+        ValueRef,
+    },
 };
 use anyhow::{Context, Result, bail};
-use std::borrow::Cow;
 
 pub(crate) struct MessageDecoder;
 
 impl MessageDecoder {
+    #[allow(dead_code)]
     pub(crate) fn decode(bytes: &[u8]) -> Result<Message> {
+        Self::decode_ref(bytes)?.to_message()
+    }
+
+    pub(crate) fn decode_ref(bytes: &[u8]) -> Result<MessageRef<'_>> {
         let mut buf = DecodingBuffer::new(bytes);
         let header = HeaderDecoder::decode(&mut buf)?;
 
@@ -19,7 +26,7 @@ impl MessageDecoder {
         let mut reply_serial = None;
         let mut destination = None;
         let mut sender = None;
-        let mut signature = None;
+        let mut signature: &[u8] = &[];
         let mut unix_fds = None;
 
         let len = buf.next_u32()?;
@@ -29,10 +36,9 @@ impl MessageDecoder {
 
         while buf.pos() < end {
             buf.align(8)?;
-            let header_field =
-                ValueDecoder::decode_value_by_complete_type(&mut buf, &header_field_type)?;
+            let header_field = ValueDecoder::decode_value_ref(&mut buf, &header_field_type)?;
 
-            let Value::Struct(pair) = header_field else {
+            let ValueRef::Struct(pair) = header_field else {
                 bail!("got {header_field:?} instead of a header field struct");
             };
 
@@ -40,42 +46,41 @@ impl MessageDecoder {
                 anyhow::anyhow!("expected two elements, got {not_a_pair:?}")
             })?;
 
-            let Value::Byte(header_field_name) = header_field_name else {
+            let ValueRef::Byte(header_field_name) = header_field_name else {
                 bail!("got {header_field_name:?} instead of a header field name");
             };
             let header_field_name = HeaderFieldName::from(header_field_name);
 
-            let Value::Variant(value) = value else {
+            let ValueRef::Variant(value) = value else {
                 bail!("got {value:?} instead of Variant in a header field");
             };
 
             match (header_field_name, *value) {
-                (HeaderFieldName::Path, Value::ObjectPath(value)) => {
+                (HeaderFieldName::Path, ValueRef::ObjectPath(value)) => {
                     path = Some(value);
                 }
-                (HeaderFieldName::Interface, Value::String(value)) => {
-                    interface = Some(Cow::Owned(value));
+                (HeaderFieldName::Interface, ValueRef::String(value)) => {
+                    interface = Some(value);
                 }
-                (HeaderFieldName::Member, Value::String(value)) => {
-                    member = Some(Cow::Owned(value));
+                (HeaderFieldName::Member, ValueRef::String(value)) => {
+                    member = Some(value);
                 }
-                (HeaderFieldName::ErrorName, Value::String(value)) => {
+                (HeaderFieldName::ErrorName, ValueRef::String(value)) => {
                     error_name = Some(value);
                 }
-                (HeaderFieldName::ReplySerial, Value::UInt32(value)) => {
+                (HeaderFieldName::ReplySerial, ValueRef::UInt32(value)) => {
                     reply_serial = Some(value);
                 }
-                (HeaderFieldName::Destination, Value::String(value)) => {
-                    destination = Some(Cow::Owned(value));
+                (HeaderFieldName::Destination, ValueRef::String(value)) => {
+                    destination = Some(value);
                 }
-                (HeaderFieldName::Sender, Value::String(value)) => {
-                    sender = Some(Cow::Owned(value));
+                (HeaderFieldName::Sender, ValueRef::String(value)) => {
+                    sender = Some(value);
                 }
-                (HeaderFieldName::Signature, Value::Signature(value)) => {
-                    let mut buf = DecodingBuffer::new(&value);
-                    signature = Some(SignatureDecoder::decode_signature(&mut buf)?);
+                (HeaderFieldName::Signature, ValueRef::Signature(value)) => {
+                    signature = value;
                 }
-                (HeaderFieldName::UnixFds, Value::UInt32(value)) => {
+                (HeaderFieldName::UnixFds, ValueRef::UInt32(value)) => {
                     unix_fds = Some(value);
                 }
                 (header_field_name, value) => {
@@ -86,12 +91,16 @@ impl MessageDecoder {
             }
         }
 
-        let mut body = vec![];
-        if let Some(signature) = signature.as_ref()
-            && !signature.items.is_empty()
-        {
+        let mut body = BodyRef {
+            signature,
+            bytes: &[],
+        };
+        if !signature.is_empty() {
             buf.align(8)?;
-            body = ValueDecoder::decode_values_by_signature(&mut buf, signature)?;
+            let start = buf.pos();
+            body.bytes = bytes
+                .get(start..start + header.body_len)
+                .context("EOF in message body")?;
         }
 
         build_message(
@@ -110,18 +119,18 @@ impl MessageDecoder {
 }
 
 #[expect(clippy::too_many_arguments)]
-fn build_message(
+fn build_message<'buf>(
     header: Header,
-    path: Option<Cow<'static, str>>,
-    interface: Option<Cow<'static, str>>,
-    member: Option<Cow<'static, str>>,
-    error_name: Option<String>,
+    path: Option<&'buf str>,
+    interface: Option<&'buf str>,
+    member: Option<&'buf str>,
+    error_name: Option<&'buf str>,
     reply_serial: Option<u32>,
-    destination: Option<Cow<'static, str>>,
-    sender: Option<Cow<'static, str>>,
+    destination: Option<&'buf str>,
+    sender: Option<&'buf str>,
     unix_fds: Option<u32>,
-    body: Vec<Value>,
-) -> Result<Message> {
+    body: BodyRef<'buf>,
+) -> Result<MessageRef<'buf>> {
     match header.message_type {
         MessageType::MethodCall => {
             let path = path.context("MethodCall missing path")?;
@@ -134,7 +143,7 @@ fn build_message(
                 bail!("MethodCall should not have reply_serial");
             }
 
-            Ok(Message::MethodCall {
+            Ok(MessageRef::MethodCall {
                 serial: header.serial,
                 path,
                 member,
@@ -142,6 +151,7 @@ fn build_message(
                 destination,
                 sender,
                 unix_fds,
+                no_reply_expected: header.flags.byte & Flags::NO_REPLY_EXPECTED != 0,
                 body,
             })
         }
@@ -161,7 +171,7 @@ fn build_message(
                 bail!("MethodReturn should not have error_name");
             }
 
-            Ok(Message::MethodReturn {
+            Ok(MessageRef::MethodReturn {
                 serial: header.serial,
                 reply_serial,
                 destination,
@@ -184,7 +194,7 @@ fn build_message(
                 bail!("Error should not have interface");
             }
 
-            Ok(Message::Error {
+            Ok(MessageRef::Error {
                 serial: header.serial,
                 error_name,
                 reply_serial,
@@ -206,7 +216,7 @@ fn build_message(
                 bail!("Signal should not have reply_serial");
             }
 
-            Ok(Message::Signal {
+            Ok(MessageRef::Signal {
                 serial: header.serial,
                 path,
                 interface,
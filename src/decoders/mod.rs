@@ -12,3 +12,6 @@ pub(crate) use signature::SignatureDecoder;
 
 mod buffer;
 pub(crate) use buffer::DecodingBuffer;
+
+mod body_reader;
+pub use body_reader::BodyReader;
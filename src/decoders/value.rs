@@ -1,87 +1,86 @@
-use std::borrow::Cow;
-
 use crate::{
     decoders::{DecodingBuffer, SignatureDecoder},
-    types::{CompleteType, Signature, Value},
+    types::{CompleteType, ValueRef},
 };
 use anyhow::{Context, Result};
 
 pub(crate) struct ValueDecoder;
 
 impl ValueDecoder {
-    fn decode_u8(buffer: &mut DecodingBuffer) -> Result<u8> {
+    pub(crate) fn decode_u8(buffer: &mut DecodingBuffer) -> Result<u8> {
         buffer.next_u8()
     }
 
-    fn decode_bool(buf: &mut DecodingBuffer) -> Result<bool> {
+    pub(crate) fn decode_bool(buf: &mut DecodingBuffer) -> Result<bool> {
         Self::decode_u32(buf).map(|v| v != 0)
     }
 
-    fn decode_i16(buf: &mut DecodingBuffer) -> Result<i16> {
+    pub(crate) fn decode_i16(buf: &mut DecodingBuffer) -> Result<i16> {
         buf.align(2)?;
         buf.next_i16()
     }
 
-    fn decode_u16(buf: &mut DecodingBuffer) -> Result<u16> {
+    pub(crate) fn decode_u16(buf: &mut DecodingBuffer) -> Result<u16> {
         buf.align(2)?;
         buf.next_u16()
     }
 
-    fn decode_i32(buf: &mut DecodingBuffer) -> Result<i32> {
+    pub(crate) fn decode_i32(buf: &mut DecodingBuffer) -> Result<i32> {
         buf.align(4)?;
         buf.next_i32()
     }
 
-    fn decode_u32(buf: &mut DecodingBuffer) -> Result<u32> {
+    pub(crate) fn decode_u32(buf: &mut DecodingBuffer) -> Result<u32> {
         buf.align(4)?;
         buf.next_u32()
     }
 
-    fn decode_i64(buf: &mut DecodingBuffer) -> Result<i64> {
+    pub(crate) fn decode_i64(buf: &mut DecodingBuffer) -> Result<i64> {
         buf.align(8)?;
         buf.next_i64()
     }
 
-    fn decode_u64(buf: &mut DecodingBuffer) -> Result<u64> {
+    pub(crate) fn decode_u64(buf: &mut DecodingBuffer) -> Result<u64> {
         buf.align(8)?;
         buf.next_u64()
     }
 
-    fn decode_f64(buf: &mut DecodingBuffer) -> Result<f64> {
+    pub(crate) fn decode_f64(buf: &mut DecodingBuffer) -> Result<f64> {
         buf.align(8)?;
         buf.next_f64()
     }
 
-    fn decode_string(buf: &mut DecodingBuffer) -> Result<String> {
+    pub(crate) fn decode_string<'a>(buf: &mut DecodingBuffer<'a>) -> Result<&'a str> {
         let len = Self::decode_u32(buf)? as usize;
-        let s = String::from_utf8_lossy(buf.next_n(len)?).into_owned();
+        let s = std::str::from_utf8(buf.next_n(len)?).context("non-utf8 string")?;
         buf.skip();
         Ok(s)
     }
 
-    fn decode_object_path(buf: &mut DecodingBuffer) -> Result<Cow<'static, str>> {
+    pub(crate) fn decode_object_path<'a>(buf: &mut DecodingBuffer<'a>) -> Result<&'a str> {
         let len = Self::decode_u32(buf)? as usize;
-        let path = buf.next_n(len)?.to_vec();
+        let path = std::str::from_utf8(buf.next_n(len)?).context("non-utf8 path")?;
         buf.skip();
-        Ok(String::from_utf8(path).context("non-utf8 path")?.into())
+        Ok(path)
     }
 
-    fn decode_complete_type(buf: &mut DecodingBuffer) -> Result<CompleteType> {
-        let len = Self::decode_u8(buf)? as usize;
-        let bytes = buf.next_n(len)?.to_vec();
-        buf.skip();
-        let mut buf = DecodingBuffer::new(&bytes);
+    pub(crate) fn decode_complete_type(buf: &mut DecodingBuffer) -> Result<CompleteType> {
+        let bytes = Self::decode_signature(buf)?;
+        let mut buf = DecodingBuffer::new(bytes);
         SignatureDecoder::decode_complete_type(&mut buf)
     }
 
-    fn decode_signature(buf: &mut DecodingBuffer) -> Result<Vec<u8>> {
+    pub(crate) fn decode_signature<'a>(buf: &mut DecodingBuffer<'a>) -> Result<&'a [u8]> {
         let len = Self::decode_u8(buf)? as usize;
-        let s = buf.next_n(len)?.to_vec();
+        let s = buf.next_n(len)?;
         buf.skip();
         Ok(s)
     }
 
-    fn decode_array(buf: &mut DecodingBuffer, item_type: &CompleteType) -> Result<Vec<Value>> {
+    pub(crate) fn decode_array<'a>(
+        buf: &mut DecodingBuffer<'a>,
+        item_type: &CompleteType,
+    ) -> Result<Vec<ValueRef<'a>>> {
         let byte_len = Self::decode_u32(buf)? as usize;
 
         buf.align(item_type.alignment())?;
@@ -91,121 +90,165 @@ impl ValueDecoder {
 
         let mut items = vec![];
         while buf.pos() < end_pos {
-            let item = Self::decode_value_by_complete_type(buf, item_type)?;
+            let item = Self::decode_value_ref(buf, item_type)?;
             items.push(item);
         }
 
         Ok(items)
     }
 
-    fn decode_struct(buf: &mut DecodingBuffer, field_types: &[CompleteType]) -> Result<Vec<Value>> {
+    pub(crate) fn decode_struct<'a>(
+        buf: &mut DecodingBuffer<'a>,
+        field_types: &[CompleteType],
+    ) -> Result<Vec<ValueRef<'a>>> {
         buf.align(8)?;
         let mut fields = vec![];
         for field_type in field_types {
-            let value = Self::decode_value_by_complete_type(buf, field_type)?;
+            let value = Self::decode_value_ref(buf, field_type)?;
             fields.push(value);
         }
         Ok(fields)
     }
 
-    fn decode_dict_entry(
-        buf: &mut DecodingBuffer,
+    pub(crate) fn decode_dict_entry<'a>(
+        buf: &mut DecodingBuffer<'a>,
         key_type: &CompleteType,
         value_type: &CompleteType,
-    ) -> Result<(Value, Value)> {
+    ) -> Result<(ValueRef<'a>, ValueRef<'a>)> {
         buf.align(8)?;
-        let key = Self::decode_value_by_complete_type(buf, key_type)?;
-        let value = Self::decode_value_by_complete_type(buf, value_type)?;
+        let key = Self::decode_value_ref(buf, key_type)?;
+        let value = Self::decode_value_ref(buf, value_type)?;
         Ok((key, value))
     }
 
-    pub(crate) fn decode_value_by_complete_type(
-        buf: &mut DecodingBuffer,
+    pub(crate) fn decode_value_ref<'a>(
+        buf: &mut DecodingBuffer<'a>,
         complete_type: &CompleteType,
-    ) -> Result<Value> {
+    ) -> Result<ValueRef<'a>> {
         match complete_type {
             CompleteType::Byte => {
                 let value = Self::decode_u8(buf)?;
-                Ok(Value::Byte(value))
+                Ok(ValueRef::Byte(value))
             }
             CompleteType::Bool => {
                 let value = Self::decode_bool(buf)?;
-                Ok(Value::Bool(value))
+                Ok(ValueRef::Bool(value))
             }
             CompleteType::Int16 => {
                 let value = Self::decode_i16(buf)?;
-                Ok(Value::Int16(value))
+                Ok(ValueRef::Int16(value))
             }
             CompleteType::UInt16 => {
                 let value = Self::decode_u16(buf)?;
-                Ok(Value::UInt16(value))
+                Ok(ValueRef::UInt16(value))
             }
             CompleteType::Int32 => {
                 let value = Self::decode_i32(buf)?;
-                Ok(Value::Int32(value))
+                Ok(ValueRef::Int32(value))
             }
             CompleteType::UInt32 => {
                 let value = Self::decode_u32(buf)?;
-                Ok(Value::UInt32(value))
+                Ok(ValueRef::UInt32(value))
             }
             CompleteType::Int64 => {
                 let value = Self::decode_i64(buf)?;
-                Ok(Value::Int64(value))
+                Ok(ValueRef::Int64(value))
             }
             CompleteType::UInt64 => {
                 let value = Self::decode_u64(buf)?;
-                Ok(Value::UInt64(value))
+                Ok(ValueRef::UInt64(value))
             }
             CompleteType::Double => {
                 let value = Self::decode_f64(buf)?;
-                Ok(Value::Double(value))
+                Ok(ValueRef::Double(value))
             }
             CompleteType::UnixFD => {
                 let value = Self::decode_u32(buf)?;
-                Ok(Value::UnixFD(value))
+                Ok(ValueRef::UnixFD(value))
             }
             CompleteType::String => {
                 let value = Self::decode_string(buf)?;
-                Ok(Value::String(value))
+                Ok(ValueRef::String(value))
             }
             CompleteType::ObjectPath => {
                 let value = Self::decode_object_path(buf)?;
-                Ok(Value::ObjectPath(value))
+                Ok(ValueRef::ObjectPath(value))
             }
             CompleteType::Signature => {
                 let value = Self::decode_signature(buf)?;
-                Ok(Value::Signature(value))
+                Ok(ValueRef::Signature(value))
             }
             CompleteType::Struct(signatures) => {
                 let fields = Self::decode_struct(buf, signatures)?;
-                Ok(Value::Struct(fields))
+                Ok(ValueRef::Struct(fields))
             }
             CompleteType::Array(item_signature) => {
                 let items = Self::decode_array(buf, item_signature)?;
-                Ok(Value::Array(*item_signature.clone(), items))
+                Ok(ValueRef::Array(*item_signature.clone(), items))
             }
             CompleteType::DictEntry(key_type, value_type) => {
                 let (key, value) = Self::decode_dict_entry(buf, key_type, value_type)?;
-                Ok(Value::DictEntry(Box::new(key), Box::new(value)))
+                Ok(ValueRef::DictEntry(Box::new(key), Box::new(value)))
             }
             CompleteType::Variant => {
                 let complete_type = Self::decode_complete_type(buf)?;
-                let inner = Self::decode_value_by_complete_type(buf, &complete_type)?;
-                Ok(Value::Variant(Box::new(inner)))
+                let inner = Self::decode_value_ref(buf, &complete_type)?;
+                Ok(ValueRef::Variant(Box::new(inner)))
             }
         }
     }
 
-    pub(crate) fn decode_values_by_signature(
-        buf: &mut DecodingBuffer,
-        signature: &Signature,
-    ) -> Result<Vec<Value>> {
-        let mut out = vec![];
-        for complete_type in &signature.items {
-            let value = Self::decode_value_by_complete_type(buf, complete_type)?;
-            out.push(value);
+    /// Moves past a value of `complete_type` without building it.
+    pub(crate) fn skip(buf: &mut DecodingBuffer, complete_type: &CompleteType) -> Result<()> {
+        match complete_type {
+            CompleteType::Byte => {
+                buf.next_n(1)?;
+            }
+            CompleteType::Int16 | CompleteType::UInt16 => {
+                buf.align(2)?;
+                buf.next_n(2)?;
+            }
+            CompleteType::Bool
+            | CompleteType::Int32
+            | CompleteType::UInt32
+            | CompleteType::UnixFD => {
+                buf.align(4)?;
+                buf.next_n(4)?;
+            }
+            CompleteType::Int64 | CompleteType::UInt64 | CompleteType::Double => {
+                buf.align(8)?;
+                buf.next_n(8)?;
+            }
+            CompleteType::String | CompleteType::ObjectPath => {
+                let len = Self::decode_u32(buf)? as usize;
+                buf.next_n(len + 1)?;
+            }
+            CompleteType::Signature => {
+                let len = Self::decode_u8(buf)? as usize;
+                buf.next_n(len + 1)?;
+            }
+            CompleteType::Array(item_type) => {
+                let byte_len = Self::decode_u32(buf)? as usize;
+                buf.align(item_type.alignment())?;
+                buf.next_n(byte_len)?;
+            }
+            CompleteType::Struct(field_types) => {
+                buf.align(8)?;
+                for field_type in field_types {
+                    Self::skip(buf, field_type)?;
+                }
+            }
+            CompleteType::DictEntry(key_type, value_type) => {
+                buf.align(8)?;
+                Self::skip(buf, key_type)?;
+                Self::skip(buf, value_type)?;
+            }
+            CompleteType::Variant => {
+                let complete_type = Self::decode_complete_type(buf)?;
+                Self::skip(buf, &complete_type)?;
+            }
         }
-        Ok(out)
+        Ok(())
     }
 }
 
@@ -222,6 +265,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_read_bool() {
         let mut buf = DecodingBuffer::new(b"\0\0\0\0\x01\x00\x00\x00");
         buf.set_pos(1);
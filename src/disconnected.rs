@@ -0,0 +1,168 @@
+#[cfg(any(
+    feature = "blocking",
+    feature = "poll",
+    feature = "io-uring",
+    feature = "tokio",
+    feature = "futures"
+))]
+use crate::decoders::{DecodingBuffer, HeaderDecoder};
+#[cfg(any(feature = "blocking", feature = "poll", feature = "io-uring"))]
+use crate::types::Message;
+#[cfg(any(feature = "blocking", feature = "poll", feature = "io-uring"))]
+use std::collections::BTreeSet;
+use std::{fmt, io::ErrorKind};
+
+/// The connection to the bus is gone.
+///
+/// Backends return this (wrapped in `anyhow::Error`) once the peer hangs up
+/// or an I/O operation fails, and keep returning it afterwards. Messages
+/// that were queued but not fully written are listed by serial in
+/// `unsent`, method calls that went out but got no reply yet in `pending`.
+/// Neither will ever get a reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disconnected {
+    pub reason: DisconnectReason,
+    pub unsent: Vec<u32>,
+    pub pending: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The peer closed the connection
+    Eof,
+    /// A socket operation failed with this errno
+    Os(i32),
+    /// A socket operation failed without an errno
+    Io(ErrorKind),
+}
+
+#[cfg(any(
+    feature = "blocking",
+    feature = "poll",
+    feature = "io-uring",
+    feature = "tokio",
+    feature = "futures"
+))]
+impl Disconnected {
+    pub(crate) fn new(reason: DisconnectReason) -> Self {
+        Self {
+            reason,
+            unsent: vec![],
+            pending: vec![],
+        }
+    }
+
+    pub(crate) fn eof() -> Self {
+        Self::new(DisconnectReason::Eof)
+    }
+
+    #[cfg(feature = "io-uring")]
+    pub(crate) fn os(errno: i32) -> Self {
+        Self::new(DisconnectReason::Os(errno))
+    }
+
+    /// Records the encoded messages in `queue` as unsent.
+    pub(crate) fn with_unsent<'a>(mut self, queue: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        self.unsent.extend(queue.into_iter().filter_map(|buf| {
+            HeaderDecoder::decode(&mut DecodingBuffer::new(buf))
+                .ok()
+                .map(|header| header.serial)
+        }));
+        self
+    }
+
+    /// Records the calls in `calls` as pending, except the unsent ones, so
+    /// it goes after `with_unsent`.
+    #[cfg(any(feature = "blocking", feature = "poll", feature = "io-uring"))]
+    pub(crate) fn with_pending(mut self, calls: &PendingCalls) -> Self {
+        self.pending = calls
+            .0
+            .iter()
+            .filter(|serial| !self.unsent.contains(serial))
+            .copied()
+            .collect();
+        self
+    }
+}
+
+impl From<&std::io::Error> for Disconnected {
+    fn from(err: &std::io::Error) -> Self {
+        let reason = match err.raw_os_error() {
+            Some(errno) => DisconnectReason::Os(errno),
+            None => DisconnectReason::Io(err.kind()),
+        };
+        Self {
+            reason,
+            unsent: vec![],
+            pending: vec![],
+        }
+    }
+}
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            DisconnectReason::Eof => write!(f, "disconnected: connection closed by peer")?,
+            DisconnectReason::Os(errno) => write!(
+                f,
+                "disconnected: {}",
+                std::io::Error::from_raw_os_error(errno)
+            )?,
+            DisconnectReason::Io(kind) => write!(f, "disconnected: {kind}")?,
+        }
+        if !self.unsent.is_empty() {
+            write!(f, " ({} unsent messages)", self.unsent.len())?;
+        }
+        if !self.pending.is_empty() {
+            write!(f, " ({} calls without a reply)", self.pending.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Disconnected {}
+
+/// Serials of the method calls enqueued on a connection that still expect
+/// a reply.
+#[cfg(any(feature = "blocking", feature = "poll", feature = "io-uring"))]
+#[derive(Debug, Default)]
+pub(crate) struct PendingCalls(BTreeSet<u32>);
+
+#[cfg(any(feature = "blocking", feature = "poll", feature = "io-uring"))]
+impl PendingCalls {
+    pub(crate) fn sent(&mut self, message: &Message) {
+        if let Message::MethodCall {
+            serial,
+            no_reply_expected: false,
+            ..
+        } = message
+        {
+            self.0.insert(*serial);
+        }
+    }
+
+    pub(crate) fn received(&mut self, message: &Message) {
+        if let Some(reply_serial) = message.reply_serial() {
+            self.0.remove(&reply_serial);
+        }
+    }
+}
+
+#[test]
+fn test_from_io_error() {
+    let err = std::io::Error::from(ErrorKind::BrokenPipe);
+    assert_eq!(
+        Disconnected::from(&err).reason,
+        DisconnectReason::Io(ErrorKind::BrokenPipe)
+    );
+    let err = std::io::Error::from_raw_os_error(32);
+    assert_eq!(Disconnected::from(&err).reason, DisconnectReason::Os(32));
+
+    let err = std::io::Error::new(ErrorKind::InvalidData, "bad frame");
+    let disconnected = Disconnected::from(&err);
+    assert_eq!(
+        disconnected.reason,
+        DisconnectReason::Io(ErrorKind::InvalidData)
+    );
+    assert_eq!(disconnected.to_string(), "disconnected: invalid data");
+}
@@ -10,6 +10,12 @@ impl EncodingBuffer {
         Self { buf: vec![] }
     }
 
+    /// Reuses the allocation of `buf`, discarding its contents.
+    pub(crate) fn from_vec(mut buf: Vec<u8>) -> Self {
+        buf.clear();
+        Self { buf }
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.buf.len()
     }
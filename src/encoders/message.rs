@@ -1,19 +1,77 @@
 use crate::{
-    encoders::{EncodingBuffer, HeaderEncoder, SignatureEncoder, ValueEncoder},
-    types::{Flags, HeaderFieldName, Message, Signature, Value},
+    encoders::{EncodingBuffer, HeaderEncoder, ValueEncoder},
+    types::{HeaderFieldName, Message, Signature, Value},
 };
-use anyhow::Result;
+use anyhow::{Result, ensure};
 
 pub struct MessageEncoder;
 
 impl MessageEncoder {
     pub fn encode(message: &Message) -> Result<Vec<u8>> {
         let mut buf = EncodingBuffer::new();
+        Self::encode_into(&mut buf, message)?;
+        Ok(buf.done())
+    }
+
+    /// Encodes `message` with `body` in place of its own body. A struct
+    /// `body_type` is taken as the list of arguments, anything else as a
+    /// single argument.
+    #[cfg(feature = "serde")]
+    pub fn encode_with_body<T>(
+        message: &Message,
+        body: &T,
+        body_type: &crate::CompleteType,
+    ) -> Result<Vec<u8>>
+    where
+        T: ::serde::Serialize + ?Sized,
+    {
+        let signature = Signature {
+            items: match body_type {
+                crate::CompleteType::Struct(fields) => fields.clone(),
+                other => vec![other.clone()],
+            },
+        };
+        let mut buf = EncodingBuffer::new();
+        Self::encode_with(&mut buf, message, &signature, |buf| {
+            crate::serde::serialize_into(buf, body_type, body)?;
+            Ok(())
+        })?;
+        Ok(buf.done())
+    }
+
+    /// Encodes `message` into `buf`, which must be empty since alignment
+    /// is relative to the start of the message.
+    pub(crate) fn encode_into(buf: &mut EncodingBuffer, message: &Message) -> Result<()> {
+        let body = message.body();
+        let signature = Signature {
+            items: body
+                .iter()
+                .map(Value::try_complete_type)
+                .collect::<Result<_>>()?,
+        };
+        Self::encode_with(buf, message, &signature, |buf| {
+            for value in body {
+                ValueEncoder::encode_value(buf, value);
+            }
+            Ok(())
+        })
+    }
+
+    fn encode_with(
+        buf: &mut EncodingBuffer,
+        message: &Message,
+        signature: &Signature,
+        encode_body: impl FnOnce(&mut EncodingBuffer) -> Result<()>,
+    ) -> Result<()> {
+        ensure!(
+            buf.size() == 0,
+            "messages must be encoded into an empty buffer"
+        );
 
         HeaderEncoder::encode(
-            &mut buf,
+            buf,
             message.message_type() as u8,
-            Flags::default().into(),
+            message.flags().into(),
             message.serial(),
         )?;
 
@@ -22,83 +80,44 @@ impl MessageEncoder {
         {
             if let Some(path) = message.path() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::Path,
-                    &Value::ObjectPath(path),
-                );
+                ValueEncoder::encode_header_str(buf, HeaderFieldName::Path, b'o', path);
             }
             if let Some(interface) = message.interface() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::Interface,
-                    &Value::String(interface.to_string()),
-                );
+                ValueEncoder::encode_header_str(buf, HeaderFieldName::Interface, b's', interface);
             }
             if let Some(member) = message.member() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::Member,
-                    &Value::String(member.to_string()),
-                );
+                ValueEncoder::encode_header_str(buf, HeaderFieldName::Member, b's', member);
             }
             if let Some(error_name) = message.error_name() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::ErrorName,
-                    &Value::String(error_name.to_string()),
-                );
+                ValueEncoder::encode_header_str(buf, HeaderFieldName::ErrorName, b's', error_name);
             }
             if let Some(reply_serial) = message.reply_serial() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::ReplySerial,
-                    &Value::UInt32(reply_serial),
-                );
+                ValueEncoder::encode_header_u32(buf, HeaderFieldName::ReplySerial, reply_serial);
             }
             if let Some(destination) = message.destination() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
+                ValueEncoder::encode_header_str(
+                    buf,
                     HeaderFieldName::Destination,
-                    &Value::String(destination.to_string()),
+                    b's',
+                    destination,
                 );
             }
             if let Some(sender) = message.sender() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::Sender,
-                    &Value::String(sender.to_string()),
-                );
+                ValueEncoder::encode_header_str(buf, HeaderFieldName::Sender, b's', sender);
             }
             if let Some(unix_fds) = message.unix_fds() {
                 buf.align(8);
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::UnixFds,
-                    &Value::UInt32(unix_fds),
-                );
+                ValueEncoder::encode_header_u32(buf, HeaderFieldName::UnixFds, unix_fds);
             }
-
-            let body = message.body();
-            if !body.is_empty() {
+            if !signature.items.is_empty() {
                 buf.align(8);
-                let signature = Signature {
-                    items: body.iter().map(|v| v.complete_type()).collect(),
-                };
-                let mut sig_buf = EncodingBuffer::new();
-                SignatureEncoder::encode_signature(&mut sig_buf, &signature);
-                let sig_buf = sig_buf.done();
-                ValueEncoder::encode_header(
-                    &mut buf,
-                    HeaderFieldName::Signature,
-                    &Value::Signature(sig_buf),
-                );
+                ValueEncoder::encode_header_signature(buf, HeaderFieldName::Signature, signature);
             }
         };
         let header_fieldss_end = buf.size();
@@ -107,14 +126,10 @@ impl MessageEncoder {
         buf.align(8);
 
         let body_starts_at = buf.size();
-        for value in message.body() {
-            ValueEncoder::encode_value(&mut buf, value);
-        }
+        encode_body(buf)?;
         let body_len = buf.size() - body_starts_at;
         buf.set_u32(4, body_len as u32)?;
 
-        let buf = buf.done();
-
-        Ok(buf)
+        Ok(())
     }
 }
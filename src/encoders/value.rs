@@ -1,6 +1,6 @@
 use crate::{
     encoders::{EncodingBuffer, SignatureEncoder},
-    types::{CompleteType, HeaderFieldName, Value},
+    types::{CompleteType, HeaderFieldName, Signature, Value},
 };
 
 pub(crate) struct ValueEncoder;
@@ -101,15 +101,48 @@ impl ValueEncoder {
         buf.set_u32(len_pos, byte_len).expect("malformed state");
     }
 
-    pub(crate) fn encode_header(buf: &mut EncodingBuffer, field: HeaderFieldName, value: &Value) {
+    pub(crate) fn encode_variant(buf: &mut EncodingBuffer, inner: &Value) {
+        buf.encode_u8(0);
+        let start = buf.size();
+        SignatureEncoder::encode_complete_type(buf, &inner.complete_type());
+        buf.set_u8(start - 1, (buf.size() - start) as u8)
+            .expect("malformed state");
+        buf.encode_u8(0);
+        Self::encode_value(buf, inner);
+    }
+
+    /// Encodes a header field holding a string-like value of the basic
+    /// type `signature` without building a `Value` for it.
+    pub(crate) fn encode_header_str(
+        buf: &mut EncodingBuffer,
+        field: HeaderFieldName,
+        signature: u8,
+        s: &str,
+    ) {
+        buf.encode_u8(field as u8);
+        Self::encode_signature(buf, &[signature]);
+        Self::encode_str(buf, s);
+    }
+
+    pub(crate) fn encode_header_u32(buf: &mut EncodingBuffer, field: HeaderFieldName, value: u32) {
+        buf.encode_u8(field as u8);
+        Self::encode_signature(buf, b"u");
+        Self::encode_u32(buf, value);
+    }
+
+    pub(crate) fn encode_header_signature(
+        buf: &mut EncodingBuffer,
+        field: HeaderFieldName,
+        signature: &Signature,
+    ) {
         buf.encode_u8(field as u8);
+        Self::encode_signature(buf, b"g");
         buf.encode_u8(0);
         let start = buf.size();
-        SignatureEncoder::encode_complete_type(buf, &value.complete_type());
+        SignatureEncoder::encode_signature(buf, signature);
         buf.set_u8(start - 1, (buf.size() - start) as u8)
             .expect("malformed state");
         buf.encode_u8(0);
-        Self::encode_value(buf, value);
     }
 
     pub(crate) fn encode_value(buf: &mut EncodingBuffer, value: &Value) {
@@ -130,7 +163,7 @@ impl ValueEncoder {
             Value::Struct(fields) => Self::encode_struct(buf, fields),
             Value::Array(item_type, items) => Self::encode_array(buf, item_type, items),
             Value::DictEntry(key, value) => Self::encode_dict_entry(buf, key, value),
-            Value::Variant(_inner) => todo!(),
+            Value::Variant(inner) => Self::encode_variant(buf, inner),
         }
     }
 }
@@ -264,3 +297,10 @@ fn test_encode_signature() {
     ValueEncoder::encode_signature(&mut buf, b"abcd");
     assert_eq!(buf.done(), b"\0\x04abcd\0")
 }
+
+#[test]
+fn test_encode_variant() {
+    let mut buf = EncodingBuffer::new();
+    ValueEncoder::encode_value(&mut buf, &Value::Variant(Box::new(Value::UInt32(7))));
+    assert_eq!(buf.done(), b"\x01u\0\0\x07\0\0\0")
+}
@@ -8,4 +8,4 @@ mod read_buffer;
 pub(crate) use read_buffer::ReadBuffer;
 
 mod writer;
-pub use writer::WriterFSM;
+pub use writer::{MAX_IOVECS, WriterFSM};
@@ -12,18 +12,6 @@ impl ReadBuffer {
         }
     }
 
-    pub(crate) fn resize(&mut self, new_size: usize) {
-        let len = self.buf.len();
-        self.buf.reserve_exact(new_size - len);
-        while self.buf.len() != new_size {
-            self.buf.push(0)
-        }
-    }
-
-    pub(crate) fn filled_part(&self) -> &[u8] {
-        &self.buf[..self.pos]
-    }
-
     pub(crate) fn remaining_part_mut(&mut self) -> &mut [u8] {
         &mut self.buf[self.pos..]
     }
@@ -1,27 +1,36 @@
 use crate::{
-    decoders::{DecodingBuffer, HeaderDecoder, MessageDecoder},
-    fsm::ReadBuffer,
-    types::Message,
+    decoders::{DecodingBuffer, HeaderDecoder},
+    types::{Message, MessageRef},
 };
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, ensure};
 
-#[derive(Debug)]
-pub struct ReaderFSM {
-    state: State,
-    buf: ReadBuffer,
-}
+/// Fixed header plus the length of the header fields array
+const HEADER_LEN: usize = HeaderDecoder::LENGTH + std::mem::size_of::<u32>();
+
+/// Space offered to a single read beyond the bytes still buffered
+const CHUNK_LEN: usize = 8 * 1024;
 
+/// Messages longer than this are rejected by the spec
+const MAX_MESSAGE_LEN: usize = 1 << 27;
+
+/// Reads the stream in chunks and splits them into messages. Bytes past the
+/// last complete message are kept for the next read, and the buffer is
+/// reused for the whole lifetime of the connection.
 #[derive(Debug)]
-enum State {
-    ReadingHeader,
-    ReadingFullMessage,
+pub struct ReaderFSM {
+    buf: Vec<u8>,
+    /// Start of the first message not handed out yet
+    start: usize,
+    /// End of the bytes read so far
+    end: usize,
 }
 
 impl Default for ReaderFSM {
     fn default() -> Self {
         Self {
-            state: State::ReadingHeader,
-            buf: ReadBuffer::new(HeaderDecoder::LENGTH + std::mem::size_of::<u32>()),
+            buf: vec![0; CHUNK_LEN],
+            start: 0,
+            end: 0,
         }
     }
 }
@@ -31,40 +40,106 @@ impl ReaderFSM {
         Self::default()
     }
 
+    /// Returns the space the next read should go to. Messages handed out
+    /// by `next_message_ref` must be dropped before calling this, since
+    /// leftover bytes are moved to the front of the buffer.
     pub fn wants(&mut self) -> &mut [u8] {
-        self.buf.remaining_part_mut()
+        if self.start != 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        // A malformed length is reported by `next_message_ref`, not here
+        let pending = self.pending_len().ok().flatten().unwrap_or(0);
+        let size = pending.max(self.end + CHUNK_LEN);
+        if self.buf.len() < size {
+            self.buf.resize(size, 0);
+        }
+
+        &mut self.buf[self.end..]
+    }
+
+    pub fn satisfy(&mut self, read: usize) {
+        self.end += read;
+        assert!(self.end <= self.buf.len());
+    }
+
+    /// Splits off the next complete message, if there is one buffered.
+    pub fn next_message(&mut self) -> Result<Option<Message>> {
+        match self.next_message_ref()? {
+            Some(message) => message.to_message().map(Some),
+            None => Ok(None),
+        }
     }
 
-    pub fn satisfy(&mut self, read: usize) -> Result<Option<Message>> {
-        self.buf.add_pos(read);
-        if !self.buf.is_full() {
+    /// Like `next_message`, but returns the message borrowing the read
+    /// buffer.
+    pub fn next_message_ref(&mut self) -> Result<Option<MessageRef<'_>>> {
+        let Some(len) = self.pending_len()? else {
+            return Ok(None);
+        };
+        if self.end - self.start < len {
             return Ok(None);
         }
 
-        match self.state {
-            State::ReadingHeader => {
-                let (header, header_fields_len) = {
-                    let mut buf = DecodingBuffer::new(self.buf.filled_part());
-                    let header = HeaderDecoder::decode(&mut buf)?;
-                    let header_fields_len = buf.peek_u32().context("EOF")? as usize;
-                    (header, header_fields_len)
-                };
-
-                let mut new_size = HeaderDecoder::LENGTH + header_fields_len;
-                new_size = new_size.next_multiple_of(8);
-                new_size += header.body_len;
-                self.buf.resize(new_size);
-
-                self.state = State::ReadingFullMessage;
-                Ok(None)
-            }
-
-            State::ReadingFullMessage => {
-                let buf = self.buf.take().into_vec();
-                let message = MessageDecoder::decode(&buf)?;
-                *self = Self::new();
-                Ok(Some(message))
-            }
+        let bytes = &self.buf[self.start..self.start + len];
+        self.start += len;
+        MessageRef::decode(bytes).map(Some)
+    }
+
+    /// Full length of the message at `start`, once its header is buffered.
+    fn pending_len(&self) -> Result<Option<usize>> {
+        let filled = &self.buf[self.start..self.end];
+        if filled.len() < HEADER_LEN {
+            return Ok(None);
+        }
+
+        let mut buf = DecodingBuffer::new(filled);
+        let header = HeaderDecoder::decode(&mut buf)?;
+        let header_fields_len = buf.peek_u32().context("EOF")? as usize;
+
+        let len = (HEADER_LEN + header_fields_len).next_multiple_of(8) + header.body_len;
+        ensure!(len <= MAX_MESSAGE_LEN, "message of {len} bytes is too long");
+        Ok(Some(len))
+    }
+}
+
+#[test]
+fn test_reader_chunks() {
+    use crate::{encoders::MessageEncoder, types::Value};
+
+    let message = |serial| Message::MethodReturn {
+        serial,
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![Value::String("x".repeat(serial as usize * 3000))],
+    };
+    let mut stream = vec![];
+    for serial in 1..=4 {
+        stream.extend(MessageEncoder::encode(&message(serial)).unwrap());
+    }
+
+    let mut reader = ReaderFSM::new();
+    let mut input = stream.as_slice();
+    let mut serials = vec![];
+    let mut reads = 0;
+    while !input.is_empty() {
+        let wants = reader.wants();
+        let len = wants.len().min(input.len());
+        wants[..len].copy_from_slice(&input[..len]);
+        input = &input[len..];
+        reader.satisfy(len);
+        reads += 1;
+
+        while let Some(decoded) = reader.next_message().unwrap() {
+            assert_eq!(decoded, message(decoded.serial()));
+            serials.push(decoded.serial());
         }
     }
+    assert_eq!(serials, vec![1, 2, 3, 4]);
+    assert!(reads < 8);
+    assert!(reader.next_message().unwrap().is_none());
 }
@@ -1,9 +1,20 @@
+use crate::{
+    encoders::{EncodingBuffer, MessageEncoder},
+    types::Message,
+};
 use anyhow::{Context, Result};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, io::IoSlice};
+
+/// Maximum number of written-out buffers kept around for reuse.
+const POOL_SIZE: usize = 16;
+
+/// Number of queued buffers the backends hand to a single vectored write.
+pub const MAX_IOVECS: usize = 16;
 
 #[derive(Debug, Default)]
 pub struct WriterFSM {
     queue: VecDeque<QueueItem>,
+    pool: Vec<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -21,20 +32,94 @@ impl WriterFSM {
         self.queue.push_back(QueueItem { pos: 0, buf });
     }
 
+    /// Encodes `message` straight into a buffer taken from the pool.
+    pub fn enqueue_message(&mut self, message: &Message) -> Result<()> {
+        let mut buf = EncodingBuffer::from_vec(self.pool.pop().unwrap_or_default());
+        MessageEncoder::encode_into(&mut buf, message)?;
+        self.enqueue(buf.done());
+        Ok(())
+    }
+
     pub fn wants(&self) -> Option<&[u8]> {
         let QueueItem { pos, buf } = self.queue.front()?;
         Some(&buf[*pos..])
     }
 
-    pub fn satisfy(&mut self, written: usize) -> Result<()> {
-        let QueueItem { pos, buf } = self.queue.front_mut().context("malformed state")?;
-        *pos += written;
-        assert!(*pos <= buf.len());
+    /// Fills `out` with the pending parts of as many queued buffers as fit,
+    /// returns the number of slices filled.
+    pub fn wants_vectored<'a>(&'a self, out: &mut [IoSlice<'a>]) -> usize {
+        let mut count = 0;
+        for (slot, QueueItem { pos, buf }) in out.iter_mut().zip(&self.queue) {
+            *slot = IoSlice::new(&buf[*pos..]);
+            count += 1;
+        }
+        count
+    }
+
+    /// Every buffer not fully written yet.
+    pub fn queued(&self) -> impl Iterator<Item = &[u8]> {
+        self.queue
+            .iter()
+            .map(|QueueItem { buf, .. }| buf.as_slice())
+    }
+
+    pub fn satisfy(&mut self, mut written: usize) -> Result<()> {
+        loop {
+            let QueueItem { pos, buf } = self.queue.front_mut().context("malformed state")?;
+            let n = written.min(buf.len() - *pos);
+            *pos += n;
+            written -= n;
+
+            if *pos < buf.len() {
+                break;
+            }
+            let QueueItem { buf, .. } = self.queue.pop_front().context("malformed state")?;
+            if self.pool.len() < POOL_SIZE {
+                self.pool.push(buf);
+            }
 
-        if *pos == buf.len() {
-            self.queue.pop_front();
+            if written == 0 {
+                break;
+            }
         }
 
         Ok(())
     }
 }
+
+#[test]
+fn test_writer_vectored() {
+    let message = |serial| Message::Signal {
+        serial,
+        path: "/a".into(),
+        interface: "a.b".into(),
+        member: "C".into(),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    let first = MessageEncoder::encode(&message(1)).unwrap();
+    let second = MessageEncoder::encode(&message(2)).unwrap();
+
+    let mut writer = WriterFSM::new();
+    writer.enqueue_message(&message(1)).unwrap();
+    writer.enqueue_message(&message(2)).unwrap();
+
+    let mut slices = [IoSlice::new(&[]); 4];
+    assert_eq!(writer.wants_vectored(&mut slices), 2);
+    assert_eq!(&*slices[0], &first[..]);
+    assert_eq!(&*slices[1], &second[..]);
+
+    writer.satisfy(first.len() + 3).unwrap();
+    assert_eq!(writer.wants(), Some(&second[3..]));
+    assert_eq!(writer.pool.len(), 1);
+
+    writer.satisfy(second.len() - 3).unwrap();
+    assert_eq!(writer.wants(), None);
+    assert_eq!(writer.pool.len(), 2);
+
+    writer.enqueue_message(&message(1)).unwrap();
+    assert_eq!(writer.pool.len(), 1);
+    assert_eq!(writer.wants(), Some(&first[..]));
+}
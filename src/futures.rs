@@ -0,0 +1,505 @@
+//! A connection over any `futures-io` stream, for runtimes other than tokio.
+//!
+//! The runtime supplies a connected `AsyncRead + AsyncWrite` stream, for
+//! example smol's `Async<UnixStream>`. There is no background task: the
+//! futures and streams handed out by a `Connection` take turns driving the
+//! socket, and a message read by one of them is routed to whoever waits
+//! for it.
+
+use crate::{
+    disconnected::Disconnected,
+    fsm::{AuthFSM, AuthWants, MAX_IOVECS, ReaderFSM, WriterFSM},
+    match_rule::MatchRule,
+    messages::{AddMatch, RemoveMatch},
+    serial::Serial,
+    types::{Message, MessageType},
+};
+use anyhow::Result;
+use futures_core::Stream;
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::poll_fn,
+    io::{ErrorKind, IoSlice},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+pub struct Connection<S> {
+    inner: Arc<Mutex<Inner<S>>>,
+}
+
+/// A cloneable handle that sends on a `Connection`.
+pub struct Sender<S> {
+    inner: Arc<Mutex<Inner<S>>>,
+}
+
+/// Signals matching a rule, returned by `Connection::signals`. Yields the
+/// `Disconnected` error once, then ends. Dropping it removes the rule from
+/// the bus.
+pub struct SignalStream<S> {
+    inner: Arc<Mutex<Inner<S>>>,
+    id: u64,
+    done: bool,
+}
+
+struct Inner<S> {
+    stream: S,
+    serial: Serial,
+
+    reader: ReaderFSM,
+    writer: WriterFSM,
+
+    /// Calls waiting for a reply, by serial
+    replies: HashMap<u32, Waiting>,
+    subscriptions: HashMap<u64, Subscription>,
+    next_subscription: u64,
+
+    disconnected: Option<Disconnected>,
+}
+
+#[derive(Default)]
+struct Waiting {
+    reply: Option<Message>,
+    waker: Option<Waker>,
+}
+
+struct Subscription {
+    rule: MatchRule,
+    queue: VecDeque<Message>,
+    waker: Option<Waker>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    /// Authenticates over an already connected `stream`.
+    pub async fn new(mut stream: S) -> Result<Self> {
+        let mut auth = AuthFSM::new();
+        loop {
+            match auth.wants() {
+                AuthWants::Read(buf) => {
+                    let read = poll_fn(|cx| Pin::new(&mut stream).poll_read(cx, buf)).await;
+                    auth.satisfy_read(check_auth_io(read)?)?;
+                }
+
+                AuthWants::Write(bytes) => {
+                    let written = poll_fn(|cx| Pin::new(&mut stream).poll_write(cx, bytes)).await;
+                    if let Some(_guid) = auth.satisfy_write(check_auth_io(written)?)? {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let inner = Inner {
+            stream,
+            serial: Serial::zero(),
+
+            reader: ReaderFSM::new(),
+            writer: WriterFSM::new(),
+
+            replies: HashMap::new(),
+            subscriptions: HashMap::new(),
+            next_subscription: 0,
+
+            disconnected: None,
+        };
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    pub fn sender(&self) -> Sender<S> {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub async fn send(&self, message: &mut Message) -> Result<()> {
+        send(&self.inner, message).await
+    }
+
+    /// Sends a method call and waits for its reply or error.
+    pub async fn call(&self, message: &mut Message) -> Result<Message> {
+        let serial = {
+            let mut inner = lock(&self.inner);
+            inner.enqueue(message)?;
+            inner.replies.insert(message.serial(), Waiting::default());
+            message.serial()
+        };
+        // Forgets the call if this future is dropped before the reply arrives
+        let _guard = ReplyGuard {
+            inner: &self.inner,
+            serial,
+        };
+        poll_fn(|cx| lock(&self.inner).poll_reply(serial, cx)).await
+    }
+
+    /// Adds `rule` on the bus and returns the signals it matches.
+    pub fn signals(&self, rule: MatchRule) -> Result<SignalStream<S>> {
+        let mut inner = lock(&self.inner);
+        inner.enqueue(&mut AddMatch::rule(&rule).into())?;
+
+        let id = inner.next_subscription;
+        inner.next_subscription += 1;
+        inner.subscriptions.insert(
+            id,
+            Subscription {
+                rule,
+                queue: VecDeque::new(),
+                waker: None,
+            },
+        );
+        Ok(SignalStream {
+            inner: self.inner.clone(),
+            id,
+            done: false,
+        })
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Sender<S> {
+    pub async fn send(&self, message: &mut Message) -> Result<()> {
+        send(&self.inner, message).await
+    }
+}
+
+impl<S> Clone for Sender<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+async fn send<S: AsyncRead + AsyncWrite + Unpin>(
+    inner: &Mutex<Inner<S>>,
+    message: &mut Message,
+) -> Result<()> {
+    lock(inner).enqueue(message)?;
+    poll_fn(|cx| lock(inner).poll_flush(cx)).await
+}
+
+/// A panic while holding the lock can't leave the FSMs half updated, so
+/// poisoning is ignored.
+fn lock<S>(inner: &Mutex<Inner<S>>) -> MutexGuard<'_, Inner<S>> {
+    inner.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn check_auth_io(result: std::io::Result<usize>) -> Result<usize> {
+    match result {
+        Ok(0) => Err(Disconnected::eof().into()),
+        Ok(len) => Ok(len),
+        Err(err) => Err(Disconnected::from(&err).into()),
+    }
+}
+
+struct ReplyGuard<'a, S> {
+    inner: &'a Mutex<Inner<S>>,
+    serial: u32,
+}
+
+impl<S> Drop for ReplyGuard<'_, S> {
+    fn drop(&mut self) {
+        lock(self.inner).replies.remove(&self.serial);
+    }
+}
+
+impl<S> Inner<S> {
+    fn enqueue(&mut self, message: &mut Message) -> Result<()> {
+        self.check_connected()?;
+        *message.serial_mut() = self.serial.increment_and_get();
+        self.writer.enqueue_message(message)
+    }
+
+    /// The stream only remembers the waker of whoever polled it last, so
+    /// once that one is done everyone else polls again to take over.
+    fn wake_all(&mut self) {
+        let replies = self.replies.values_mut().map(|waiting| &mut waiting.waker);
+        let subscriptions = self.subscriptions.values_mut().map(|sub| &mut sub.waker);
+        for slot in replies.chain(subscriptions) {
+            if let Some(waker) = slot.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn check_connected(&self) -> Result<()> {
+        match &self.disconnected {
+            Some(disconnected) => Err(disconnected.clone().into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Inner<S> {
+    /// Done once everything is written, even if reading fails afterwards.
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let driven = self.drive(cx);
+        if self.writer.wants().is_none() {
+            self.wake_all();
+            return Poll::Ready(Ok(()));
+        }
+        driven?;
+        Poll::Pending
+    }
+
+    fn poll_reply(&mut self, serial: u32, cx: &mut Context<'_>) -> Poll<Result<Message>> {
+        let driven = self.drive(cx);
+        let waiting = self.replies.get_mut(&serial).expect("registered by call");
+        if let Some(reply) = waiting.reply.take() {
+            self.wake_all();
+            return Poll::Ready(Ok(reply));
+        }
+        waiting.waker = Some(cx.waker().clone());
+        driven?;
+        Poll::Pending
+    }
+
+    /// Writes what is queued and reads until the stream would block,
+    /// routing every message to whoever waits for it.
+    fn drive(&mut self, cx: &mut Context<'_>) -> Result<()> {
+        self.check_connected()?;
+        while self.writer.wants().is_some() {
+            let written = {
+                let mut slices = [IoSlice::new(&[]); MAX_IOVECS];
+                let count = self.writer.wants_vectored(&mut slices);
+                Pin::new(&mut self.stream).poll_write_vectored(cx, &slices[..count])
+            };
+            let Poll::Ready(written) = written else {
+                break;
+            };
+            if let Some(len) = self.check_io(written)? {
+                self.writer.satisfy(len)?;
+            }
+        }
+
+        loop {
+            let Poll::Ready(read) = Pin::new(&mut self.stream).poll_read(cx, self.reader.wants())
+            else {
+                return Ok(());
+            };
+            if let Some(len) = self.check_io(read)? {
+                self.reader.satisfy(len);
+                while let Some(message) = self.reader.next_message()? {
+                    self.route(message);
+                }
+            }
+        }
+    }
+
+    /// Messages nobody waits for, such as replies to `send`, are dropped.
+    fn route(&mut self, message: Message) {
+        if let Some(serial) = message.reply_serial()
+            && let Some(waiting) = self.replies.get_mut(&serial)
+        {
+            waiting.reply = Some(message);
+            if let Some(waker) = waiting.waker.take() {
+                waker.wake();
+            }
+            return;
+        }
+        if message.message_type() == MessageType::Signal {
+            for subscription in self.subscriptions.values_mut() {
+                if subscription.rule.matches(&message) {
+                    subscription.queue.push_back(message.clone());
+                    if let Some(waker) = subscription.waker.take() {
+                        waker.wake();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turns hangups and stream errors into `Disconnected`, `None` means
+    /// the operation should be retried.
+    fn check_io(&mut self, result: std::io::Result<usize>) -> Result<Option<usize>> {
+        let disconnected = match result {
+            Ok(0) => Disconnected::eof(),
+            Ok(len) => return Ok(Some(len)),
+            Err(err) if err.kind() == ErrorKind::Interrupted => return Ok(None),
+            Err(err) => Disconnected::from(&err),
+        };
+        let disconnected = disconnected.with_unsent(self.writer.queued());
+        self.disconnected = Some(disconnected.clone());
+        self.wake_all();
+        Err(disconnected.into())
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Stream for SignalStream<S> {
+    type Item = Result<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let mut inner = lock(&this.inner);
+        let driven = inner.drive(cx);
+        let subscription = inner
+            .subscriptions
+            .get_mut(&this.id)
+            .expect("removed on drop");
+        if let Some(message) = subscription.queue.pop_front() {
+            inner.wake_all();
+            return Poll::Ready(Some(Ok(message)));
+        }
+        subscription.waker = Some(cx.waker().clone());
+        if let Err(err) = driven {
+            this.done = true;
+            return Poll::Ready(Some(Err(err)));
+        }
+        Poll::Pending
+    }
+}
+
+impl<S> Drop for SignalStream<S> {
+    fn drop(&mut self) {
+        let mut inner = lock(&self.inner);
+        let Some(subscription) = inner.subscriptions.remove(&self.id) else {
+            return;
+        };
+        // Goes out with whatever is polled next, nothing to do if the
+        // connection is gone
+        if inner
+            .enqueue(&mut RemoveMatch::rule(&subscription.rule).into())
+            .is_ok()
+        {
+            inner.wake_all();
+        }
+    }
+}
+
+#[test]
+fn test_futures_connection() {
+    use crate::{encoders::MessageEncoder, messages::Hello, types::Value};
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        pin::pin,
+    };
+
+    /// Busy polls, any runtime would do
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(out) = future.as_mut().poll(&mut cx) {
+                return out;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    struct Nonblocking(UnixStream);
+    fn ready(cx: &mut Context<'_>, result: std::io::Result<usize>) -> Poll<std::io::Result<usize>> {
+        match result {
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+    impl AsyncRead for Nonblocking {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            ready(cx, (&self.0).read(buf))
+        }
+    }
+    impl AsyncWrite for Nonblocking {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            ready(cx, (&self.0).write(buf))
+        }
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    let signal = |member: &'static str| Message::Signal {
+        serial: 1,
+        path: "/a".into(),
+        interface: "a.b".into(),
+        member: member.into(),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    let reply = || Message::MethodReturn {
+        serial: 2,
+        reply_serial: 3,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    ours.set_nonblocking(true).unwrap();
+    let bus = std::thread::spawn(move || {
+        theirs
+            .write_all(b"DATA\r\nOK 0123456789abcdef0123456789abcdef\r\n")
+            .unwrap();
+        let mut handshake = [0; 29];
+        theirs.read_exact(&mut handshake).unwrap();
+
+        let mut reader = ReaderFSM::new();
+        let mut read = || loop {
+            if let Some(message) = reader.next_message().unwrap() {
+                break message;
+            }
+            let len = (&theirs).read(reader.wants()).unwrap();
+            reader.satisfy(len);
+        };
+        let mut received = vec![read(), read(), read()];
+        for message in [signal("D"), signal("C"), reply()] {
+            (&theirs)
+                .write_all(&MessageEncoder::encode(&message).unwrap())
+                .unwrap();
+        }
+        received.extend([read(), read()]);
+        received
+    });
+
+    let conn = block_on(Connection::new(Nonblocking(ours))).unwrap();
+    let mut signals = conn.signals(MatchRule::new().member("C")).unwrap();
+
+    // Sending from another thread flushes the queued AddMatch as well
+    let sender = conn.sender();
+    std::thread::spawn(move || block_on(sender.send(&mut signal("Sent"))).unwrap())
+        .join()
+        .unwrap();
+
+    // The signal read while waiting for the reply is kept for the stream
+    assert_eq!(block_on(conn.call(&mut Hello.into())).unwrap(), reply());
+    let next = poll_fn(|cx| Pin::new(&mut signals).poll_next(cx));
+    assert_eq!(block_on(next).unwrap().unwrap(), signal("C"));
+
+    drop(signals);
+    block_on(conn.send(&mut signal("Flush"))).unwrap();
+
+    let received = bus.join().unwrap();
+    let rule = [Value::String("type='signal',member='C'".into())];
+    assert_eq!(received[0].member(), Some("AddMatch"));
+    assert_eq!(received[0].body(), rule);
+    assert_eq!(received[1].member(), Some("Sent"));
+    assert_eq!(received[2].member(), Some("Hello"));
+    assert_eq!(received[3].member(), Some("RemoveMatch"));
+    assert_eq!(received[3].body(), rule);
+    assert_eq!(received[4].member(), Some("Flush"));
+
+    let err = block_on(conn.call(&mut Hello.into())).unwrap_err();
+    assert!(err.downcast_ref::<Disconnected>().is_some());
+}
@@ -0,0 +1,8 @@
+mod model;
+pub use model::{Access, Annotation, Arg, Direction, Interface, Method, Node, Property, Signal};
+
+mod parser;
+
+mod writer;
+
+mod xml;
@@ -0,0 +1,158 @@
+use crate::types::{CompleteType, Value};
+use anyhow::{Result, ensure};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Node {
+    pub name: Option<String>,
+    pub interfaces: Vec<Interface>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Interface {
+    pub name: String,
+    pub methods: Vec<Method>,
+    pub signals: Vec<Signal>,
+    pub properties: Vec<Property>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Method {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Signal {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub complete_type: CompleteType,
+    pub access: Access,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg {
+    pub name: Option<String>,
+    pub complete_type: CompleteType,
+    pub direction: Direction,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    pub fn is_readable(self) -> bool {
+        matches!(self, Self::Read | Self::ReadWrite)
+    }
+
+    pub fn is_writable(self) -> bool {
+        matches!(self, Self::Write | Self::ReadWrite)
+    }
+}
+
+impl Node {
+    pub fn interface(&self, name: &str) -> Option<&Interface> {
+        self.interfaces.iter().find(|i| i.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Node> {
+        self.children
+            .iter()
+            .find(|c| c.name.as_deref() == Some(name))
+    }
+}
+
+impl Interface {
+    pub fn method(&self, name: &str) -> Option<&Method> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    pub fn signal(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|s| s.name == name)
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+impl Method {
+    pub fn in_args(&self) -> impl Iterator<Item = &Arg> {
+        self.args.iter().filter(|a| a.direction == Direction::In)
+    }
+
+    pub fn out_args(&self) -> impl Iterator<Item = &Arg> {
+        self.args.iter().filter(|a| a.direction == Direction::Out)
+    }
+
+    pub fn in_signature(&self) -> Vec<CompleteType> {
+        self.in_args().map(|a| a.complete_type.clone()).collect()
+    }
+
+    pub fn out_signature(&self) -> Vec<CompleteType> {
+        self.out_args().map(|a| a.complete_type.clone()).collect()
+    }
+
+    /// Checks that `body` can be sent as a call to this method.
+    pub fn validate_call(&self, body: &[Value]) -> Result<()> {
+        validate_body(&self.name, &self.in_signature(), body)
+    }
+
+    /// Checks that `body` is a valid reply of this method.
+    pub fn validate_reply(&self, body: &[Value]) -> Result<()> {
+        validate_body(&self.name, &self.out_signature(), body)
+    }
+}
+
+impl Signal {
+    pub fn signature(&self) -> Vec<CompleteType> {
+        self.args.iter().map(|a| a.complete_type.clone()).collect()
+    }
+
+    pub fn validate(&self, body: &[Value]) -> Result<()> {
+        validate_body(&self.name, &self.signature(), body)
+    }
+}
+
+fn validate_body(member: &str, expected: &[CompleteType], body: &[Value]) -> Result<()> {
+    ensure!(
+        expected.len() == body.len(),
+        "{member} expects {} argument(s), got {}",
+        expected.len(),
+        body.len()
+    );
+    for (idx, (expected, value)) in expected.iter().zip(body).enumerate() {
+        let actual = value.try_complete_type()?;
+        ensure!(
+            *expected == actual,
+            "{member} expects argument {idx} to be {expected:?}, got {actual:?}"
+        );
+    }
+    Ok(())
+}
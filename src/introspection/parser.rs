@@ -0,0 +1,332 @@
+use crate::{
+    body_is,
+    introspection::{
+        Access, Annotation, Arg, Direction, Interface, Method, Node, Property, Signal,
+        xml::{XmlEvent, XmlReader},
+    },
+    message_is,
+    types::{CompleteType, Message, Value},
+};
+use anyhow::{Context as _, Result, bail, ensure};
+
+struct Element<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, String)>,
+    self_closing: bool,
+}
+
+impl Element<'_> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn required_attr(&self, name: &str) -> Result<&str> {
+        self.attr(name)
+            .with_context(|| format!("<{}> is missing {name:?} attribute", self.name))
+    }
+
+    fn complete_type(&self) -> Result<CompleteType> {
+        let signature = self.required_attr("type")?;
+        signature
+            .parse()
+            .with_context(|| format!("invalid type {signature:?} in <{}>", self.name))
+    }
+}
+
+struct Parser<'a> {
+    reader: XmlReader<'a>,
+}
+
+impl<'a> Parser<'a> {
+    fn next_open(&mut self, parent: &str) -> Result<Option<Element<'a>>> {
+        match self.reader.next_event()? {
+            Some(XmlEvent::Open {
+                name,
+                attrs,
+                self_closing,
+            }) => Ok(Some(Element {
+                name,
+                attrs,
+                self_closing,
+            })),
+            Some(XmlEvent::Close { name }) => {
+                ensure!(name == parent, "expected </{parent}>, got </{name}>");
+                Ok(None)
+            }
+            None => bail!("unexpected EOF inside <{parent}>"),
+        }
+    }
+
+    fn skip(&mut self, element: Element<'a>) -> Result<()> {
+        if element.self_closing {
+            return Ok(());
+        }
+        while let Some(child) = self.next_open(element.name)? {
+            self.skip(child)?;
+        }
+        Ok(())
+    }
+
+    fn annotation(&mut self, element: Element<'a>) -> Result<Annotation> {
+        let annotation = Annotation {
+            name: element.required_attr("name")?.to_string(),
+            value: element.required_attr("value")?.to_string(),
+        };
+        self.skip(element)?;
+        Ok(annotation)
+    }
+
+    fn arg(&mut self, element: Element<'a>, default_direction: Direction) -> Result<Arg> {
+        let direction = match element.attr("direction") {
+            None => default_direction,
+            Some("in") => Direction::In,
+            Some("out") => Direction::Out,
+            Some(other) => bail!("unknown arg direction {other:?}"),
+        };
+        let mut arg = Arg {
+            name: element.attr("name").map(str::to_string),
+            complete_type: element.complete_type()?,
+            direction,
+            annotations: vec![],
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "annotation" => arg.annotations.push(self.annotation(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(arg)
+    }
+
+    fn method(&mut self, element: Element<'a>) -> Result<Method> {
+        let mut method = Method {
+            name: element.required_attr("name")?.to_string(),
+            ..Default::default()
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "arg" => method.args.push(self.arg(child, Direction::In)?),
+                    "annotation" => method.annotations.push(self.annotation(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(method)
+    }
+
+    fn signal(&mut self, element: Element<'a>) -> Result<Signal> {
+        let mut signal = Signal {
+            name: element.required_attr("name")?.to_string(),
+            ..Default::default()
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "arg" => {
+                        let arg = self.arg(child, Direction::Out)?;
+                        ensure!(
+                            arg.direction == Direction::Out,
+                            "signal {} has an \"in\" argument",
+                            signal.name
+                        );
+                        signal.args.push(arg);
+                    }
+                    "annotation" => signal.annotations.push(self.annotation(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(signal)
+    }
+
+    fn property(&mut self, element: Element<'a>) -> Result<Property> {
+        let access = match element.required_attr("access")? {
+            "read" => Access::Read,
+            "write" => Access::Write,
+            "readwrite" => Access::ReadWrite,
+            other => bail!("unknown property access {other:?}"),
+        };
+        let mut property = Property {
+            name: element.required_attr("name")?.to_string(),
+            complete_type: element.complete_type()?,
+            access,
+            annotations: vec![],
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "annotation" => property.annotations.push(self.annotation(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(property)
+    }
+
+    fn interface(&mut self, element: Element<'a>) -> Result<Interface> {
+        let mut interface = Interface {
+            name: element.required_attr("name")?.to_string(),
+            ..Default::default()
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "method" => interface.methods.push(self.method(child)?),
+                    "signal" => interface.signals.push(self.signal(child)?),
+                    "property" => interface.properties.push(self.property(child)?),
+                    "annotation" => interface.annotations.push(self.annotation(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(interface)
+    }
+
+    fn node(&mut self, element: Element<'a>) -> Result<Node> {
+        ensure!(
+            element.name == "node",
+            "expected <node>, got <{}>",
+            element.name
+        );
+        let mut node = Node {
+            name: element.attr("name").map(str::to_string),
+            ..Default::default()
+        };
+        if !element.self_closing {
+            while let Some(child) = self.next_open(element.name)? {
+                match child.name {
+                    "interface" => node.interfaces.push(self.interface(child)?),
+                    "node" => node.children.push(self.node(child)?),
+                    _ => self.skip(child)?,
+                }
+            }
+        }
+        Ok(node)
+    }
+}
+
+impl Node {
+    pub fn parse(xml: &str) -> Result<Self> {
+        let mut parser = Parser {
+            reader: XmlReader::new(xml),
+        };
+        let root = match parser.reader.next_event()? {
+            Some(XmlEvent::Open {
+                name,
+                attrs,
+                self_closing,
+            }) => Element {
+                name,
+                attrs,
+                self_closing,
+            },
+            Some(XmlEvent::Close { name }) => bail!("unexpected </{name}>"),
+            None => bail!("empty introspection data"),
+        };
+        let node = parser.node(root)?;
+        ensure!(
+            parser.reader.next_event()?.is_none(),
+            "trailing elements after root <node>"
+        );
+        Ok(node)
+    }
+}
+
+impl std::str::FromStr for Node {
+    type Err = anyhow::Error;
+
+    fn from_str(xml: &str) -> Result<Self> {
+        Self::parse(xml)
+    }
+}
+
+impl TryFrom<&Message> for Node {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &Message) -> Result<Self> {
+        message_is!(message, Message::MethodReturn { body, .. });
+        body_is!(body, [Value::String(xml)]);
+        Self::parse(xml)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        introspection::{Access, Direction, Node},
+        types::{CompleteType, Value},
+    };
+
+    const XML: &str = r#"
+<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node name="/org/me/test">
+    <interface name="org.me.test">
+        <annotation name="org.freedesktop.DBus.Deprecated" value="false"/>
+        <method name="Plus">
+            <arg type="i" name="x" direction="in" />
+            <arg type="i" name="y" direction="in" />
+            <arg type="i" name="sum" direction="out" />
+        </method>
+        <signal name="Changed">
+            <arg type="a{sv}" name="changes"/>
+        </signal>
+        <property name="Count" type="u" access="read">
+            <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="true"/>
+        </property>
+    </interface>
+    <node name="child"/>
+</node>
+"#;
+
+    #[test]
+    fn test_parse_introspection() {
+        let node = Node::parse(XML).unwrap();
+        assert_eq!(node.name.as_deref(), Some("/org/me/test"));
+        assert!(node.child("child").is_some());
+
+        let interface = node.interface("org.me.test").unwrap();
+        assert_eq!(interface.annotations.len(), 1);
+
+        let plus = interface.method("Plus").unwrap();
+        assert_eq!(
+            plus.in_signature(),
+            vec![CompleteType::Int32, CompleteType::Int32]
+        );
+        assert_eq!(plus.out_signature(), vec![CompleteType::Int32]);
+        plus.validate_call(&[Value::Int32(1), Value::Int32(2)])
+            .unwrap();
+        assert!(plus.validate_call(&[Value::Int32(1)]).is_err());
+        assert!(
+            plus.validate_call(&[Value::Int32(1), Value::UInt32(2)])
+                .is_err()
+        );
+
+        let changed = interface.signal("Changed").unwrap();
+        assert_eq!(changed.args[0].direction, Direction::Out);
+        assert_eq!(
+            changed.args[0].complete_type,
+            CompleteType::Array(Box::new(CompleteType::DictEntry(
+                Box::new(CompleteType::String),
+                Box::new(CompleteType::Variant)
+            )))
+        );
+
+        let count = interface.property("Count").unwrap();
+        assert_eq!(count.complete_type, CompleteType::UInt32);
+        assert_eq!(count.access, Access::Read);
+        assert_eq!(count.annotations.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_introspection_invalid_type() {
+        let xml = r#"<node><interface name="a"><method name="b"><arg type="ii"/></method></interface></node>"#;
+        assert!(Node::parse(xml).is_err());
+    }
+}
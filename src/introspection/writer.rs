@@ -0,0 +1,209 @@
+use crate::introspection::{Access, Annotation, Arg, Direction, Interface, Node};
+use std::fmt::{self, Write};
+
+const DOCTYPE: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">"#;
+
+/// Writes the introspection XML that `Node::parse` reads back.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{DOCTYPE}")?;
+        node(f, self, 0)
+    }
+}
+
+fn node(f: &mut fmt::Formatter<'_>, node: &Node, depth: usize) -> fmt::Result {
+    indent(f, depth)?;
+    f.write_str("<node")?;
+    if let Some(name) = &node.name {
+        attr(f, "name", name)?;
+    }
+    if node.interfaces.is_empty() && node.children.is_empty() {
+        return f.write_str("/>\n");
+    }
+    f.write_str(">\n")?;
+    for interface in &node.interfaces {
+        self::interface(f, interface, depth + 1)?;
+    }
+    for child in &node.children {
+        self::node(f, child, depth + 1)?;
+    }
+    indent(f, depth)?;
+    f.write_str("</node>\n")
+}
+
+fn interface(f: &mut fmt::Formatter<'_>, interface: &Interface, depth: usize) -> fmt::Result {
+    indent(f, depth)?;
+    f.write_str("<interface")?;
+    attr(f, "name", &interface.name)?;
+    f.write_str(">\n")?;
+    annotations(f, &interface.annotations, depth + 1)?;
+
+    for method in &interface.methods {
+        open(f, "method", &method.name, depth + 1)?;
+        args(f, &method.args, true, depth + 2)?;
+        annotations(f, &method.annotations, depth + 2)?;
+        close(f, "method", depth + 1)?;
+    }
+    for signal in &interface.signals {
+        open(f, "signal", &signal.name, depth + 1)?;
+        args(f, &signal.args, false, depth + 2)?;
+        annotations(f, &signal.annotations, depth + 2)?;
+        close(f, "signal", depth + 1)?;
+    }
+    for property in &interface.properties {
+        indent(f, depth + 1)?;
+        f.write_str("<property")?;
+        attr(f, "name", &property.name)?;
+        attr(f, "type", &property.complete_type.to_string())?;
+        let access = match property.access {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::ReadWrite => "readwrite",
+        };
+        attr(f, "access", access)?;
+        if property.annotations.is_empty() {
+            f.write_str("/>\n")?;
+        } else {
+            f.write_str(">\n")?;
+            annotations(f, &property.annotations, depth + 2)?;
+            close(f, "property", depth + 1)?;
+        }
+    }
+
+    close(f, "interface", depth)
+}
+
+/// Signal arguments have no direction attribute.
+fn args(f: &mut fmt::Formatter<'_>, args: &[Arg], directed: bool, depth: usize) -> fmt::Result {
+    for arg in args {
+        indent(f, depth)?;
+        f.write_str("<arg")?;
+        if let Some(name) = &arg.name {
+            attr(f, "name", name)?;
+        }
+        attr(f, "type", &arg.complete_type.to_string())?;
+        if directed {
+            let direction = match arg.direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+            };
+            attr(f, "direction", direction)?;
+        }
+        if arg.annotations.is_empty() {
+            f.write_str("/>\n")?;
+        } else {
+            f.write_str(">\n")?;
+            annotations(f, &arg.annotations, depth + 1)?;
+            close(f, "arg", depth)?;
+        }
+    }
+    Ok(())
+}
+
+fn annotations(
+    f: &mut fmt::Formatter<'_>,
+    annotations: &[Annotation],
+    depth: usize,
+) -> fmt::Result {
+    for annotation in annotations {
+        indent(f, depth)?;
+        f.write_str("<annotation")?;
+        attr(f, "name", &annotation.name)?;
+        attr(f, "value", &annotation.value)?;
+        f.write_str("/>\n")?;
+    }
+    Ok(())
+}
+
+fn open(f: &mut fmt::Formatter<'_>, tag: &str, name: &str, depth: usize) -> fmt::Result {
+    indent(f, depth)?;
+    write!(f, "<{tag}")?;
+    attr(f, "name", name)?;
+    f.write_str(">\n")
+}
+
+fn close(f: &mut fmt::Formatter<'_>, tag: &str, depth: usize) -> fmt::Result {
+    indent(f, depth)?;
+    writeln!(f, "</{tag}>")
+}
+
+fn indent(f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+    for _ in 0..depth {
+        f.write_str("  ")?;
+    }
+    Ok(())
+}
+
+fn attr(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    write!(f, " {name}=\"")?;
+    for c in value.chars() {
+        match c {
+            '&' => f.write_str("&amp;")?,
+            '<' => f.write_str("&lt;")?,
+            '>' => f.write_str("&gt;")?,
+            '"' => f.write_str("&quot;")?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[test]
+fn test_write_introspection() {
+    use crate::introspection::{Method, Property, Signal};
+    use crate::types::CompleteType;
+
+    let node = Node {
+        name: Some("/org/me/test".into()),
+        interfaces: vec![Interface {
+            name: "org.me.test".into(),
+            methods: vec![Method {
+                name: "Plus".into(),
+                args: vec![
+                    Arg {
+                        name: Some("x".into()),
+                        complete_type: CompleteType::Int32,
+                        direction: Direction::In,
+                        annotations: vec![],
+                    },
+                    Arg {
+                        name: None,
+                        complete_type: CompleteType::Int32,
+                        direction: Direction::Out,
+                        annotations: vec![],
+                    },
+                ],
+                annotations: vec![Annotation {
+                    name: "org.me.Note".into(),
+                    value: "<\"a\" & 'b'>".into(),
+                }],
+            }],
+            signals: vec![Signal {
+                name: "Changed".into(),
+                args: vec![Arg {
+                    name: Some("changes".into()),
+                    complete_type: "a{sv}".parse().unwrap(),
+                    direction: Direction::Out,
+                    annotations: vec![],
+                }],
+                annotations: vec![],
+            }],
+            properties: vec![Property {
+                name: "Count".into(),
+                complete_type: CompleteType::UInt32,
+                access: Access::ReadWrite,
+                annotations: vec![],
+            }],
+            annotations: vec![],
+        }],
+        children: vec![Node {
+            name: Some("child".into()),
+            ..Node::default()
+        }],
+    };
+
+    let xml = node.to_string();
+    assert!(xml.contains(r#"<arg name="changes" type="a{sv}"/>"#));
+    assert_eq!(Node::parse(&xml).unwrap(), node);
+}
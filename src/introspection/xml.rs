@@ -0,0 +1,215 @@
+use anyhow::{Context as _, Result, bail, ensure};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum XmlEvent<'a> {
+    Open {
+        name: &'a str,
+        attrs: Vec<(&'a str, String)>,
+        self_closing: bool,
+    },
+    Close {
+        name: &'a str,
+    },
+}
+
+/// A tiny pull-parser that understands the subset of XML used by
+/// introspection data: elements, attributes, comments, processing
+/// instructions and a DOCTYPE. Text content is skipped.
+pub(crate) struct XmlReader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rem(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_until(&mut self, terminator: &str) -> Result<()> {
+        let idx = self
+            .rem()
+            .find(terminator)
+            .with_context(|| format!("unterminated markup, expected {terminator:?}"))?;
+        self.pos += idx + terminator.len();
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rem = self.rem();
+        self.pos += rem.len() - rem.trim_start().len();
+    }
+
+    fn read_name(&mut self) -> Result<&'a str> {
+        let rem = self.rem();
+        let len = rem
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rem.len());
+        ensure!(len > 0, "expected a name at {}", self.pos);
+        self.pos += len;
+        Ok(&rem[..len])
+    }
+
+    fn expect(&mut self, s: &str) -> Result<()> {
+        ensure!(
+            self.rem().starts_with(s),
+            "expected {s:?} at {}, got {:?}",
+            self.pos,
+            self.rem().chars().take(10).collect::<String>()
+        );
+        self.pos += s.len();
+        Ok(())
+    }
+
+    fn read_attr_value(&mut self) -> Result<String> {
+        let quote = match self.rem().chars().next() {
+            Some(q @ ('"' | '\'')) => q,
+            other => bail!("expected a quoted attribute value, got {other:?}"),
+        };
+        self.pos += 1;
+        let len = self
+            .rem()
+            .find(quote)
+            .context("unterminated attribute value")?;
+        let raw = &self.rem()[..len];
+        self.pos += len + 1;
+        unescape(raw)
+    }
+
+    fn read_open(&mut self) -> Result<XmlEvent<'a>> {
+        let name = self.read_name()?;
+        let mut attrs = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.rem().starts_with("/>") {
+                self.pos += 2;
+                return Ok(XmlEvent::Open {
+                    name,
+                    attrs,
+                    self_closing: true,
+                });
+            }
+            if self.rem().starts_with('>') {
+                self.pos += 1;
+                return Ok(XmlEvent::Open {
+                    name,
+                    attrs,
+                    self_closing: false,
+                });
+            }
+            let attr_name = self.read_name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let attr_value = self.read_attr_value()?;
+            attrs.push((attr_name, attr_value));
+        }
+    }
+
+    pub(crate) fn next_event(&mut self) -> Result<Option<XmlEvent<'a>>> {
+        loop {
+            let Some(idx) = self.rem().find('<') else {
+                ensure!(
+                    self.rem().trim().is_empty(),
+                    "trailing text outside of elements"
+                );
+                self.pos = self.src.len();
+                return Ok(None);
+            };
+            self.pos += idx;
+
+            let rem = self.rem();
+            if rem.starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if rem.starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if rem.starts_with("<!") {
+                self.skip_until(">")?;
+            } else if rem.starts_with("</") {
+                self.pos += 2;
+                let name = self.read_name()?;
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(Some(XmlEvent::Close { name }));
+            } else {
+                self.pos += 1;
+                return self.read_open().map(Some);
+            }
+        }
+    }
+}
+
+fn unescape(raw: &str) -> Result<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rem = raw;
+    while let Some(idx) = rem.find('&') {
+        out.push_str(&rem[..idx]);
+        rem = &rem[idx + 1..];
+        let end = rem.find(';').context("unterminated entity")?;
+        let entity = &rem[..end];
+        rem = &rem[end + 1..];
+        match entity {
+            "lt" => out.push('<'),
+            "gt" => out.push('>'),
+            "amp" => out.push('&'),
+            "quot" => out.push('"'),
+            "apos" => out.push('\''),
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16)?
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse()?
+                } else {
+                    bail!("unknown entity &{entity};");
+                };
+                out.push(char::from_u32(code).context("invalid character reference")?);
+            }
+        }
+    }
+    out.push_str(rem);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XmlEvent, XmlReader};
+
+    #[test]
+    fn test_xml_reader() {
+        let mut reader = XmlReader::new(
+            r#"<?xml version="1.0"?>
+            <!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+             "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+            <!-- a comment with <tags> -->
+            <node name='/a'><arg type="a{sv}" value="&lt;&amp;&#65;&#x42;&gt;"/></node>"#,
+        );
+
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(XmlEvent::Open {
+                name: "node",
+                attrs: vec![("name", String::from("/a"))],
+                self_closing: false
+            })
+        );
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(XmlEvent::Open {
+                name: "arg",
+                attrs: vec![
+                    ("type", String::from("a{sv}")),
+                    ("value", String::from("<&AB>"))
+                ],
+                self_closing: true
+            })
+        );
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(XmlEvent::Close { name: "node" })
+        );
+        assert_eq!(reader.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    alloc::{Layout, alloc_zeroed, dealloc},
+    ptr::NonNull,
+    sync::atomic::{AtomicU16, Ordering},
+};
+
+/// `struct io_uring_buf`, the tail of the ring overlays `resv` of the first
+/// entry.
+#[repr(C)]
+struct RingEntry {
+    addr: u64,
+    len: u32,
+    bid: u16,
+    resv: u16,
+}
+
+/// A provided buffer ring (`IORING_REGISTER_PBUF_RING`) together with the
+/// buffers it hands out.
+///
+/// The caller registers it with its ring using `ring_addr`, `entries` and
+/// `bgid`. Buffers are returned to the ring as soon as their contents have
+/// been copied out.
+#[derive(Debug)]
+pub struct BufRing {
+    ring: NonNull<RingEntry>,
+    layout: Layout,
+    bufs: Vec<u8>,
+    buf_len: usize,
+    entries: u16,
+    bgid: u16,
+    tail: u16,
+}
+
+impl BufRing {
+    /// `entries` must be a power of two no larger than 32768.
+    pub fn new(bgid: u16, entries: u16, buf_len: usize) -> Self {
+        assert!(entries.is_power_of_two() && entries <= 1 << 15);
+        assert!(buf_len > 0 && buf_len <= u32::MAX as usize);
+
+        let layout =
+            Layout::from_size_align(std::mem::size_of::<RingEntry>() * entries as usize, 4096)
+                .expect("valid layout");
+        let ring = NonNull::new(unsafe { alloc_zeroed(layout) }.cast::<RingEntry>())
+            .expect("allocation failure");
+
+        let mut out = Self {
+            ring,
+            layout,
+            bufs: vec![0; buf_len * entries as usize],
+            buf_len,
+            entries,
+            bgid,
+            tail: 0,
+        };
+        for bid in 0..entries {
+            out.recycle(bid);
+        }
+        out
+    }
+
+    pub fn ring_addr(&self) -> u64 {
+        self.ring.as_ptr() as u64
+    }
+
+    pub fn entries(&self) -> u16 {
+        self.entries
+    }
+
+    pub fn bgid(&self) -> u16 {
+        self.bgid
+    }
+
+    /// The first `len` bytes of buffer `bid`, as filled by the kernel.
+    pub(crate) fn buf(&self, bid: u16, len: usize) -> &[u8] {
+        let start = bid as usize * self.buf_len;
+        &self.bufs[start..start + len.min(self.buf_len)]
+    }
+
+    /// Hands buffer `bid` back to the kernel.
+    pub(crate) fn recycle(&mut self, bid: u16) {
+        assert!(bid < self.entries);
+        let idx = (self.tail & (self.entries - 1)) as usize;
+        let addr = self.bufs[bid as usize * self.buf_len..].as_ptr() as u64;
+
+        unsafe {
+            let entry = self.ring.as_ptr().add(idx);
+            (&raw mut (*entry).addr).write(addr);
+            (&raw mut (*entry).len).write(self.buf_len as u32);
+            (&raw mut (*entry).bid).write(bid);
+        }
+
+        self.tail = self.tail.wrapping_add(1);
+        let tail = unsafe { AtomicU16::from_ptr(&raw mut (*self.ring.as_ptr()).resv) };
+        tail.store(self.tail, Ordering::Release);
+    }
+}
+
+impl Drop for BufRing {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ring.as_ptr().cast(), self.layout) }
+    }
+}
@@ -1,5 +1,44 @@
+use crate::disconnected::Disconnected;
+use anyhow::Result;
+
+/// `IORING_CQE_F_BUFFER`: the upper 16 bits of `flags` hold a buffer id
+const CQE_F_BUFFER: u32 = 1 << 0;
+/// `IORING_CQE_F_MORE`: a multishot request stays armed
+const CQE_F_MORE: u32 = 1 << 1;
+const CQE_BUFFER_SHIFT: u32 = 16;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Cqe {
     pub user_data: u64,
     pub result: i32,
+    pub flags: u32,
+}
+
+impl Cqe {
+    /// Id of the provided buffer the kernel picked for this completion.
+    pub fn buffer_id(&self) -> Option<u16> {
+        (self.flags & CQE_F_BUFFER != 0).then_some((self.flags >> CQE_BUFFER_SHIFT) as u16)
+    }
+
+    /// `result` as a byte count or fd, a negative errno as `Disconnected`.
+    pub(crate) fn checked_result(&self) -> Result<usize> {
+        if self.result < 0 {
+            return Err(Disconnected::os(-self.result).into());
+        }
+        Ok(self.result as usize)
+    }
+
+    /// Like `checked_result`, but a zero-length read or write means the
+    /// peer hung up.
+    pub(crate) fn transferred(&self) -> Result<usize> {
+        match self.checked_result()? {
+            0 => Err(Disconnected::eof().into()),
+            len => Ok(len),
+        }
+    }
+
+    /// Whether more completions will follow for the same request.
+    pub fn more(&self) -> bool {
+        self.flags & CQE_F_MORE != 0
+    }
 }
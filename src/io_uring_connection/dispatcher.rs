@@ -0,0 +1,229 @@
+use crate::{
+    Message,
+    io_uring_connection::{Cqe, IoUringConnection, OpKind, Sqe, UserData},
+};
+use anyhow::{Context as _, Result};
+use std::collections::HashMap;
+
+/// Runs several connections on one ring, routing every cqe to the
+/// connection and generation its user_data names.
+///
+/// Reconnecting a connection bumps its generation. The old connection is
+/// shut down, its operations are cancelled and it is kept until the last of
+/// them completes, so buffers the kernel may still touch stay alive and its
+/// cqes never reach the new one.
+///
+/// Like a single `IoUringConnection`, it must not be dropped before every
+/// operation it submitted has completed.
+#[derive(Default)]
+pub struct IoUringDispatcher {
+    conns: HashMap<u32, Slot>,
+    retired: HashMap<(u32, u32), IoUringConnection>,
+    cancels: Vec<Sqe>,
+    next_id: u32,
+}
+
+struct Slot {
+    generation: u32,
+    conn: IoUringConnection,
+}
+
+impl IoUringDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a connection to the session bus, returns its id.
+    pub fn add_session(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.conns.insert(
+            id,
+            Slot {
+                generation: 0,
+                conn: IoUringConnection::session_with_id(id, 0),
+            },
+        );
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut IoUringConnection> {
+        self.conns.get_mut(&id).map(|slot| &mut slot.conn)
+    }
+
+    /// Replaces connection `id` with a fresh one under a new generation.
+    pub fn reconnect(&mut self, id: u32) -> Result<&mut IoUringConnection> {
+        let slot = self.conns.get_mut(&id).context("unknown connection")?;
+        let generation = (slot.generation + 1) & UserData::MAX_GENERATION;
+        let old = std::mem::replace(
+            &mut slot.conn,
+            IoUringConnection::session_with_id(id, generation),
+        );
+        let old_generation = std::mem::replace(&mut slot.generation, generation);
+        self.retire(id, old_generation, old);
+        Ok(&mut self.conns.get_mut(&id).context("unknown connection")?.conn)
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        if let Some(Slot { generation, conn }) = self.conns.remove(&id) {
+            self.retire(id, generation, conn);
+        }
+    }
+
+    fn retire(&mut self, id: u32, generation: u32, mut conn: IoUringConnection) {
+        let cancel = UserData {
+            conn: id,
+            generation,
+            op: OpKind::Cancel,
+        };
+        self.cancels.extend(conn.cancel(cancel.encode()));
+        if !conn.is_idle() {
+            self.retired.insert((id, generation), conn);
+        }
+    }
+
+    /// Cancellations for retired connections, then sqes of every live
+    /// connection.
+    pub fn next_sqes(&mut self) -> Vec<Sqe> {
+        let mut out = std::mem::take(&mut self.cancels);
+        out.extend(
+            self.conns
+                .values_mut()
+                .flat_map(|slot| slot.conn.next_sqe())
+                .flatten(),
+        );
+        out
+    }
+
+    /// Hands `cqe` to its connection, returns the connection id and what it
+    /// produced. `None` for cqes of other owners or of retired generations.
+    pub fn process_cqe(&mut self, cqe: Cqe) -> Option<(u32, Result<Vec<Message>>)> {
+        let UserData {
+            conn, generation, ..
+        } = UserData::decode(cqe.user_data)?;
+
+        match self.conns.get_mut(&conn) {
+            Some(slot) if slot.generation == generation => Some((conn, slot.conn.process_cqe(cqe))),
+            _ => {
+                let key = (conn, generation);
+                let retired = self.retired.get_mut(&key)?;
+                let _ = retired.process_cqe(cqe);
+                if retired.is_idle() {
+                    self.retired.remove(&key);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dispatcher_routing() {
+    use crate::Disconnected;
+
+    let mut dispatcher = IoUringDispatcher::new();
+    let a = dispatcher.add_session();
+    let b = dispatcher.add_session();
+
+    let sqes = dispatcher.next_sqes();
+    assert_eq!(sqes.len(), 2);
+    for sqe in &sqes {
+        assert!(matches!(sqe, Sqe::Socket { .. }));
+    }
+    // Already in flight
+    assert!(dispatcher.next_sqes().is_empty());
+
+    let socket = |conn, generation| Cqe {
+        user_data: UserData {
+            conn,
+            generation,
+            op: OpKind::Socket,
+        }
+        .encode(),
+        result: -libc::EMFILE,
+        flags: 0,
+    };
+
+    dispatcher.reconnect(a).unwrap();
+    assert_eq!(dispatcher.retired.len(), 1);
+    let sqes = dispatcher.next_sqes();
+    assert_eq!(
+        sqes[0],
+        Sqe::Cancel {
+            target: socket(a, 0).user_data,
+            user_data: UserData {
+                conn: a,
+                generation: 0,
+                op: OpKind::Cancel,
+            }
+            .encode(),
+        }
+    );
+    assert!(
+        matches!(sqes[1], Sqe::Socket { user_data, .. } if user_data == socket(a, 1).user_data)
+    );
+    // The socket op of the first generation completes late
+    assert!(dispatcher.process_cqe(socket(a, 0)).is_none());
+    assert!(dispatcher.retired.is_empty());
+
+    let (conn, result) = dispatcher.process_cqe(socket(b, 0)).unwrap();
+    assert_eq!(conn, b);
+    assert!(result.unwrap_err().downcast_ref::<Disconnected>().is_some());
+
+    assert!(dispatcher.process_cqe(socket(7, 0)).is_none());
+    assert!(
+        dispatcher
+            .process_cqe(Cqe {
+                user_data: 0xff,
+                result: 0,
+                flags: 0
+            })
+            .is_none()
+    );
+
+    // Nothing may be in flight when the connections are dropped
+    let (conn, result) = dispatcher.process_cqe(socket(a, 1)).unwrap();
+    assert_eq!(conn, a);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_retired_socket() {
+    use std::{
+        io::Read as _,
+        os::{fd::IntoRawFd, unix::net::UnixStream},
+    };
+
+    let mut dispatcher = IoUringDispatcher::new();
+    let a = dispatcher.add_session();
+    assert!(matches!(dispatcher.next_sqes()[..], [Sqe::Socket { .. }]));
+    dispatcher.reconnect(a).unwrap();
+
+    // The socket op of the retired generation succeeds anyway
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    theirs.set_nonblocking(true).unwrap();
+    let socket = Cqe {
+        user_data: UserData {
+            conn: a,
+            generation: 0,
+            op: OpKind::Socket,
+        }
+        .encode(),
+        result: ours.into_raw_fd(),
+        flags: 0,
+    };
+    assert!(dispatcher.process_cqe(socket).is_none());
+    assert!(dispatcher.retired.is_empty());
+    // Closed rather than leaked
+    assert_eq!(theirs.read(&mut [0; 1]).unwrap(), 0);
+
+    let [Sqe::Cancel { .. }, Sqe::Socket { user_data, .. }] = dispatcher.next_sqes()[..] else {
+        panic!("expected the cancel and the new socket op");
+    };
+    let cqe = Cqe {
+        user_data,
+        result: -libc::EMFILE,
+        flags: 0,
+    };
+    assert!(dispatcher.process_cqe(cqe).unwrap().1.is_err());
+}
@@ -51,8 +51,7 @@ impl IoUringAuthFSM {
     pub(crate) fn process_cqe(&mut self, cqe: Cqe) -> Result<Option<()>> {
         match cqe.user_data {
             data if data == self.write_user_data => {
-                assert!(cqe.result >= 0);
-                let written = cqe.result as usize;
+                let written = cqe.transferred()?;
 
                 if let Some(_guid) = self.auth.satisfy_write(written)? {
                     return Ok(Some(()));
@@ -61,8 +60,7 @@ impl IoUringAuthFSM {
             }
 
             data if data == self.read_user_data => {
-                assert!(cqe.result >= 0);
-                let read = cqe.result as usize;
+                let read = cqe.transferred()?;
 
                 self.auth.satisfy_read(read)?;
                 Ok(None)
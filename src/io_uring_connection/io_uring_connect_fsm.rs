@@ -9,7 +9,8 @@ use libc::{AF_UNIX, sockaddr_un};
 
 #[derive(Debug)]
 pub(crate) struct IoUringConnectFSM {
-    fd_and_socket: Option<(i32, sockaddr_un)>,
+    // Boxed so the address handed to the kernel survives moves of the FSM
+    fd_and_socket: Option<(i32, Box<sockaddr_un>)>,
     pub(crate) serial: Serial,
     pub(crate) queue: Vec<Vec<u8>>,
     socket_user_data: u64,
@@ -34,30 +35,41 @@ impl IoUringConnectFSM {
         Ok(())
     }
 
+    pub(crate) fn fd(&self) -> Option<i32> {
+        self.fd_and_socket.as_ref().map(|(fd, _)| *fd)
+    }
+
+    /// Closes the socket of a socket cqe that completed after the FSM was
+    /// given up, nobody else knows about it.
+    pub(crate) fn close_late_socket(&self, cqe: &Cqe) {
+        if cqe.user_data == self.socket_user_data && cqe.result >= 0 {
+            unsafe { libc::close(cqe.result) };
+        }
+    }
+
     pub(crate) fn next_sqe(&mut self) -> Sqe {
         match self.fd_and_socket.as_ref() {
             None => socket_sqe(self.socket_user_data),
-            Some((fd, addr)) => connect_sqe(*fd, addr, self.connect_user_data),
+            Some((fd, addr)) => connect_sqe(*fd, &**addr, self.connect_user_data),
         }
     }
 
     pub(crate) fn process_cqe(&mut self, cqe: Cqe) -> Result<Option<i32>> {
         match cqe.user_data {
             data if data == self.socket_user_data => {
-                let fd = cqe.result;
-                assert!(fd > 0);
+                let fd = cqe.checked_result()? as i32;
 
                 let None = self.fd_and_socket.take() else {
                     panic!("malformed state, {self:?}")
                 };
 
-                self.fd_and_socket = Some((fd, addr_to_connect()?));
+                self.fd_and_socket = Some((fd, Box::new(addr_to_connect()?)));
 
                 Ok(None)
             }
 
             data if data == self.connect_user_data => {
-                assert!(cqe.result >= 0);
+                cqe.checked_result()?;
 
                 let Some((fd, _)) = self.fd_and_socket.take() else {
                     panic!("malformed state, {self:?}")
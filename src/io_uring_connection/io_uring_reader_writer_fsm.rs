@@ -1,11 +1,15 @@
 use crate::{
     Cqe, Message, Sqe,
-    encoders::MessageEncoder,
-    fsm::{ReaderFSM, WriterFSM},
-    io_uring_connection::sqe::{read_sqe, write_sqe},
+    fsm::{MAX_IOVECS, ReaderFSM, WriterFSM},
+    io_uring_connection::{
+        BufRing,
+        sqe::{read_sqe, recv_multi_sqe, writev_sqe},
+    },
     serial::Serial,
 };
-use anyhow::Result;
+use anyhow::{Context as _, Result};
+use libc::iovec;
+use std::io::IoSlice;
 
 #[derive(Debug)]
 pub(crate) struct IoUringReaderWriterFSM {
@@ -13,6 +17,14 @@ pub(crate) struct IoUringReaderWriterFSM {
     serial: Serial,
     reader: ReaderFSM,
     writer: WriterFSM,
+    // Points into the reader's buffer, kept until its cqe arrives
+    read_sqe: Option<Sqe>,
+    // Receives go through provided buffers instead when set
+    buf_ring: Option<BufRing>,
+    recv_armed: bool,
+    // Handed to the kernel by pointer, only rebuilt while no write is in flight
+    iovecs: Vec<iovec>,
+    write_in_flight: bool,
     read_user_data: u64,
     write_user_data: u64,
 }
@@ -24,6 +36,7 @@ impl IoUringReaderWriterFSM {
         queue: Vec<Vec<u8>>,
         read_user_data: u64,
         write_user_data: u64,
+        buf_ring: Option<BufRing>,
     ) -> Self {
         let mut writer = WriterFSM::new();
         for buf in queue {
@@ -35,54 +48,122 @@ impl IoUringReaderWriterFSM {
             serial,
             reader: ReaderFSM::new(),
             writer,
+            read_sqe: None,
+            buf_ring,
+            recv_armed: false,
+            iovecs: Vec::with_capacity(MAX_IOVECS),
+            write_in_flight: false,
             read_user_data,
             write_user_data,
         }
     }
 
+    pub(crate) fn fd(&self) -> i32 {
+        self.fd
+    }
+
+    pub(crate) fn queued(&self) -> impl Iterator<Item = &[u8]> {
+        self.writer.queued()
+    }
+
     pub(crate) fn enqueue(&mut self, message: &mut Message) -> Result<()> {
         *message.serial_mut() = self.serial.increment_and_get();
-        let buf = MessageEncoder::encode(message)?;
-        self.writer.enqueue(buf);
-        Ok(())
+        self.writer.enqueue_message(message)
     }
 
     pub(crate) fn next_sqe(&mut self) -> [Option<Sqe>; 2] {
         let mut out = [None; 2];
 
-        let buf = self.reader.wants();
-        out[0] = Some(read_sqe(self.fd, buf, self.read_user_data));
+        // The buffer may move in `wants`, so it is only asked for between reads
+        if let Some(buf_ring) = &self.buf_ring {
+            if !self.recv_armed {
+                self.recv_armed = true;
+                out[0] = Some(recv_multi_sqe(
+                    self.fd,
+                    buf_ring.bgid(),
+                    self.read_user_data,
+                ));
+            }
+        } else {
+            if self.read_sqe.is_none() {
+                let buf = self.reader.wants();
+                self.read_sqe = Some(read_sqe(self.fd, buf, self.read_user_data));
+            }
+            out[0] = self.read_sqe;
+        }
 
-        if let Some(buf) = self.writer.wants() {
-            out[1] = Some(write_sqe(self.fd, buf, self.write_user_data));
+        if !self.write_in_flight {
+            let mut slices = [IoSlice::new(&[]); MAX_IOVECS];
+            let count = self.writer.wants_vectored(&mut slices);
+            self.iovecs.clear();
+            self.iovecs
+                .extend(slices[..count].iter().map(|slice| iovec {
+                    iov_base: slice.as_ptr() as *mut libc::c_void,
+                    iov_len: slice.len(),
+                }));
+        }
+        if !self.iovecs.is_empty() {
+            self.write_in_flight = true;
+            out[1] = Some(writev_sqe(self.fd, &self.iovecs, self.write_user_data));
         }
 
         out
     }
 
-    pub(crate) fn process_cqe(&mut self, cqe: Cqe) -> Result<Option<Message>> {
+    pub(crate) fn process_cqe(&mut self, cqe: Cqe) -> Result<Vec<Message>> {
         match cqe.user_data {
             data if data == self.write_user_data => {
-                let written = cqe.result;
-                assert!(written >= 0);
-                let written = written as usize;
+                let written = cqe.transferred()?;
 
                 self.writer.satisfy(written)?;
-                Ok(None)
+                self.write_in_flight = false;
+                self.iovecs.clear();
+                Ok(vec![])
             }
 
             data if data == self.read_user_data => {
-                let read = cqe.result;
-                assert!(read >= 0);
-                let read = read as usize;
+                if self.buf_ring.is_some() {
+                    self.process_recv(cqe)?;
+                } else {
+                    self.read_sqe = None;
+                    let read = cqe.transferred()?;
+                    self.reader.satisfy(read);
+                }
 
-                if let Some(message) = self.reader.satisfy(read)? {
-                    return Ok(Some(message));
+                let mut messages = vec![];
+                while let Some(message) = self.reader.next_message()? {
+                    messages.push(message);
                 }
-                Ok(None)
+                Ok(messages)
             }
 
-            _ => Ok(None),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Copies a multishot receive out of its provided buffer, messages may
+    /// span several buffers.
+    fn process_recv(&mut self, cqe: Cqe) -> Result<()> {
+        let buf_ring = self.buf_ring.as_mut().context("malformed state")?;
+        if !cqe.more() {
+            self.recv_armed = false;
+        }
+        if cqe.result == -libc::ENOBUFS {
+            return Ok(());
         }
+        let len = cqe.transferred()?;
+        let bid = cqe.buffer_id().context("recv completed without a buffer")?;
+
+        let mut bytes = buf_ring.buf(bid, len);
+        while !bytes.is_empty() {
+            let wants = self.reader.wants();
+            let len = wants.len().min(bytes.len());
+            wants[..len].copy_from_slice(&bytes[..len]);
+            self.reader.satisfy(len);
+            bytes = &bytes[len..];
+        }
+        buf_ring.recycle(bid);
+
+        Ok(())
     }
 }
@@ -1,35 +1,62 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, os::fd::RawFd};
 
-use crate::Message;
+use crate::{
+    Message,
+    disconnected::{Disconnected, PendingCalls},
+};
 use anyhow::Result;
+pub use buf_ring::BufRing;
 pub use cqe::Cqe;
+pub use dispatcher::IoUringDispatcher;
 use io_uring_auth_fsm::IoUringAuthFSM;
 use io_uring_connect_fsm::IoUringConnectFSM;
 use io_uring_reader_writer_fsm::IoUringReaderWriterFSM;
 pub use sqe::Sqe;
+use sqe::cancel_sqe;
+pub use user_data::{OpKind, UserData};
 
+mod buf_ring;
 mod cqe;
+mod dispatcher;
 mod io_uring_auth_fsm;
 mod io_uring_connect_fsm;
 mod io_uring_reader_writer_fsm;
 mod sqe;
+mod user_data;
 
 #[derive(Default)]
 enum IoUringFSM {
     Connect(IoUringConnectFSM),
     Auth(IoUringAuthFSM),
     ReaderWriter(IoUringReaderWriterFSM),
+    Disconnected(Disconnected),
     #[default]
     None,
 }
 
+/// A connection driven through a caller-owned io_uring.
+///
+/// Sqes point into buffers owned by the connection. They live on the heap
+/// and are not freed or resized until the matching cqe has been passed to
+/// `process_cqe`, so the connection itself may be moved freely.
+///
+/// Dropping it while operations are in flight leaks its buffers, the
+/// socket state and the buffer ring rather than letting the kernel write
+/// into freed memory, and fails a debug assertion. Call `cancel` and pass
+/// cqes to `process_cqe` until `is_idle` before dropping it.
 pub struct IoUringConnection {
     read_user_data: u64,
     write_user_data: u64,
 
     fsm: IoUringFSM,
+    buf_ring: Option<BufRing>,
+    // Kept after a disconnect, sqes still in flight may point into it
+    retired: Option<IoUringFSM>,
+    // Shut down but still open, closed once nothing is in flight on it
+    closing: Option<RawFd>,
 
     pending: HashSet<u64>,
+    calls: PendingCalls,
 }
 
 impl IoUringConnection {
@@ -44,18 +71,52 @@ impl IoUringConnection {
             write_user_data,
 
             fsm: IoUringFSM::Connect(IoUringConnectFSM::new(socket_user_data, connect_user_data)),
+            buf_ring: None,
+            retired: None,
+            closing: None,
 
             pending: HashSet::new(),
+            calls: PendingCalls::default(),
         }
     }
 
+    /// Like `session`, with user_data values built from `conn` and
+    /// `generation` as described on `UserData`.
+    pub fn session_with_id(conn: u32, generation: u32) -> Self {
+        let user_data = |op| {
+            UserData {
+                conn,
+                generation,
+                op,
+            }
+            .encode()
+        };
+        Self::session(
+            user_data(OpKind::Socket),
+            user_data(OpKind::Connect),
+            user_data(OpKind::Read),
+            user_data(OpKind::Write),
+        )
+    }
+
+    /// Receives through the provided buffer ring `buf_ring` with a
+    /// multishot recv once authenticated. The caller must register it with
+    /// its ring before submitting any sqe.
+    pub fn with_buf_ring(mut self, buf_ring: BufRing) -> Self {
+        self.buf_ring = Some(buf_ring);
+        self
+    }
+
     pub fn enqueue(&mut self, message: &mut Message) -> Result<()> {
         match &mut self.fsm {
-            IoUringFSM::Connect(connector) => connector.enqueue(message),
-            IoUringFSM::Auth(auth) => auth.enqueue(message),
-            IoUringFSM::ReaderWriter(rw) => rw.enqueue(message),
+            IoUringFSM::Connect(connector) => connector.enqueue(message)?,
+            IoUringFSM::Auth(auth) => auth.enqueue(message)?,
+            IoUringFSM::ReaderWriter(rw) => rw.enqueue(message)?,
+            IoUringFSM::Disconnected(disconnected) => return Err(disconnected.clone().into()),
             IoUringFSM::None => unreachable!(),
         }
+        self.calls.sent(message);
+        Ok(())
     }
 
     pub fn next_sqe(&mut self) -> [Option<Sqe>; 2] {
@@ -63,6 +124,7 @@ impl IoUringConnection {
             IoUringFSM::Connect(connector) => [Some(connector.next_sqe()), None],
             IoUringFSM::Auth(auth) => [Some(auth.next_sqe()), None],
             IoUringFSM::ReaderWriter(rw) => rw.next_sqe(),
+            IoUringFSM::Disconnected(_) => [None, None],
             IoUringFSM::None => unreachable!(),
         };
 
@@ -82,9 +144,94 @@ impl IoUringConnection {
         std::mem::take(&mut self.fsm)
     }
 
-    pub fn process_cqe(&mut self, cqe: Cqe) -> Result<Option<Message>> {
-        self.pending.remove(&cqe.user_data);
+    pub fn process_cqe(&mut self, cqe: Cqe) -> Result<Vec<Message>> {
+        // A multishot request stays in flight until its last cqe
+        if !cqe.more() {
+            self.pending.remove(&cqe.user_data);
+        }
+        let messages = self
+            .process_cqe_in_fsm(cqe)
+            .map_err(|err| self.disconnect(err));
+        self.close_if_idle();
+        let messages = messages?;
+        for message in &messages {
+            self.calls.received(message);
+        }
+        Ok(messages)
+    }
+
+    /// Whether no submitted operation is waiting for its cqe.
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Shuts the connection down and returns sqes cancelling every
+    /// operation still in flight, submitted with `user_data`. Their cqes,
+    /// and the cancellations' own, must still go through `process_cqe`; the
+    /// socket is closed once the last of them has.
+    pub fn cancel(&mut self, user_data: u64) -> Vec<Sqe> {
+        self.shutdown();
+        self.pending
+            .iter()
+            .map(|target| cancel_sqe(*target, user_data))
+            .collect()
+    }
+
+    /// Closes the socket and moves to the `Disconnected` state.
+    pub(crate) fn shutdown(&mut self) {
+        let _ = self.disconnect(Disconnected::eof().into());
+    }
+
+    /// Moves to the terminal `Disconnected` state if `err` is a
+    /// `Disconnected`, shutting the socket down and recording the messages
+    /// that never went out and the calls left without a reply. The socket
+    /// is closed by `close_if_idle`, so that its number can't be reused
+    /// under operations still in flight.
+    fn disconnect(&mut self, err: anyhow::Error) -> anyhow::Error {
+        let Some(disconnected) = err.downcast_ref::<Disconnected>() else {
+            return err;
+        };
+        let (fd, disconnected) = match &self.fsm {
+            IoUringFSM::Connect(connector) => (connector.fd(), disconnected.clone()),
+            IoUringFSM::Auth(auth) => (
+                Some(auth.fd),
+                disconnected
+                    .clone()
+                    .with_unsent(auth.queue.iter().map(Vec::as_slice)),
+            ),
+            IoUringFSM::ReaderWriter(rw) => {
+                (Some(rw.fd()), disconnected.clone().with_unsent(rw.queued()))
+            }
+            IoUringFSM::Disconnected(_) | IoUringFSM::None => (None, disconnected.clone()),
+        };
+        let disconnected = disconnected.with_pending(&self.calls);
+        if let Some(fd) = fd {
+            // Wakes up a multishot recv that would otherwise stay armed
+            unsafe { libc::shutdown(fd, libc::SHUT_RDWR) };
+            self.closing = Some(fd);
+        }
+
+        let fsm = std::mem::replace(
+            &mut self.fsm,
+            IoUringFSM::Disconnected(disconnected.clone()),
+        );
+        if !matches!(fsm, IoUringFSM::Disconnected(_)) {
+            self.retired = Some(fsm);
+        }
+        self.close_if_idle();
+        disconnected.into()
+    }
 
+    fn close_if_idle(&mut self) {
+        if !self.is_idle() {
+            return;
+        }
+        if let Some(fd) = self.closing.take() {
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    fn process_cqe_in_fsm(&mut self, cqe: Cqe) -> Result<Vec<Message>> {
         match &mut self.fsm {
             IoUringFSM::Connect(connector) => match connector.process_cqe(cqe)? {
                 Some(fd) => {
@@ -101,9 +248,9 @@ impl IoUringConnection {
                         self.read_user_data,
                         self.write_user_data,
                     ));
-                    Ok(None)
+                    Ok(vec![])
                 }
-                None => Ok(None),
+                None => Ok(vec![]),
             },
 
             IoUringFSM::Auth(auth) => match auth.process_cqe(cqe)? {
@@ -120,15 +267,180 @@ impl IoUringConnection {
                         queue,
                         self.read_user_data,
                         self.write_user_data,
+                        self.buf_ring.take(),
                     ));
-                    Ok(None)
+                    Ok(vec![])
                 }
-                None => Ok(None),
+                None => Ok(vec![]),
             },
 
             IoUringFSM::ReaderWriter(rw) => rw.process_cqe(cqe),
 
+            IoUringFSM::Disconnected(disconnected) => {
+                if let Some(IoUringFSM::Connect(connector)) = &self.retired {
+                    connector.close_late_socket(&cqe);
+                }
+                Err(disconnected.clone().into())
+            }
+
             IoUringFSM::None => unreachable!(),
         }
     }
 }
+
+impl Drop for IoUringConnection {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.is_idle() || std::thread::panicking(),
+            "IoUringConnection dropped with {} operations in flight, leaking its buffers",
+            self.pending.len()
+        );
+        if !self.is_idle() {
+            // The kernel may still read or write these
+            std::mem::forget(self.take_fsm());
+            std::mem::forget(self.retired.take());
+            std::mem::forget(self.buf_ring.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encoders::MessageEncoder, serial::Serial};
+    use std::{
+        io::Write as _,
+        os::{fd::IntoRawFd, unix::net::UnixStream},
+    };
+
+    const READ: u64 = 3;
+    const WRITE: u64 = 4;
+
+    /// A connection past connect whose peer is the returned stream.
+    fn connected() -> (IoUringConnection, UnixStream) {
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        let mut conn = IoUringConnection::session(1, 2, READ, WRITE);
+        conn.fsm = IoUringFSM::Auth(IoUringAuthFSM::new(
+            ours.into_raw_fd(),
+            Serial::zero(),
+            vec![],
+            READ,
+            WRITE,
+        ));
+        (conn, theirs)
+    }
+
+    /// Runs `sqe` synchronously, like the kernel would.
+    fn complete(sqe: Sqe) -> Cqe {
+        let (user_data, result) = match sqe {
+            Sqe::Write {
+                fd,
+                buf,
+                len,
+                user_data,
+            } => (user_data, unsafe {
+                libc::write(fd, buf.cast(), len as usize)
+            }),
+            Sqe::Writev {
+                fd,
+                iovecs,
+                len,
+                user_data,
+            } => (user_data, unsafe { libc::writev(fd, iovecs, len as i32) }),
+            Sqe::Read {
+                fd,
+                buf,
+                len,
+                user_data,
+            } => (user_data, unsafe {
+                libc::read(fd, buf.cast(), len as usize)
+            }),
+            Sqe::Cancel { user_data, .. } => (user_data, 0),
+            sqe => panic!("unexpected {sqe:?}"),
+        };
+        let result = match result {
+            -1 => -std::io::Error::last_os_error().raw_os_error().unwrap(),
+            result => result as i32,
+        };
+        Cqe {
+            user_data,
+            result,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn test_disconnected_pending() {
+        use crate::messages::Hello;
+
+        let (mut conn, mut theirs) = connected();
+        conn.enqueue(&mut Hello.into()).unwrap();
+        conn.enqueue(&mut Hello.into()).unwrap();
+
+        let reply = Message::MethodReturn {
+            serial: 1,
+            reply_serial: 1,
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![],
+        };
+        theirs.write_all(b"DATA\r\n").unwrap();
+        theirs
+            .write_all(b"OK 0123456789abcdef0123456789abcdef\r\n")
+            .unwrap();
+        theirs
+            .write_all(&MessageEncoder::encode(&reply).unwrap())
+            .unwrap();
+        theirs.shutdown(std::net::Shutdown::Write).unwrap();
+
+        let mut messages = vec![];
+        let err = 'drive: loop {
+            for sqe in conn.next_sqe().into_iter().flatten() {
+                match conn.process_cqe(complete(sqe)) {
+                    Ok(read) => messages.extend(read),
+                    Err(err) => break 'drive err,
+                }
+            }
+        };
+        assert_eq!(messages, vec![reply]);
+        let disconnected = err.downcast_ref::<Disconnected>().unwrap();
+        assert_eq!(disconnected.reason, crate::DisconnectReason::Eof);
+        assert!(disconnected.unsent.is_empty());
+        assert_eq!(disconnected.pending, vec![2]);
+        assert!(conn.is_idle());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (mut conn, _theirs) = connected();
+        let [Some(write), None] = conn.next_sqe() else {
+            panic!("expected the first auth write");
+        };
+        assert!(!conn.is_idle());
+
+        let cancels = conn.cancel(9);
+        assert_eq!(
+            cancels,
+            vec![Sqe::Cancel {
+                target: WRITE,
+                user_data: 9
+            }]
+        );
+        assert_eq!(conn.next_sqe(), [None, None]);
+        assert!(conn.process_cqe(complete(cancels[0])).is_err());
+        assert!(!conn.is_idle());
+        // The write may still touch the socket, it stays open until then
+        assert!(conn.closing.is_some());
+
+        let canceled = Cqe {
+            user_data: write.user_data(),
+            result: -libc::ECANCELED,
+            flags: 0,
+        };
+        let err = conn.process_cqe(canceled).unwrap_err();
+        assert!(err.downcast_ref::<Disconnected>().is_some());
+        assert!(conn.is_idle());
+        assert_eq!(conn.closing, None);
+    }
+}
@@ -1,4 +1,4 @@
-use libc::{AF_UNIX, SOCK_STREAM, sockaddr, sockaddr_un};
+use libc::{AF_UNIX, SOCK_STREAM, iovec, sockaddr, sockaddr_un};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Sqe {
@@ -23,12 +23,29 @@ pub enum Sqe {
         user_data: u64,
     },
 
+    Writev {
+        fd: i32,
+        iovecs: *const iovec,
+        len: u32,
+        user_data: u64,
+    },
+
     Read {
         fd: i32,
         buf: *mut u8,
         len: u32,
         user_data: u64,
     },
+
+    /// `IORING_OP_ASYNC_CANCEL` of the request submitted with `target`
+    Cancel { target: u64, user_data: u64 },
+
+    /// Multishot `IORING_OP_RECV` picking buffers from group `buf_group`
+    RecvMulti {
+        fd: i32,
+        buf_group: u16,
+        user_data: u64,
+    },
 }
 
 impl Sqe {
@@ -37,7 +54,10 @@ impl Sqe {
             Self::Socket { user_data, .. }
             | Self::Connect { user_data, .. }
             | Self::Write { user_data, .. }
-            | Self::Read { user_data, .. } => user_data,
+            | Self::Writev { user_data, .. }
+            | Self::Read { user_data, .. }
+            | Self::Cancel { user_data, .. }
+            | Self::RecvMulti { user_data, .. } => user_data,
         }
     }
 }
@@ -69,6 +89,15 @@ pub(crate) fn write_sqe(fd: i32, buf: &[u8], user_data: u64) -> Sqe {
     }
 }
 
+pub(crate) fn writev_sqe(fd: i32, iovecs: &[iovec], user_data: u64) -> Sqe {
+    Sqe::Writev {
+        fd,
+        iovecs: iovecs.as_ptr(),
+        len: iovecs.len() as u32,
+        user_data,
+    }
+}
+
 pub(crate) fn read_sqe(fd: i32, buf: &mut [u8], user_data: u64) -> Sqe {
     Sqe::Read {
         fd,
@@ -77,3 +106,15 @@ pub(crate) fn read_sqe(fd: i32, buf: &mut [u8], user_data: u64) -> Sqe {
         user_data,
     }
 }
+
+pub(crate) fn recv_multi_sqe(fd: i32, buf_group: u16, user_data: u64) -> Sqe {
+    Sqe::RecvMulti {
+        fd,
+        buf_group,
+        user_data,
+    }
+}
+
+pub(crate) fn cancel_sqe(target: u64, user_data: u64) -> Sqe {
+    Sqe::Cancel { target, user_data }
+}
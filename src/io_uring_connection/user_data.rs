@@ -0,0 +1,47 @@
+/// The operation a cqe completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum OpKind {
+    Socket = 0,
+    Connect = 1,
+    Read = 2,
+    Write = 3,
+    Cancel = 4,
+}
+
+/// Identifies an operation of one connection when several share a ring.
+///
+/// Encoded as `conn` in the upper 32 bits, `generation` in the next 24 and
+/// `op` in the lowest 8. The generation changes on every reconnect, so
+/// completions of the previous socket can't be mistaken for new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserData {
+    pub conn: u32,
+    pub generation: u32,
+    pub op: OpKind,
+}
+
+impl UserData {
+    pub const MAX_GENERATION: u32 = (1 << 24) - 1;
+
+    pub fn encode(self) -> u64 {
+        assert!(self.generation <= Self::MAX_GENERATION);
+        (self.conn as u64) << 32 | (self.generation as u64) << 8 | self.op as u64
+    }
+
+    pub fn decode(user_data: u64) -> Option<Self> {
+        let op = match user_data as u8 {
+            0 => OpKind::Socket,
+            1 => OpKind::Connect,
+            2 => OpKind::Read,
+            3 => OpKind::Write,
+            4 => OpKind::Cancel,
+            _ => return None,
+        };
+        Some(Self {
+            conn: (user_data >> 32) as u32,
+            generation: (user_data >> 8) as u32 & Self::MAX_GENERATION,
+            op,
+        })
+    }
+}
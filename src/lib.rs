@@ -1,10 +1,31 @@
 use anyhow::{Context, Result};
 use std::os::unix::net::UnixStream;
 
+pub mod args;
+pub mod codegen;
 mod decoders;
+mod disconnected;
 mod encoders;
 pub mod fsm;
+pub mod introspection;
+mod match_rule;
+mod object_cache;
+mod object_server;
+mod peer;
+mod pretty;
+mod proxy;
+mod reconnect;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(any(
+    feature = "blocking",
+    feature = "poll",
+    feature = "io-uring",
+    feature = "tokio",
+    feature = "futures"
+))]
 mod serial;
+mod signal;
 mod types;
 
 #[cfg(feature = "blocking")]
@@ -15,16 +36,42 @@ pub use blocking_connection::BlockingConnection;
 #[cfg(feature = "poll")]
 mod poll_connection;
 #[cfg(feature = "poll")]
-pub use poll_connection::PollConnection;
+pub use poll_connection::{EpollConnection, PollConnection};
+
+#[cfg(feature = "tokio")]
+mod tokio_connection;
+#[cfg(feature = "tokio")]
+pub use tokio_connection::AsyncConnection;
+
+#[cfg(feature = "futures")]
+pub mod futures;
 
 #[cfg(feature = "io-uring")]
 mod io_uring_connection;
 #[cfg(feature = "io-uring")]
-pub use io_uring_connection::{Cqe, IoUringConnection, Sqe};
+pub use io_uring_connection::{
+    BufRing, Cqe, IoUringConnection, IoUringDispatcher, OpKind, Sqe, UserData,
+};
 
-pub use types::{CompleteType, Message, Value};
+#[cfg(feature = "derive")]
+pub use dbus_sans_io_derive::{DBusType, FromValue, ToValue};
+pub use types::{BodyRef, CompleteType, DBusType, Message, MessageRef, Value, ValueRef, Variant};
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+}
 pub mod messages;
+pub use decoders::BodyReader;
+pub use disconnected::{DisconnectReason, Disconnected};
 pub use encoders::MessageEncoder;
+pub use match_rule::MatchRule;
+pub use object_cache::ObjectCache;
+pub use object_server::{Handler, MethodError, ObjectServer, Signals};
+pub use peer::PeerResponder;
+pub use pretty::Pretty;
+pub use proxy::Proxy;
+pub use reconnect::Reconnect;
+pub use signal::SignalBuilder;
 
 #[allow(dead_code)]
 pub(crate) fn session_connection() -> Result<UnixStream> {
@@ -53,3 +100,98 @@ fn test_encode_decode_show_notification() {
     let decoded = MessageDecoder::decode(&encoded).unwrap();
     assert_eq!(decoded, ShowNotification::new("Header", "Body").into());
 }
+
+#[test]
+fn test_encode_decode_option_body() {
+    use crate::{decoders::MessageDecoder, encoders::MessageEncoder};
+    let message = Message::Signal {
+        serial: 1,
+        path: std::borrow::Cow::Borrowed("/"),
+        interface: std::borrow::Cow::Borrowed("org.me.test"),
+        member: std::borrow::Cow::Borrowed("Changed"),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![Value::from(Some(42_u32)), Value::from(None::<String>)],
+    };
+    let encoded = MessageEncoder::encode(&message).unwrap();
+    let decoded = MessageDecoder::decode(&encoded).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn test_reader_next_message_ref() {
+    use crate::{encoders::MessageEncoder, fsm::ReaderFSM};
+    let message = |serial| Message::Signal {
+        serial,
+        path: std::borrow::Cow::Borrowed("/meter"),
+        interface: std::borrow::Cow::Borrowed("org.me.Audio"),
+        member: std::borrow::Cow::Borrowed("Level"),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![Value::String(String::from("left")), Value::Double(0.5)],
+    };
+
+    let mut reader = ReaderFSM::new();
+    for serial in 1..=2 {
+        let encoded = MessageEncoder::encode(&message(serial)).unwrap();
+        let mut input = encoded.as_slice();
+        let wants = reader.wants();
+        let len = wants.len().min(input.len());
+        wants[..len].copy_from_slice(&input[..len]);
+        input = &input[len..];
+        reader.satisfy(len);
+        assert!(input.is_empty());
+        let decoded = reader.next_message_ref().unwrap().unwrap();
+
+        let MessageRef::Signal { member, body, .. } = decoded else {
+            panic!("expected a signal, got {decoded:?}");
+        };
+        assert_eq!(member, "Level");
+        assert_eq!(
+            body.values().unwrap(),
+            vec![ValueRef::String("left"), ValueRef::Double(0.5)]
+        );
+        assert_eq!(decoded.to_message().unwrap(), message(serial));
+    }
+}
+
+#[test]
+fn test_encode_decode_no_reply_expected() {
+    use crate::{decoders::MessageDecoder, encoders::MessageEncoder};
+    let message = Message::MethodCall {
+        serial: 1,
+        path: std::borrow::Cow::Borrowed("/"),
+        member: std::borrow::Cow::Borrowed("Poke"),
+        interface: None,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        no_reply_expected: true,
+        body: vec![],
+    };
+    let encoded = MessageEncoder::encode(&message).unwrap();
+    assert_eq!(encoded[2], 0x1);
+    let decoded = MessageDecoder::decode(&encoded).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn test_encode_heterogenous_array() {
+    use crate::encoders::MessageEncoder;
+    let message = Message::Signal {
+        serial: 1,
+        path: std::borrow::Cow::Borrowed("/"),
+        interface: std::borrow::Cow::Borrowed("org.me.test"),
+        member: std::borrow::Cow::Borrowed("Changed"),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![Value::Variant(Box::new(Value::Array(
+            CompleteType::String,
+            vec![Value::Int32(1)],
+        )))],
+    };
+    assert!(MessageEncoder::encode(&message).is_err());
+}
@@ -0,0 +1,189 @@
+use crate::types::Message;
+use std::{borrow::Cow, fmt};
+
+/// A match rule for signals, as passed to `AddMatch`, or for any message
+/// with `all_types`, as monitors use.
+///
+/// Unset fields match anything. The bus resolves a well-known `sender` to
+/// its current owner, while messages carry the owner's unique name, so
+/// `matches` only checks unique-name senders and leaves the others to the
+/// bus. Two rules differing only by a well-known sender thus both match
+/// locally.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchRule {
+    all_types: bool,
+    sender: Option<Cow<'static, str>>,
+    destination: Option<Cow<'static, str>>,
+    path: Option<Cow<'static, str>>,
+    path_namespace: Option<Cow<'static, str>>,
+    interface: Option<Cow<'static, str>>,
+    member: Option<Cow<'static, str>>,
+}
+
+impl MatchRule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sender(mut self, sender: impl Into<Cow<'static, str>>) -> Self {
+        self.sender = Some(sender.into());
+        self
+    }
+
+    /// Matches method calls, replies and errors as well as signals.
+    pub fn all_types(mut self) -> Self {
+        self.all_types = true;
+        self
+    }
+
+    pub fn destination(mut self, destination: impl Into<Cow<'static, str>>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    pub fn path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Matches `path_namespace` and every path below it.
+    pub fn path_namespace(mut self, path_namespace: impl Into<Cow<'static, str>>) -> Self {
+        self.path_namespace = Some(path_namespace.into());
+        self
+    }
+
+    pub fn interface(mut self, interface: impl Into<Cow<'static, str>>) -> Self {
+        self.interface = Some(interface.into());
+        self
+    }
+
+    pub fn member(mut self, member: impl Into<Cow<'static, str>>) -> Self {
+        self.member = Some(member.into());
+        self
+    }
+
+    /// Whether the bus would route `message` to us because of this rule.
+    pub fn matches(&self, message: &Message) -> bool {
+        if !self.all_types && !matches!(message, Message::Signal { .. }) {
+            return false;
+        }
+        let sender = self
+            .sender
+            .as_ref()
+            .filter(|sender| sender.starts_with(':'));
+        field_matches(sender, message.sender())
+            && field_matches(self.destination.as_ref(), message.destination())
+            && field_matches(self.path.as_ref(), message.path())
+            && self.path_namespace.as_deref().is_none_or(|namespace| {
+                message
+                    .path()
+                    .is_some_and(|path| in_namespace(path, namespace))
+            })
+            && field_matches(self.interface.as_ref(), message.interface())
+            && field_matches(self.member.as_ref(), message.member())
+    }
+}
+
+fn field_matches(expected: Option<&Cow<'static, str>>, actual: Option<&str>) -> bool {
+    match expected {
+        Some(expected) => actual == Some(expected.as_ref()),
+        None => true,
+    }
+}
+
+fn in_namespace(path: &str, namespace: &str) -> bool {
+    namespace == "/"
+        || path
+            .strip_prefix(namespace)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        if !self.all_types {
+            write!(f, "type='signal'")?;
+            separator = ",";
+        }
+        for (key, value) in [
+            ("sender", &self.sender),
+            ("destination", &self.destination),
+            ("interface", &self.interface),
+            ("member", &self.member),
+            ("path", &self.path),
+            ("path_namespace", &self.path_namespace),
+        ] {
+            if let Some(value) = value {
+                // Quotes can't be escaped inside a quoted value, only between them
+                write!(f, "{separator}{key}='{}'", value.replace('\'', r"'\''"))?;
+                separator = ",";
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_match_rule() {
+    let signal = |sender: Option<&'static str>, member: &'static str| Message::Signal {
+        serial: 1,
+        path: "/a".into(),
+        interface: "a.b".into(),
+        member: member.into(),
+        destination: None,
+        sender: sender.map(Cow::Borrowed),
+        unix_fds: None,
+        body: vec![],
+    };
+
+    let rule = MatchRule::new().interface("a.b").member("C");
+    assert_eq!(rule.to_string(), "type='signal',interface='a.b',member='C'");
+    assert!(rule.matches(&signal(None, "C")));
+    assert!(!rule.matches(&signal(None, "D")));
+    assert!(MatchRule::new().matches(&signal(None, "D")));
+
+    let rule = MatchRule::new().sender(":1.5");
+    assert!(rule.matches(&signal(Some(":1.5"), "C")));
+    assert!(!rule.matches(&signal(Some(":1.6"), "C")));
+    assert!(!rule.matches(&signal(None, "C")));
+    // The bus already checked that :1.5 owns org.me
+    let rule = MatchRule::new().sender("org.me").member("C");
+    assert!(rule.matches(&signal(Some(":1.5"), "C")));
+    assert!(!rule.matches(&signal(Some(":1.5"), "D")));
+
+    let rule = MatchRule::new().path_namespace("/a");
+    assert_eq!(rule.to_string(), "type='signal',path_namespace='/a'");
+    assert!(rule.matches(&signal(None, "C")));
+    assert!(
+        !MatchRule::new()
+            .path_namespace("/a/b")
+            .matches(&signal(None, "C"))
+    );
+    assert!(
+        !MatchRule::new()
+            .path_namespace("/")
+            .path("/b")
+            .matches(&signal(None, "C"))
+    );
+
+    let rule = MatchRule::new().all_types().destination(":1.5");
+    assert_eq!(rule.to_string(), "destination=':1.5'");
+    assert!(!rule.matches(&signal(None, "C")));
+    let reply = Message::MethodReturn {
+        serial: 2,
+        reply_serial: 1,
+        destination: Some(":1.5".into()),
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    assert!(rule.matches(&reply));
+    assert!(!rule.clone().path("/a").matches(&reply));
+    assert!(!MatchRule::new().destination(":1.5").matches(&reply));
+    assert_eq!(MatchRule::new().all_types().to_string(), "");
+
+    assert_eq!(
+        MatchRule::new().path("/it's").to_string(),
+        r"type='signal',path='/it'\''s'"
+    );
+}
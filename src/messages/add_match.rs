@@ -1,13 +1,28 @@
-use crate::types::{Message, Value};
+use crate::{
+    match_rule::MatchRule,
+    types::{Message, Value},
+};
 use std::borrow::Cow;
 
 pub struct AddMatch {
-    path: Cow<'static, str>,
+    rule: String,
 }
 
 impl AddMatch {
+    /// Matches `PropertiesChanged` signals of the object at `path`.
     pub fn new(path: Cow<'static, str>) -> Self {
-        Self { path }
+        Self::rule(
+            &MatchRule::new()
+                .interface("org.freedesktop.DBus.Properties")
+                .member("PropertiesChanged")
+                .path(path),
+        )
+    }
+
+    pub fn rule(rule: &MatchRule) -> Self {
+        Self {
+            rule: rule.to_string(),
+        }
     }
 }
 
@@ -21,10 +36,8 @@ impl From<AddMatch> for Message {
             destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
             sender: None,
             unix_fds: None,
-            body: vec![Value::String(format!(
-                "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',path='{}'",
-                value.path
-            ))],
+            no_reply_expected: false,
+            body: vec![Value::String(value.rule)],
         }
     }
 }
@@ -0,0 +1,37 @@
+use crate::{
+    match_rule::MatchRule,
+    types::{CompleteType, Message, Value},
+};
+use std::borrow::Cow;
+
+/// Turns the connection into a monitor receiving every message matching
+/// `rules`, or every message on the bus if there are none. The bus drops
+/// the connection if it sends anything afterwards.
+pub struct BecomeMonitor {
+    rules: Vec<String>,
+}
+
+impl BecomeMonitor {
+    pub fn new(rules: &[MatchRule]) -> Self {
+        Self {
+            rules: rules.iter().map(MatchRule::to_string).collect(),
+        }
+    }
+}
+
+impl From<BecomeMonitor> for Message {
+    fn from(value: BecomeMonitor) -> Message {
+        let rules = value.rules.into_iter().map(Value::String).collect();
+        Message::MethodCall {
+            serial: 0,
+            path: Cow::Borrowed("/org/freedesktop/DBus"),
+            member: Cow::Borrowed("BecomeMonitor"),
+            interface: Some(Cow::Borrowed("org.freedesktop.DBus.Monitoring")),
+            destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
+            sender: None,
+            unix_fds: None,
+            no_reply_expected: false,
+            body: vec![Value::Array(CompleteType::String, rules), Value::UInt32(0)],
+        }
+    }
+}
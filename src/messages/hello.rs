@@ -13,6 +13,7 @@ impl From<Hello> for Message {
             destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
             sender: None,
             unix_fds: None,
+            no_reply_expected: false,
             body: vec![],
         }
     }
@@ -10,12 +10,32 @@ pub use properties_changed::PropertiesChanged;
 mod add_match;
 pub use add_match::AddMatch;
 
+mod remove_match;
+pub use remove_match::RemoveMatch;
+
+mod become_monitor;
+pub use become_monitor::BecomeMonitor;
+
 mod request_name;
 pub use request_name::RequestName;
 
 mod introspect;
 pub use introspect::{IntrospectRequest, IntrospectResponse};
 
+mod peer;
+pub(crate) use peer::PEER;
+pub use peer::{GetMachineIdRequest, GetMachineIdResponse, PingRequest, PingResponse};
+
+mod object_manager;
+pub(crate) use object_manager::OBJECT_MANAGER;
+pub use object_manager::{
+    GetManagedObjects, Interfaces, InterfacesAdded, InterfacesRemoved, ManagedObjects,
+};
+
+mod properties;
+pub(crate) use properties::PROPERTIES;
+pub use properties::{GetAllProperties, GetProperty, SetProperty};
+
 mod show_notification;
 pub use show_notification::ShowNotification;
 
@@ -0,0 +1,300 @@
+use crate::{
+    body_is, interface_is, member_is, message_is,
+    types::{CompleteType, Message, Value},
+    value_is,
+};
+use anyhow::Result;
+use std::{borrow::Cow, collections::BTreeMap};
+
+pub(crate) const OBJECT_MANAGER: &str = "org.freedesktop.DBus.ObjectManager";
+
+/// Properties by name for each interface of an object, `a{sa{sv}}` with
+/// the variants unwrapped.
+pub type Interfaces = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// Asks the object manager at `path` for everything below it.
+pub struct GetManagedObjects<'a> {
+    pub destination: Cow<'a, str>,
+    pub path: Cow<'a, str>,
+}
+
+impl<'a> GetManagedObjects<'a> {
+    pub fn new(destination: impl Into<Cow<'a, str>>, path: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            destination: destination.into(),
+            path: path.into(),
+        }
+    }
+}
+
+impl<'a> From<GetManagedObjects<'a>> for Message {
+    fn from(value: GetManagedObjects<'a>) -> Message {
+        Message::MethodCall {
+            serial: 0,
+            path: Cow::Owned(value.path.into_owned()),
+            member: Cow::Borrowed("GetManagedObjects"),
+            interface: Some(Cow::Borrowed(OBJECT_MANAGER)),
+            destination: Some(Cow::Owned(value.destination.into_owned())),
+            sender: None,
+            unix_fds: None,
+            no_reply_expected: false,
+            body: vec![],
+        }
+    }
+}
+
+/// The reply to `GetManagedObjects`, `a{oa{sa{sv}}}`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ManagedObjects {
+    pub objects: BTreeMap<String, Interfaces>,
+}
+
+impl TryFrom<&Message> for ManagedObjects {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &Message) -> Result<Self> {
+        message_is!(message, Message::MethodReturn { body, .. });
+        body_is!(body, [objects]);
+
+        let mut out = Self::default();
+        for (path, interfaces) in dict_entries(objects)? {
+            value_is!(path, Value::ObjectPath(path));
+            out.objects
+                .insert(path.to_string(), parse_interfaces(interfaces)?);
+        }
+        Ok(out)
+    }
+}
+
+impl ManagedObjects {
+    pub(crate) fn to_value(&self) -> Value {
+        let entry_type = CompleteType::DictEntry(
+            Box::new(CompleteType::ObjectPath),
+            Box::new(CompleteType::Array(Box::new(interfaces_entry_type()))),
+        );
+        let entries = self
+            .objects
+            .iter()
+            .map(|(path, interfaces)| {
+                Value::DictEntry(
+                    Box::new(Value::ObjectPath(Cow::Owned(path.clone()))),
+                    Box::new(interfaces_value(interfaces)),
+                )
+            })
+            .collect();
+        Value::Array(entry_type, entries)
+    }
+}
+
+/// Sent by the object manager at `manager` when `object` gains interfaces.
+#[derive(Debug, PartialEq)]
+pub struct InterfacesAdded {
+    pub manager: String,
+    pub object: String,
+    pub interfaces: Interfaces,
+}
+
+impl TryFrom<&Message> for InterfacesAdded {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &Message) -> Result<Self> {
+        message_is!(
+            message,
+            Message::Signal {
+                path,
+                interface,
+                member,
+                body,
+                ..
+            }
+        );
+
+        interface_is!(interface, OBJECT_MANAGER);
+        member_is!(member, "InterfacesAdded");
+        body_is!(body, [Value::ObjectPath(object), interfaces]);
+
+        Ok(Self {
+            manager: path.to_string(),
+            object: object.to_string(),
+            interfaces: parse_interfaces(interfaces)?,
+        })
+    }
+}
+
+impl From<InterfacesAdded> for Message {
+    fn from(value: InterfacesAdded) -> Message {
+        Message::Signal {
+            serial: 0,
+            path: Cow::Owned(value.manager),
+            interface: Cow::Borrowed(OBJECT_MANAGER),
+            member: Cow::Borrowed("InterfacesAdded"),
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![
+                Value::ObjectPath(Cow::Owned(value.object)),
+                interfaces_value(&value.interfaces),
+            ],
+        }
+    }
+}
+
+/// Sent by the object manager at `manager` when `object` loses interfaces.
+#[derive(Debug, PartialEq)]
+pub struct InterfacesRemoved {
+    pub manager: String,
+    pub object: String,
+    pub interfaces: Vec<String>,
+}
+
+impl TryFrom<&Message> for InterfacesRemoved {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &Message) -> Result<Self> {
+        message_is!(
+            message,
+            Message::Signal {
+                path,
+                interface,
+                member,
+                body,
+                ..
+            }
+        );
+
+        interface_is!(interface, OBJECT_MANAGER);
+        member_is!(member, "InterfacesRemoved");
+        body_is!(body, [Value::ObjectPath(object), interfaces]);
+
+        Ok(Self {
+            manager: path.to_string(),
+            object: object.to_string(),
+            interfaces: Vec::<String>::try_from(interfaces.clone())?,
+        })
+    }
+}
+
+impl From<InterfacesRemoved> for Message {
+    fn from(value: InterfacesRemoved) -> Message {
+        Message::Signal {
+            serial: 0,
+            path: Cow::Owned(value.manager),
+            interface: Cow::Borrowed(OBJECT_MANAGER),
+            member: Cow::Borrowed("InterfacesRemoved"),
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![
+                Value::ObjectPath(Cow::Owned(value.object)),
+                Value::from(value.interfaces),
+            ],
+        }
+    }
+}
+
+fn interfaces_entry_type() -> CompleteType {
+    CompleteType::DictEntry(
+        Box::new(CompleteType::String),
+        Box::new(CompleteType::Array(Box::new(properties_entry_type()))),
+    )
+}
+
+fn properties_entry_type() -> CompleteType {
+    CompleteType::DictEntry(
+        Box::new(CompleteType::String),
+        Box::new(CompleteType::Variant),
+    )
+}
+
+fn interfaces_value(interfaces: &Interfaces) -> Value {
+    let entries = interfaces
+        .iter()
+        .map(|(name, properties)| {
+            let properties = properties
+                .iter()
+                .map(|(name, value)| {
+                    Value::DictEntry(
+                        Box::new(Value::String(name.clone())),
+                        Box::new(Value::Variant(Box::new(value.clone()))),
+                    )
+                })
+                .collect();
+            Value::DictEntry(
+                Box::new(Value::String(name.clone())),
+                Box::new(Value::Array(properties_entry_type(), properties)),
+            )
+        })
+        .collect();
+    Value::Array(interfaces_entry_type(), entries)
+}
+
+fn parse_interfaces(value: &Value) -> Result<Interfaces> {
+    let mut out = Interfaces::new();
+    for (name, properties) in dict_entries(value)? {
+        value_is!(name, Value::String(name));
+        let mut parsed = BTreeMap::new();
+        for (property, value) in dict_entries(properties)? {
+            value_is!(property, Value::String(property));
+            value_is!(value, Value::Variant(value));
+            parsed.insert(property.clone(), (**value).clone());
+        }
+        out.insert(name.clone(), parsed);
+    }
+    Ok(out)
+}
+
+fn dict_entries(value: &Value) -> Result<Vec<(&Value, &Value)>> {
+    value_is!(value, Value::Array(CompleteType::DictEntry(..), entries));
+    entries
+        .iter()
+        .map(|entry| {
+            value_is!(entry, Value::DictEntry(key, value));
+            Ok((&**key, &**value))
+        })
+        .collect()
+}
+
+#[test]
+fn test_object_manager_messages() {
+    let interfaces = Interfaces::from([(
+        String::from("org.bluez.Device1"),
+        BTreeMap::from([
+            (String::from("Name"), Value::String("Speaker".into())),
+            (String::from("RSSI"), Value::Int16(-60)),
+        ]),
+    )]);
+
+    let added = InterfacesAdded {
+        manager: "/".into(),
+        object: "/org/bluez/hci0/dev_1".into(),
+        interfaces: interfaces.clone(),
+    };
+    let message = Message::from(added);
+    assert_eq!(message.body()[1].complete_type().to_string(), "a{sa{sv}}");
+    let parsed = InterfacesAdded::try_from(&message).unwrap();
+    assert_eq!(parsed.object, "/org/bluez/hci0/dev_1");
+    assert_eq!(parsed.interfaces, interfaces);
+    assert!(InterfacesRemoved::try_from(&message).is_err());
+
+    let removed = Message::from(InterfacesRemoved {
+        manager: "/".into(),
+        object: "/org/bluez/hci0/dev_1".into(),
+        interfaces: vec!["org.bluez.Device1".into()],
+    });
+    let parsed = InterfacesRemoved::try_from(&removed).unwrap();
+    assert_eq!(parsed.interfaces, vec![String::from("org.bluez.Device1")]);
+
+    let objects = ManagedObjects {
+        objects: BTreeMap::from([(String::from("/org/bluez/hci0/dev_1"), interfaces)]),
+    };
+    let reply = Message::MethodReturn {
+        serial: 2,
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![objects.to_value()],
+    };
+    assert_eq!(reply.body()[0].complete_type().to_string(), "a{oa{sa{sv}}}");
+    assert_eq!(ManagedObjects::try_from(&reply).unwrap(), objects);
+}
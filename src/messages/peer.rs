@@ -0,0 +1,130 @@
+use crate::{
+    body_is, interface_is, member_is, message_is,
+    types::{Message, Value},
+};
+use anyhow::Result;
+use std::borrow::Cow;
+
+pub(crate) const PEER: &str = "org.freedesktop.DBus.Peer";
+
+/// `org.freedesktop.DBus.Peer.Ping`, on any path. `sender` is `None` on
+/// peer-to-peer connections.
+#[derive(Debug)]
+pub struct PingRequest<'a> {
+    pub serial: u32,
+    pub path: Cow<'a, str>,
+    pub sender: Option<Cow<'a, str>>,
+}
+
+impl<'a> TryFrom<&'a Message> for PingRequest<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &'a Message) -> Result<Self> {
+        let (serial, path, sender) = peer_call(message, "Ping")?;
+        Ok(Self {
+            serial,
+            path,
+            sender,
+        })
+    }
+}
+
+pub struct PingResponse<'a> {
+    req: PingRequest<'a>,
+}
+
+impl<'a> PingResponse<'a> {
+    pub fn new(req: PingRequest<'a>) -> Self {
+        Self { req }
+    }
+}
+
+impl<'a> From<PingResponse<'a>> for Message {
+    fn from(value: PingResponse<'a>) -> Message {
+        reply(value.req.serial, value.req.sender, vec![])
+    }
+}
+
+/// `org.freedesktop.DBus.Peer.GetMachineId`, on any path.
+#[derive(Debug)]
+pub struct GetMachineIdRequest<'a> {
+    pub serial: u32,
+    pub path: Cow<'a, str>,
+    pub sender: Option<Cow<'a, str>>,
+}
+
+impl<'a> TryFrom<&'a Message> for GetMachineIdRequest<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(message: &'a Message) -> Result<Self> {
+        let (serial, path, sender) = peer_call(message, "GetMachineId")?;
+        Ok(Self {
+            serial,
+            path,
+            sender,
+        })
+    }
+}
+
+pub struct GetMachineIdResponse<'a> {
+    req: GetMachineIdRequest<'a>,
+    machine_id: String,
+}
+
+impl<'a> GetMachineIdResponse<'a> {
+    pub fn new(req: GetMachineIdRequest<'a>, machine_id: impl Into<String>) -> Self {
+        Self {
+            req,
+            machine_id: machine_id.into(),
+        }
+    }
+}
+
+impl<'a> From<GetMachineIdResponse<'a>> for Message {
+    fn from(value: GetMachineIdResponse<'a>) -> Message {
+        reply(
+            value.req.serial,
+            value.req.sender,
+            vec![Value::String(value.machine_id)],
+        )
+    }
+}
+
+fn peer_call<'a>(
+    message: &'a Message,
+    expected: &str,
+) -> Result<(u32, Cow<'a, str>, Option<Cow<'a, str>>)> {
+    message_is!(
+        message,
+        Message::MethodCall {
+            serial,
+            path,
+            member,
+            interface: Some(interface),
+            sender,
+            body,
+            ..
+        }
+    );
+
+    interface_is!(interface, PEER);
+    member_is!(member, expected);
+    body_is!(body, []);
+
+    Ok((
+        *serial,
+        Cow::Borrowed(path.as_ref()),
+        sender.as_deref().map(Cow::Borrowed),
+    ))
+}
+
+fn reply(reply_serial: u32, destination: Option<Cow<'_, str>>, body: Vec<Value>) -> Message {
+    Message::MethodReturn {
+        serial: 0,
+        reply_serial,
+        destination: destination.map(|destination| Cow::Owned(destination.into_owned())),
+        sender: None,
+        unix_fds: None,
+        body,
+    }
+}
@@ -0,0 +1,87 @@
+use crate::types::{Message, Value};
+use std::borrow::Cow;
+
+pub(crate) const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// `org.freedesktop.DBus.Properties.Get`, replied with a variant.
+pub struct GetProperty<'a> {
+    pub destination: Cow<'a, str>,
+    pub path: Cow<'a, str>,
+    pub interface: Cow<'a, str>,
+    pub name: Cow<'a, str>,
+}
+
+impl<'a> From<GetProperty<'a>> for Message {
+    fn from(value: GetProperty<'a>) -> Message {
+        properties_call(
+            value.destination,
+            value.path,
+            "Get",
+            vec![
+                Value::String(value.interface.into_owned()),
+                Value::String(value.name.into_owned()),
+            ],
+        )
+    }
+}
+
+/// `org.freedesktop.DBus.Properties.Set`, `value` is wrapped in a variant.
+pub struct SetProperty<'a> {
+    pub destination: Cow<'a, str>,
+    pub path: Cow<'a, str>,
+    pub interface: Cow<'a, str>,
+    pub name: Cow<'a, str>,
+    pub value: Value,
+}
+
+impl<'a> From<SetProperty<'a>> for Message {
+    fn from(value: SetProperty<'a>) -> Message {
+        properties_call(
+            value.destination,
+            value.path,
+            "Set",
+            vec![
+                Value::String(value.interface.into_owned()),
+                Value::String(value.name.into_owned()),
+                Value::Variant(Box::new(value.value)),
+            ],
+        )
+    }
+}
+
+/// `org.freedesktop.DBus.Properties.GetAll`, replied with `a{sv}`.
+pub struct GetAllProperties<'a> {
+    pub destination: Cow<'a, str>,
+    pub path: Cow<'a, str>,
+    pub interface: Cow<'a, str>,
+}
+
+impl<'a> From<GetAllProperties<'a>> for Message {
+    fn from(value: GetAllProperties<'a>) -> Message {
+        properties_call(
+            value.destination,
+            value.path,
+            "GetAll",
+            vec![Value::String(value.interface.into_owned())],
+        )
+    }
+}
+
+fn properties_call(
+    destination: Cow<'_, str>,
+    path: Cow<'_, str>,
+    member: &'static str,
+    body: Vec<Value>,
+) -> Message {
+    Message::MethodCall {
+        serial: 0,
+        path: Cow::Owned(path.into_owned()),
+        member: Cow::Borrowed(member),
+        interface: Some(Cow::Borrowed(PROPERTIES)),
+        destination: Some(Cow::Owned(destination.into_owned())),
+        sender: None,
+        unix_fds: None,
+        no_reply_expected: false,
+        body,
+    }
+}
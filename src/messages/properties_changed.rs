@@ -11,6 +11,8 @@ pub struct PropertiesChanged<'a> {
     pub path: Cow<'a, str>,
     pub interface: Cow<'a, str>,
     pub changes: HashMap<Cow<'a, str>, Value>,
+    /// Properties that changed without their new value being sent
+    pub invalidated: Vec<Cow<'a, str>>,
 }
 
 impl<'a> TryFrom<&'a Message> for PropertiesChanged<'a> {
@@ -30,11 +32,16 @@ impl<'a> TryFrom<&'a Message> for PropertiesChanged<'a> {
         interface_is!(interface, "org.freedesktop.DBus.Properties");
         body_is!(
             body,
-            [Value::String(interface), Value::Array(item_t, items), _]
+            [
+                Value::String(interface),
+                Value::Array(item_t, items),
+                Value::Array(invalidated_t, invalidated)
+            ]
         );
         type_is!(item_t, CompleteType::DictEntry(key_t, value_t));
         type_is!(&**key_t, CompleteType::String);
         type_is!(&**value_t, CompleteType::Variant);
+        type_is!(invalidated_t, CompleteType::String);
 
         let mut changes = HashMap::new();
         for item in items {
@@ -44,10 +51,19 @@ impl<'a> TryFrom<&'a Message> for PropertiesChanged<'a> {
             changes.insert(Cow::Borrowed(key.as_str()), *value.clone());
         }
 
+        let invalidated = invalidated
+            .iter()
+            .map(|name| {
+                value_is!(name, Value::String(name));
+                Ok(Cow::Borrowed(name.as_str()))
+            })
+            .collect::<Result<_>>()?;
+
         Ok(Self {
             path: path.clone(),
             interface: Cow::Borrowed(interface),
             changes,
+            invalidated,
         })
     }
 }
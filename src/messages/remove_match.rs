@@ -0,0 +1,33 @@
+use crate::{
+    match_rule::MatchRule,
+    types::{Message, Value},
+};
+use std::borrow::Cow;
+
+pub struct RemoveMatch {
+    rule: String,
+}
+
+impl RemoveMatch {
+    pub fn rule(rule: &MatchRule) -> Self {
+        Self {
+            rule: rule.to_string(),
+        }
+    }
+}
+
+impl From<RemoveMatch> for Message {
+    fn from(value: RemoveMatch) -> Message {
+        Message::MethodCall {
+            serial: 0,
+            path: Cow::Borrowed("/org/freedesktop/DBus"),
+            member: Cow::Borrowed("RemoveMatch"),
+            interface: Some(Cow::Borrowed("org.freedesktop.DBus")),
+            destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
+            sender: None,
+            unix_fds: None,
+            no_reply_expected: false,
+            body: vec![Value::String(value.rule)],
+        }
+    }
+}
@@ -21,6 +21,7 @@ impl From<RequestName> for Message {
             destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
             sender: None,
             unix_fds: None,
+            no_reply_expected: false,
             body: vec![Value::String(value.name.to_string()), Value::UInt32(7)],
         }
     }
@@ -1,5 +1,5 @@
-use crate::types::{CompleteType, Message, Value};
-use std::borrow::Cow;
+use crate::types::{Message, Value, Variant};
+use std::{borrow::Cow, collections::HashMap};
 
 pub struct ShowNotification {
     pub header: String,
@@ -25,20 +25,15 @@ impl From<ShowNotification> for Message {
             destination: Some(Cow::Borrowed("org.freedesktop.Notifications")),
             sender: None,
             unix_fds: None,
+            no_reply_expected: false,
             body: vec![
                 Value::String(String::from("")),
                 Value::UInt32(1),
                 Value::String(String::from("")),
                 Value::String(value.header),
                 Value::String(value.body),
-                Value::Array(CompleteType::String, vec![]),
-                Value::Array(
-                    CompleteType::DictEntry(
-                        Box::new(CompleteType::String),
-                        Box::new(CompleteType::Variant),
-                    ),
-                    vec![],
-                ),
+                Value::from(Vec::<String>::new()),
+                Value::from(HashMap::<String, Variant>::new()),
                 Value::Int32(1_000),
             ],
         }
@@ -0,0 +1,215 @@
+use crate::{
+    match_rule::MatchRule,
+    messages::{
+        GetManagedObjects, Interfaces, InterfacesAdded, InterfacesRemoved, ManagedObjects,
+        OBJECT_MANAGER, PROPERTIES, PropertiesChanged,
+    },
+    types::Message,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// A client-side copy of the objects exported below an
+/// `org.freedesktop.DBus.ObjectManager`, kept in sync from its signals.
+///
+/// Add the `match_rules` first, then enqueue `request` and pass its reply
+/// to `load`. Every incoming signal can be passed to `update`. Signals are
+/// not checked against `destination`: it is usually a well-known name while
+/// signals carry the owner's unique name, so the match rules are what keeps
+/// other senders out.
+#[derive(Debug)]
+pub struct ObjectCache {
+    destination: String,
+    manager: String,
+    objects: BTreeMap<String, Interfaces>,
+}
+
+impl ObjectCache {
+    pub fn new(destination: impl Into<String>, manager: impl Into<String>) -> Self {
+        Self {
+            destination: destination.into(),
+            manager: manager.into(),
+            objects: BTreeMap::new(),
+        }
+    }
+
+    /// Rules for `InterfacesAdded`/`InterfacesRemoved` and for property
+    /// changes below the manager.
+    pub fn match_rules(&self) -> Vec<MatchRule> {
+        vec![
+            MatchRule::new()
+                .sender(self.destination.clone())
+                .path(self.manager.clone())
+                .interface(OBJECT_MANAGER),
+            MatchRule::new()
+                .sender(self.destination.clone())
+                .path_namespace(self.manager.clone())
+                .interface(PROPERTIES)
+                .member("PropertiesChanged"),
+        ]
+    }
+
+    /// The `GetManagedObjects` call whose reply goes to `load`.
+    pub fn request(&self) -> Message {
+        GetManagedObjects::new(self.destination.clone(), self.manager.clone()).into()
+    }
+
+    /// Replaces the cached tree with the reply to `request`.
+    pub fn load(&mut self, reply: &Message) -> Result<()> {
+        self.objects = ManagedObjects::try_from(reply)?.objects;
+        Ok(())
+    }
+
+    /// Applies `message` if it's a signal about the cached tree, returns
+    /// `true` if anything changed.
+    pub fn update(&mut self, message: &Message) -> bool {
+        if let Ok(added) = InterfacesAdded::try_from(message) {
+            if added.manager != self.manager {
+                return false;
+            }
+            let object = self.objects.entry(added.object).or_default();
+            object.extend(added.interfaces);
+            return true;
+        }
+
+        if let Ok(removed) = InterfacesRemoved::try_from(message) {
+            if removed.manager != self.manager {
+                return false;
+            }
+            let Some(object) = self.objects.get_mut(&removed.object) else {
+                return false;
+            };
+            for interface in &removed.interfaces {
+                object.remove(interface);
+            }
+            if object.is_empty() {
+                self.objects.remove(&removed.object);
+            }
+            return true;
+        }
+
+        let Ok(changed) = PropertiesChanged::try_from(message) else {
+            return false;
+        };
+        let Some(properties) = self
+            .objects
+            .get_mut(changed.path.as_ref())
+            .and_then(|object| object.get_mut(changed.interface.as_ref()))
+        else {
+            return false;
+        };
+        for (name, value) in changed.changes {
+            properties.insert(name.into_owned(), value);
+        }
+        for name in changed.invalidated {
+            properties.remove(name.as_ref());
+        }
+        true
+    }
+
+    pub fn objects(&self) -> &BTreeMap<String, Interfaces> {
+        &self.objects
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Interfaces> {
+        self.objects.get(path)
+    }
+}
+
+#[test]
+fn test_object_cache() {
+    use crate::types::{CompleteType, Value};
+    use std::borrow::Cow;
+
+    let device = |rssi| {
+        Interfaces::from([(
+            String::from("org.bluez.Device1"),
+            BTreeMap::from([(String::from("RSSI"), Value::Int16(rssi))]),
+        )])
+    };
+
+    let mut cache = ObjectCache::new("org.bluez", "/");
+    assert_eq!(
+        cache.match_rules()[0].to_string(),
+        "type='signal',sender='org.bluez',interface='org.freedesktop.DBus.ObjectManager',path='/'"
+    );
+    let Message::MethodCall { member, .. } = cache.request() else {
+        unreachable!()
+    };
+    assert_eq!(member, "GetManagedObjects");
+
+    let objects = ManagedObjects {
+        objects: BTreeMap::from([(String::from("/org/bluez/hci0/dev_1"), device(-60))]),
+    };
+    cache
+        .load(&Message::MethodReturn {
+            serial: 2,
+            reply_serial: 1,
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![objects.to_value()],
+        })
+        .unwrap();
+    assert_eq!(cache.objects(), &objects.objects);
+
+    assert!(cache.update(&Message::from(InterfacesAdded {
+        manager: "/".into(),
+        object: "/org/bluez/hci0/dev_2".into(),
+        interfaces: device(-70),
+    })));
+    assert!(!cache.update(&Message::from(InterfacesAdded {
+        manager: "/other".into(),
+        object: "/org/bluez/hci0/dev_3".into(),
+        interfaces: device(-80),
+    })));
+    assert_eq!(cache.objects().len(), 2);
+
+    let changed = |changes, invalidated: &[&str]| Message::Signal {
+        serial: 3,
+        path: Cow::Borrowed("/org/bluez/hci0/dev_2"),
+        interface: Cow::Borrowed("org.freedesktop.DBus.Properties"),
+        member: Cow::Borrowed("PropertiesChanged"),
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![
+            Value::String("org.bluez.Device1".into()),
+            Value::Array(
+                CompleteType::DictEntry(
+                    Box::new(CompleteType::String),
+                    Box::new(CompleteType::Variant),
+                ),
+                changes,
+            ),
+            Value::from(
+                invalidated
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+        ],
+    };
+    let rssi = Value::DictEntry(
+        Box::new(Value::String("RSSI".into())),
+        Box::new(Value::Variant(Box::new(Value::Int16(-50)))),
+    );
+    assert!(cache.update(&changed(vec![rssi], &[])));
+    assert_eq!(cache.get("/org/bluez/hci0/dev_2"), Some(&device(-50)));
+    assert!(cache.update(&changed(vec![], &["RSSI"])));
+    assert_eq!(
+        cache.get("/org/bluez/hci0/dev_2"),
+        Some(&Interfaces::from([(
+            String::from("org.bluez.Device1"),
+            BTreeMap::new()
+        )]))
+    );
+
+    assert!(cache.update(&Message::from(InterfacesRemoved {
+        manager: "/".into(),
+        object: "/org/bluez/hci0/dev_1".into(),
+        interfaces: vec!["org.bluez.Device1".into()],
+    })));
+    assert_eq!(cache.get("/org/bluez/hci0/dev_1"), None);
+    assert_eq!(cache.objects().len(), 1);
+}
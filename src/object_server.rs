@@ -0,0 +1,768 @@
+use crate::{
+    introspection::{self, Arg, Direction, Method, Node, Signal},
+    messages::{InterfacesAdded, InterfacesRemoved, ManagedObjects, OBJECT_MANAGER, PEER},
+    peer::PeerResponder,
+    signal::SignalBuilder,
+    types::{CompleteType, Message, Value},
+};
+use anyhow::{Context, Result, bail};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+const INTROSPECTABLE: &str = "org.freedesktop.DBus.Introspectable";
+
+/// Implements one interface of an exported object.
+pub trait Handler {
+    /// Only methods listed here are passed to `call`, and only with
+    /// arguments matching their signature.
+    fn introspect(&self) -> introspection::Interface;
+
+    /// Signals emitted to `signals` are sent after the reply, or dropped if
+    /// the call fails.
+    fn call(
+        &mut self,
+        member: &str,
+        args: &[Value],
+        signals: &mut Signals,
+    ) -> Result<Vec<Value>, MethodError>;
+
+    /// Current property values, reported to object managers.
+    fn properties(&self) -> BTreeMap<String, Value> {
+        BTreeMap::new()
+    }
+}
+
+/// An error reply, `name` is the D-Bus error name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodError {
+    pub name: String,
+    pub message: String,
+}
+
+impl MethodError {
+    pub fn new(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::new("org.freedesktop.DBus.Error.Failed", message)
+    }
+
+    pub fn invalid_args(message: impl Into<String>) -> Self {
+        Self::new("org.freedesktop.DBus.Error.InvalidArgs", message)
+    }
+
+    fn unknown_object(path: &str) -> Self {
+        Self::new(
+            "org.freedesktop.DBus.Error.UnknownObject",
+            format!("no object at {path}"),
+        )
+    }
+
+    fn unknown_interface(path: &str, interface: &str) -> Self {
+        Self::new(
+            "org.freedesktop.DBus.Error.UnknownInterface",
+            format!("{path} has no interface {interface}"),
+        )
+    }
+
+    fn unknown_method(path: &str, member: &str) -> Self {
+        Self::new(
+            "org.freedesktop.DBus.Error.UnknownMethod",
+            format!("{path} has no method {member}"),
+        )
+    }
+}
+
+/// Lets handlers use `?` on anything returning `anyhow::Result`.
+impl From<anyhow::Error> for MethodError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::failed(format!("{err:#}"))
+    }
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+/// Signals emitted by a `Handler` while handling a call.
+pub struct Signals<'a> {
+    path: &'a str,
+    interface: &'a introspection::Interface,
+    caller: Option<&'a str>,
+    queued: Vec<Message>,
+}
+
+impl Signals<'_> {
+    /// The path of the called object.
+    pub fn path(&self) -> &str {
+        self.path
+    }
+
+    /// The unique name of the caller, if called through a bus.
+    pub fn caller(&self) -> Option<&str> {
+        self.caller
+    }
+
+    /// Broadcasts `member` of the handler's interface from the called
+    /// object, checked against its introspection.
+    pub fn emit(&mut self, member: &str, args: Vec<Value>) -> Result<()> {
+        let signal = self
+            .interface
+            .signal(member)
+            .with_context(|| format!("{} has no signal {member}", self.interface.name))?;
+        let builder = SignalBuilder::new(
+            self.path.to_string(),
+            self.interface.name.clone(),
+            member.to_string(),
+        )
+        .args(args);
+        builder.validate(signal)?;
+        self.push(builder);
+        Ok(())
+    }
+
+    /// Sends any signal, e.g. a unicast one to the `caller`.
+    pub fn push(&mut self, signal: impl Into<Message>) {
+        self.queued.push(signal.into());
+    }
+}
+
+struct Registered {
+    interface: introspection::Interface,
+    handler: Box<dyn Handler>,
+}
+
+/// Dispatches incoming method calls to handlers registered per object
+/// path and interface.
+///
+/// It does no I/O: the application passes every message it reads to
+/// `dispatch` and enqueues the replies it returns. Unknown objects,
+/// interfaces and methods get the standard errors, and
+/// `org.freedesktop.DBus.Peer` and `org.freedesktop.DBus.Introspectable`
+/// are answered for every object.
+///
+/// Paths added with `add_object_manager` also implement
+/// `org.freedesktop.DBus.ObjectManager` for the objects below them. Their
+/// `InterfacesAdded` and `InterfacesRemoved` signals are queued by `add` and
+/// `remove` and collected with `take_signals`.
+#[derive(Default)]
+pub struct ObjectServer {
+    objects: BTreeMap<String, Vec<Registered>>,
+    managers: BTreeSet<String>,
+    signals: Vec<Message>,
+    peer: PeerResponder,
+}
+
+impl ObjectServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `GetMachineId` from `peer` instead of `/etc/machine-id`.
+    pub fn with_peer(mut self, peer: PeerResponder) -> Self {
+        self.peer = peer;
+        self
+    }
+
+    /// Exports `org.freedesktop.DBus.ObjectManager` at `path`.
+    pub fn add_object_manager(&mut self, path: impl Into<String>) {
+        self.managers.insert(path.into());
+    }
+
+    /// Exports `handler` at `path`, which must not have its interface yet.
+    pub fn add(&mut self, path: impl Into<String>, handler: impl Handler + 'static) -> Result<()> {
+        let path = path.into();
+        let interface = handler.introspect();
+        let exported = self.objects.get(&path).is_some_and(|object| {
+            object
+                .iter()
+                .any(|known| known.interface.name == interface.name)
+        });
+        if exported {
+            bail!("{} is already exported", interface.name);
+        }
+
+        if let Some(manager) = self.manager_of(&path) {
+            let properties = handler.properties();
+            let added = InterfacesAdded {
+                manager,
+                object: path.clone(),
+                interfaces: BTreeMap::from([(interface.name.clone(), properties)]),
+            };
+            self.signals.push(added.into());
+        }
+        self.objects.entry(path).or_default().push(Registered {
+            interface,
+            handler: Box::new(handler),
+        });
+        Ok(())
+    }
+
+    /// Returns `false` if `interface` wasn't exported at `path`.
+    pub fn remove(&mut self, path: &str, interface: &str) -> bool {
+        let Some(object) = self.objects.get_mut(path) else {
+            return false;
+        };
+        let len = object.len();
+        object.retain(|known| known.interface.name != interface);
+        let removed = object.len() != len;
+        if object.is_empty() {
+            self.objects.remove(path);
+        }
+        if !removed {
+            return false;
+        }
+
+        if let Some(manager) = self.manager_of(path) {
+            let removed = InterfacesRemoved {
+                manager,
+                object: path.to_string(),
+                interfaces: vec![interface.to_string()],
+            };
+            self.signals.push(removed.into());
+        }
+        true
+    }
+
+    /// Signals queued since the last call, to be enqueued by the caller.
+    pub fn take_signals(&mut self) -> Vec<Message> {
+        std::mem::take(&mut self.signals)
+    }
+
+    /// Returns the replies to enqueue for `message`, followed by the signals
+    /// its handler emitted. Anything but a method call, and calls with
+    /// `NO_REPLY_EXPECTED`, get no reply.
+    pub fn dispatch(&mut self, message: &Message) -> Vec<Message> {
+        let Message::MethodCall {
+            serial,
+            path,
+            member,
+            interface,
+            sender,
+            no_reply_expected,
+            body,
+            ..
+        } = message
+        else {
+            return vec![];
+        };
+
+        let mut signals = vec![];
+        let result = self.call(
+            path,
+            interface.as_deref(),
+            member,
+            body,
+            sender.as_deref(),
+            &mut signals,
+        );
+        if *no_reply_expected {
+            return signals;
+        }
+        let reply = match result {
+            Ok(body) => Message::MethodReturn {
+                serial: 0,
+                reply_serial: *serial,
+                destination: sender.clone(),
+                sender: None,
+                unix_fds: None,
+                body,
+            },
+            Err(err) => Message::Error {
+                serial: 0,
+                error_name: err.name,
+                reply_serial: *serial,
+                destination: sender.clone(),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::String(err.message)],
+            },
+        };
+        std::iter::once(reply).chain(signals).collect()
+    }
+
+    fn call(
+        &mut self,
+        path: &str,
+        interface: Option<&str>,
+        member: &str,
+        args: &[Value],
+        caller: Option<&str>,
+        queued: &mut Vec<Message>,
+    ) -> Result<Vec<Value>, MethodError> {
+        // Calls without an interface go to the first one with that member
+        let standard = self.standard(path).into_iter().find(|standard| {
+            interface.map_or(standard.method(member).is_some(), |name| {
+                name == standard.name
+            })
+        });
+        if let Some(standard) = standard {
+            let method = standard
+                .method(member)
+                .ok_or_else(|| MethodError::unknown_method(path, member))?;
+            validate(method, args)?;
+            return match member {
+                "Ping" => Ok(vec![]),
+                "GetMachineId" => Ok(vec![Value::String(self.peer.machine_id()?.into())]),
+                "GetManagedObjects" => Ok(vec![self.managed_objects(path).to_value()]),
+                _ => self.introspect(path).map(|xml| vec![Value::String(xml)]),
+            };
+        }
+
+        let object = self
+            .objects
+            .get_mut(path)
+            .ok_or_else(|| MethodError::unknown_object(path))?;
+        let registered = match interface {
+            Some(interface) => object
+                .iter_mut()
+                .find(|known| known.interface.name == interface)
+                .ok_or_else(|| MethodError::unknown_interface(path, interface))?,
+            None => object
+                .iter_mut()
+                .find(|known| known.interface.method(member).is_some())
+                .ok_or_else(|| MethodError::unknown_method(path, member))?,
+        };
+        let method = registered
+            .interface
+            .method(member)
+            .ok_or_else(|| MethodError::unknown_method(path, member))?;
+        validate(method, args)?;
+
+        let mut signals = Signals {
+            path,
+            interface: &registered.interface,
+            caller,
+            queued: vec![],
+        };
+        let out = registered.handler.call(member, args, &mut signals)?;
+        *queued = signals.queued;
+        Ok(out)
+    }
+
+    /// Interfaces answered by the server itself at `path`.
+    fn standard(&self, path: &str) -> Vec<introspection::Interface> {
+        let mut out = vec![peer(), introspectable()];
+        if self.managers.contains(path) {
+            out.push(object_manager());
+        }
+        out
+    }
+
+    /// The closest object manager above `path`.
+    fn manager_of(&self, path: &str) -> Option<String> {
+        self.managers
+            .iter()
+            .filter(|manager| below(path, manager).is_some())
+            .max_by_key(|manager| manager.len())
+            .cloned()
+    }
+
+    fn managed_objects(&self, manager: &str) -> ManagedObjects {
+        let objects = self
+            .objects
+            .iter()
+            .filter(|(path, _)| below(path, manager).is_some())
+            .map(|(path, object)| {
+                let interfaces = object
+                    .iter()
+                    .map(|known| (known.interface.name.clone(), known.handler.properties()))
+                    .collect();
+                (path.clone(), interfaces)
+            })
+            .collect();
+        ManagedObjects { objects }
+    }
+
+    /// Objects are listed as children of every path above them, so those
+    /// paths can be introspected as well.
+    fn introspect(&self, path: &str) -> Result<String, MethodError> {
+        let children = self
+            .objects
+            .keys()
+            .filter_map(|known| below(known, path))
+            .filter_map(|rest| rest.split('/').next())
+            .collect::<BTreeSet<_>>();
+
+        let interfaces = match self.objects.get(path) {
+            Some(object) => object.iter().map(|known| known.interface.clone()).collect(),
+            None if children.is_empty() && path != "/" => {
+                return Err(MethodError::unknown_object(path));
+            }
+            None => vec![],
+        };
+        let node = Node {
+            name: None,
+            interfaces: self.standard(path).into_iter().chain(interfaces).collect(),
+            children: children
+                .into_iter()
+                .map(|child| Node {
+                    name: Some(child.to_string()),
+                    ..Node::default()
+                })
+                .collect(),
+        };
+        Ok(node.to_string())
+    }
+}
+
+/// The rest of `path` if it's strictly below `ancestor`.
+fn below<'a>(path: &'a str, ancestor: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(ancestor)?;
+    let rest = if ancestor == "/" {
+        rest
+    } else {
+        rest.strip_prefix('/')?
+    };
+    (!rest.is_empty()).then_some(rest)
+}
+
+fn validate(method: &Method, args: &[Value]) -> Result<(), MethodError> {
+    method
+        .validate_call(args)
+        .map_err(|err| MethodError::invalid_args(format!("{err:#}")))
+}
+
+fn peer() -> introspection::Interface {
+    introspection::Interface {
+        name: PEER.into(),
+        methods: vec![
+            method("Ping", None),
+            method("GetMachineId", Some(("machine_uuid", CompleteType::String))),
+        ],
+        ..Default::default()
+    }
+}
+
+fn introspectable() -> introspection::Interface {
+    introspection::Interface {
+        name: INTROSPECTABLE.into(),
+        methods: vec![method(
+            "Introspect",
+            Some(("xml_data", CompleteType::String)),
+        )],
+        ..Default::default()
+    }
+}
+
+fn object_manager() -> introspection::Interface {
+    let interfaces = "a{sa{sv}}".parse().expect("valid signature");
+    let signal = |name: &str, args: Vec<(&str, CompleteType)>| Signal {
+        name: name.into(),
+        args: args
+            .into_iter()
+            .map(|(name, complete_type)| arg(name, complete_type, Direction::Out))
+            .collect(),
+        annotations: vec![],
+    };
+    introspection::Interface {
+        name: OBJECT_MANAGER.into(),
+        methods: vec![method(
+            "GetManagedObjects",
+            Some((
+                "object_paths_interfaces_and_properties",
+                "a{oa{sa{sv}}}".parse().expect("valid signature"),
+            )),
+        )],
+        signals: vec![
+            signal(
+                "InterfacesAdded",
+                vec![
+                    ("object_path", CompleteType::ObjectPath),
+                    ("interfaces_and_properties", interfaces),
+                ],
+            ),
+            signal(
+                "InterfacesRemoved",
+                vec![
+                    ("object_path", CompleteType::ObjectPath),
+                    (
+                        "interfaces",
+                        CompleteType::Array(Box::new(CompleteType::String)),
+                    ),
+                ],
+            ),
+        ],
+        ..Default::default()
+    }
+}
+
+/// A method without arguments returning at most one value.
+fn method(name: &str, out: Option<(&str, CompleteType)>) -> Method {
+    Method {
+        name: name.into(),
+        args: out
+            .map(|(name, complete_type)| arg(name, complete_type, Direction::Out))
+            .into_iter()
+            .collect(),
+        annotations: vec![],
+    }
+}
+
+fn arg(name: &str, complete_type: CompleteType, direction: Direction) -> Arg {
+    Arg {
+        name: Some(name.into()),
+        complete_type,
+        direction,
+        annotations: vec![],
+    }
+}
+
+#[test]
+fn test_object_server() {
+    use std::borrow::Cow;
+
+    struct Plus;
+    impl Handler for Plus {
+        fn introspect(&self) -> introspection::Interface {
+            let xml = r#"<node><interface name="org.me.Calc"><method name="Plus">
+                <arg type="i" direction="in"/><arg type="i" direction="in"/>
+                <arg type="i" direction="out"/>
+            </method><signal name="Computed"><arg type="i"/></signal></interface></node>"#;
+            Node::parse(xml).unwrap().interfaces.remove(0)
+        }
+
+        fn call(
+            &mut self,
+            member: &str,
+            args: &[Value],
+            signals: &mut Signals,
+        ) -> Result<Vec<Value>, MethodError> {
+            let (Value::Int32(lhs), Value::Int32(rhs)) = (&args[0], &args[1]) else {
+                unreachable!("validated against introspection")
+            };
+            assert_eq!(member, "Plus");
+            assert!(signals.emit("Computed", vec![]).is_err());
+            signals.emit("Computed", vec![Value::Int32(lhs.wrapping_add(*rhs))])?;
+            let sum = lhs
+                .checked_add(*rhs)
+                .ok_or_else(|| anyhow::anyhow!("overflow"))?;
+            Ok(vec![Value::Int32(sum)])
+        }
+    }
+
+    let call =
+        |path: &'static str, interface: Option<&'static str>, member, body| Message::MethodCall {
+            serial: 7,
+            path: Cow::Borrowed(path),
+            member: Cow::Borrowed(member),
+            interface: interface.map(Cow::Borrowed),
+            destination: None,
+            sender: Some(Cow::Borrowed(":1.2")),
+            unix_fds: None,
+            no_reply_expected: false,
+            body,
+        };
+    let plus = |lhs, rhs| vec![Value::Int32(lhs), Value::Int32(rhs)];
+    let error_name = |replies: Vec<Message>| match replies.as_slice() {
+        [Message::Error { error_name, .. }] => error_name.clone(),
+        other => panic!("expected an error, got {other:?}"),
+    };
+
+    let peer = PeerResponder::with_machine_id("0123456789abcdef0123456789abcdef");
+    let mut server = ObjectServer::new().with_peer(peer);
+    server.add("/org/me/calc", Plus).unwrap();
+    assert!(server.add("/org/me/calc", Plus).is_err());
+
+    let replies = server.dispatch(&call(
+        "/org/me/calc",
+        Some("org.me.Calc"),
+        "Plus",
+        plus(1, 2),
+    ));
+    assert_eq!(
+        replies,
+        vec![
+            Message::MethodReturn {
+                serial: 0,
+                reply_serial: 7,
+                destination: Some(Cow::Borrowed(":1.2")),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::Int32(3)],
+            },
+            SignalBuilder::new("/org/me/calc", "org.me.Calc", "Computed")
+                .arg(3)
+                .into(),
+        ]
+    );
+    let replies = server.dispatch(&call("/org/me/calc", None, "Plus", plus(1, 2)));
+    assert_eq!(replies[0].body(), [Value::Int32(3)]);
+
+    for (message, expected) in [
+        (
+            call("/nope", Some("org.me.Calc"), "Plus", plus(1, 2)),
+            "UnknownObject",
+        ),
+        (
+            call("/org/me/calc", Some("org.me.Nope"), "Plus", plus(1, 2)),
+            "UnknownInterface",
+        ),
+        (
+            call("/org/me/calc", Some("org.me.Calc"), "Minus", plus(1, 2)),
+            "UnknownMethod",
+        ),
+        (
+            call("/org/me/calc", Some("org.me.Calc"), "Plus", vec![]),
+            "InvalidArgs",
+        ),
+        (
+            call(
+                "/org/me/calc",
+                Some("org.me.Calc"),
+                "Plus",
+                plus(i32::MAX, 1),
+            ),
+            "Failed",
+        ),
+    ] {
+        let name = error_name(server.dispatch(&message));
+        assert_eq!(name, format!("org.freedesktop.DBus.Error.{expected}"));
+    }
+
+    let mut quiet = call("/org/me/calc", Some("org.me.Calc"), "Plus", plus(1, 2));
+    let Message::MethodCall {
+        no_reply_expected, ..
+    } = &mut quiet
+    else {
+        unreachable!()
+    };
+    *no_reply_expected = true;
+    let replies = server.dispatch(&quiet);
+    assert!(matches!(replies[..], [Message::Signal { .. }]));
+
+    let replies = server.dispatch(&call("/anywhere", Some(PEER), "Ping", vec![]));
+    assert_eq!(replies[0].body(), []);
+    let replies = server.dispatch(&call("/", None, "GetMachineId", vec![]));
+    assert_eq!(
+        replies[0].body(),
+        [Value::String("0123456789abcdef0123456789abcdef".into())]
+    );
+
+    let introspect = |server: &mut ObjectServer, path| {
+        let replies = server.dispatch(&call(path, Some(INTROSPECTABLE), "Introspect", vec![]));
+        let [Value::String(xml)] = replies[0].body() else {
+            panic!("expected XML, got {replies:?}");
+        };
+        Node::parse(xml).unwrap()
+    };
+    let root = introspect(&mut server, "/");
+    assert!(root.child("org").is_some());
+    assert!(root.interface(PEER).is_some());
+    assert!(introspect(&mut server, "/org/me").child("calc").is_some());
+    let calc = introspect(&mut server, "/org/me/calc");
+    assert!(
+        calc.interface("org.me.Calc")
+            .unwrap()
+            .method("Plus")
+            .is_some()
+    );
+    assert_eq!(
+        error_name(server.dispatch(&call(
+            "/org/you",
+            Some(INTROSPECTABLE),
+            "Introspect",
+            vec![]
+        ))),
+        "org.freedesktop.DBus.Error.UnknownObject"
+    );
+
+    assert!(server.remove("/org/me/calc", "org.me.Calc"));
+    assert!(!server.remove("/org/me/calc", "org.me.Calc"));
+    assert!(introspect(&mut server, "/").children.is_empty());
+}
+
+#[test]
+fn test_object_server_manager() {
+    use crate::object_cache::ObjectCache;
+    use std::borrow::Cow;
+
+    struct Battery(u8);
+    impl Handler for Battery {
+        fn introspect(&self) -> introspection::Interface {
+            introspection::Interface {
+                name: "org.me.Battery".into(),
+                ..Default::default()
+            }
+        }
+
+        fn call(
+            &mut self,
+            member: &str,
+            _: &[Value],
+            _: &mut Signals,
+        ) -> Result<Vec<Value>, MethodError> {
+            unreachable!("no methods, got {member}")
+        }
+
+        fn properties(&self) -> BTreeMap<String, Value> {
+            BTreeMap::from([(String::from("Percentage"), Value::Byte(self.0))])
+        }
+    }
+
+    let call = |path: &'static str, interface: &'static str, member| Message::MethodCall {
+        serial: 3,
+        path: Cow::Borrowed(path),
+        member: Cow::Borrowed(member),
+        interface: Some(Cow::Borrowed(interface)),
+        destination: None,
+        sender: Some(Cow::Borrowed(":1.2")),
+        unix_fds: None,
+        no_reply_expected: false,
+        body: vec![],
+    };
+
+    let mut server = ObjectServer::new();
+    server.add("/outside", Battery(1)).unwrap();
+    server.add_object_manager("/org/me");
+    server.add("/org/me/bat0", Battery(80)).unwrap();
+    server.add("/org/me/bat1", Battery(20)).unwrap();
+
+    let mut cache = ObjectCache::new(":1.1", "/org/me");
+    let replies = server.dispatch(&call("/org/me", OBJECT_MANAGER, "GetManagedObjects"));
+    cache.load(&replies[0]).unwrap();
+    assert_eq!(
+        cache.objects().keys().collect::<Vec<_>>(),
+        ["/org/me/bat0", "/org/me/bat1"]
+    );
+    assert!(matches!(
+        server.dispatch(&call("/outside", OBJECT_MANAGER, "GetManagedObjects"))[..],
+        [Message::Error { .. }]
+    ));
+
+    let signals = server.take_signals();
+    assert_eq!(signals.len(), 2);
+    assert!(server.take_signals().is_empty());
+
+    server.add("/org/me/bat2", Battery(50)).unwrap();
+    assert!(server.remove("/org/me/bat0", "org.me.Battery"));
+    assert!(server.remove("/outside", "org.me.Battery"));
+    for signal in server.take_signals() {
+        assert!(cache.update(&signal));
+    }
+    assert_eq!(
+        cache.objects().keys().collect::<Vec<_>>(),
+        ["/org/me/bat1", "/org/me/bat2"]
+    );
+    assert_eq!(
+        cache.get("/org/me/bat2").unwrap()["org.me.Battery"]["Percentage"],
+        Value::Byte(50)
+    );
+
+    let replies = server.dispatch(&call("/org/me", INTROSPECTABLE, "Introspect"));
+    let [Value::String(xml)] = replies[0].body() else {
+        panic!("expected XML, got {replies:?}");
+    };
+    let node = Node::parse(xml).unwrap();
+    let manager = node.interface(OBJECT_MANAGER).unwrap();
+    assert!(manager.method("GetManagedObjects").is_some());
+    assert!(node.child("bat1").is_some());
+}
@@ -0,0 +1,144 @@
+use crate::{
+    messages::{GetMachineIdRequest, GetMachineIdResponse, PingRequest, PingResponse},
+    types::{Message, Value},
+};
+use anyhow::{Context, Result, ensure};
+use std::path::PathBuf;
+
+/// Answers `org.freedesktop.DBus.Peer` calls, whatever the backend.
+///
+/// Every incoming message can be passed to `respond`, which returns the
+/// reply to enqueue for `Ping` and `GetMachineId`. The machine id is read
+/// the first time it's asked for, unless given with `with_machine_id`.
+#[derive(Debug)]
+pub struct PeerResponder {
+    machine_id_path: PathBuf,
+    machine_id: Option<String>,
+}
+
+impl Default for PeerResponder {
+    fn default() -> Self {
+        Self::with_machine_id_path("/etc/machine-id")
+    }
+}
+
+impl PeerResponder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_machine_id_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            machine_id_path: path.into(),
+            machine_id: None,
+        }
+    }
+
+    /// Answers with `machine_id`, which should be 32 hex digits, instead of
+    /// reading a file.
+    pub fn with_machine_id(machine_id: impl Into<String>) -> Self {
+        Self {
+            machine_id_path: PathBuf::new(),
+            machine_id: Some(machine_id.into()),
+        }
+    }
+
+    /// The 32 hex digits of the machine id file.
+    pub fn machine_id(&mut self) -> Result<&str> {
+        if self.machine_id.is_none() {
+            let path = &self.machine_id_path;
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let machine_id = contents.trim();
+            ensure!(
+                machine_id.len() == 32 && machine_id.bytes().all(|b| b.is_ascii_hexdigit()),
+                "malformed machine id in {}",
+                path.display()
+            );
+            self.machine_id = Some(machine_id.to_string());
+        }
+        Ok(self.machine_id.as_deref().unwrap_or_default())
+    }
+
+    /// Returns `None` for anything but a `Peer` call expecting a reply.
+    pub fn respond(&mut self, message: &Message) -> Option<Message> {
+        if let Message::MethodCall {
+            no_reply_expected: true,
+            ..
+        } = message
+        {
+            return None;
+        }
+        if let Ok(req) = PingRequest::try_from(message) {
+            return Some(PingResponse::new(req).into());
+        }
+
+        let req = GetMachineIdRequest::try_from(message).ok()?;
+        Some(match self.machine_id() {
+            Ok(machine_id) => GetMachineIdResponse::new(req, machine_id).into(),
+            Err(err) => Message::Error {
+                serial: 0,
+                error_name: String::from("org.freedesktop.DBus.Error.Failed"),
+                reply_serial: req.serial,
+                destination: req.sender.map(|sender| sender.into_owned().into()),
+                sender: None,
+                unix_fds: None,
+                body: vec![Value::String(format!("{err:#}"))],
+            },
+        })
+    }
+}
+
+#[test]
+fn test_peer_responder() {
+    use crate::messages::PEER;
+    use std::borrow::Cow;
+
+    let call = |member: &'static str| Message::MethodCall {
+        serial: 3,
+        path: Cow::Borrowed("/some/where"),
+        member: Cow::Borrowed(member),
+        interface: Some(Cow::Borrowed(PEER)),
+        destination: None,
+        sender: Some(Cow::Borrowed(":1.9")),
+        unix_fds: None,
+        no_reply_expected: false,
+        body: vec![],
+    };
+    let reply = |body| Message::MethodReturn {
+        serial: 0,
+        reply_serial: 3,
+        destination: Some(Cow::Borrowed(":1.9")),
+        sender: None,
+        unix_fds: None,
+        body,
+    };
+
+    let path = std::env::temp_dir().join(format!("machine-id-{}", std::process::id()));
+    std::fs::write(&path, "0123456789abcdef0123456789abcdef\n").unwrap();
+    let mut peer = PeerResponder::with_machine_id_path(&path);
+    assert_eq!(peer.respond(&call("Ping")), Some(reply(vec![])));
+    assert_eq!(
+        peer.respond(&call("GetMachineId")),
+        Some(reply(vec![Value::String(
+            "0123456789abcdef0123456789abcdef".into()
+        )]))
+    );
+    assert_eq!(peer.respond(&call("Introspect")), None);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut peer = PeerResponder::with_machine_id_path(&path);
+    assert!(matches!(
+        peer.respond(&call("GetMachineId")),
+        Some(Message::Error {
+            reply_serial: 3,
+            ..
+        })
+    ));
+
+    let mut peer = PeerResponder::with_machine_id("fedcba9876543210fedcba9876543210");
+    assert_eq!(
+        peer.machine_id().unwrap(),
+        "fedcba9876543210fedcba9876543210"
+    );
+}
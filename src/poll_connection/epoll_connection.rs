@@ -0,0 +1,153 @@
+use crate::{Message, poll_connection::PollConnection};
+use anyhow::Result;
+use libc::{EPOLLET, EPOLLIN, EPOLLOUT, EPOLLRDHUP, POLLIN, POLLOUT};
+use std::os::fd::AsRawFd;
+
+/// A `PollConnection` for edge-triggered epoll.
+///
+/// Readiness is only reported on change, so every wakeup drains reads and
+/// writes until the socket would block, whatever direction it was for.
+/// Register the fd with `interest()` and pass `interest_changed()` to
+/// `EPOLL_CTL_MOD` after every call that may have queued or flushed
+/// messages.
+pub struct EpollConnection {
+    conn: PollConnection,
+    registered: u32,
+}
+
+impl EpollConnection {
+    pub fn session() -> Result<Self> {
+        Ok(Self::new(PollConnection::session()?))
+    }
+
+    pub(crate) fn new(conn: PollConnection) -> Self {
+        let mut out = Self {
+            conn,
+            registered: 0,
+        };
+        out.registered = out.interest();
+        out
+    }
+
+    pub fn enqueue(&mut self, message: &mut Message) -> Result<()> {
+        self.conn.enqueue(message)
+    }
+
+    /// `EPOLLET | EPOLLRDHUP` plus the directions the connection waits
+    /// for, `0` once disconnected.
+    pub fn interest(&self) -> u32 {
+        let events = self.conn.events();
+        if events == 0 {
+            return 0;
+        }
+        let mut out = (EPOLLET | EPOLLRDHUP) as u32;
+        if events & POLLIN != 0 {
+            out |= EPOLLIN as u32;
+        }
+        if events & POLLOUT != 0 {
+            out |= EPOLLOUT as u32;
+        }
+        out
+    }
+
+    /// The new interest if it differs from the one last reported.
+    pub fn interest_changed(&mut self) -> Option<u32> {
+        let interest = self.interest();
+        if interest == self.registered {
+            return None;
+        }
+        self.registered = interest;
+        Some(interest)
+    }
+
+    /// Handles a wakeup with the epoll `events` reported for the fd. The
+    /// messages read are appended to `out`, also when an error is
+    /// returned, e.g. if the peer sent them right before hanging up.
+    pub fn handle_events(&mut self, _events: u32, out: &mut Vec<Message>) -> Result<()> {
+        // Finishing authentication doesn't produce an edge for what the
+        // bus sent right after it, so go on with the new state right away
+        while self.conn.poll_into(true, true, out)? {}
+        Ok(())
+    }
+}
+
+impl AsRawFd for EpollConnection {
+    fn as_raw_fd(&self) -> std::os::unix::prelude::RawFd {
+        self.conn.as_raw_fd()
+    }
+}
+
+#[test]
+fn test_edge_triggered() {
+    use crate::{Disconnected, encoders::MessageEncoder, messages::Hello};
+    use libc::{EPOLL_CTL_ADD, EPOLL_CTL_MOD, epoll_create1, epoll_ctl, epoll_event, epoll_wait};
+    use std::{
+        io::{Read as _, Write as _},
+        os::unix::net::UnixStream,
+    };
+
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    let mut conn = EpollConnection::new(PollConnection::from_stream(ours).unwrap());
+
+    let fd = conn.as_raw_fd();
+    let epfd = unsafe { epoll_create1(0) };
+    assert!(epfd >= 0);
+    let ctl = |op, interest: u32| {
+        let mut event = epoll_event {
+            events: interest,
+            u64: 0,
+        };
+        assert_eq!(unsafe { epoll_ctl(epfd, op, fd, &mut event) }, 0);
+    };
+    let wait = || {
+        let mut event = epoll_event { events: 0, u64: 0 };
+        assert_eq!(unsafe { epoll_wait(epfd, &mut event, 1, 1000) }, 1);
+        event.events
+    };
+    ctl(EPOLL_CTL_ADD, conn.interest());
+
+    // The whole handshake and a first message are already waiting
+    let hello = MessageEncoder::encode(&Hello.into()).unwrap();
+    theirs.write_all(b"DATA\r\n").unwrap();
+    theirs
+        .write_all(b"OK 0123456789abcdef0123456789abcdef\r\n")
+        .unwrap();
+    theirs.write_all(&hello).unwrap();
+
+    let mut messages = vec![];
+    while messages.is_empty() {
+        conn.handle_events(wait(), &mut messages).unwrap();
+        if let Some(interest) = conn.interest_changed() {
+            ctl(EPOLL_CTL_MOD, interest);
+        }
+    }
+    assert_eq!(messages, vec![Hello.into()]);
+    assert_eq!(conn.interest_changed(), None);
+    assert_eq!(conn.interest() & EPOLLOUT as u32, 0);
+
+    let mut handshake = [0; 1 + 15 + 6 + 7];
+    theirs.read_exact(&mut handshake).unwrap();
+    assert_eq!(&handshake, b"\0AUTH EXTERNAL\r\nDATA\r\nBEGIN\r\n");
+
+    // Queueing wants writability, which is reported again after the MOD
+    conn.enqueue(&mut Hello.into()).unwrap();
+    let interest = conn.interest_changed().unwrap();
+    assert_ne!(interest & EPOLLOUT as u32, 0);
+    ctl(EPOLL_CTL_MOD, interest);
+    conn.handle_events(wait(), &mut messages).unwrap();
+    let mut sent = vec![0; hello.len()];
+    theirs.read_exact(&mut sent).unwrap();
+    assert_eq!(&sent[12..], &hello[12..]);
+
+    // A message followed by a hangup arrive together
+    theirs.write_all(&hello).unwrap();
+    drop(theirs);
+    let mut messages = vec![];
+    let err = conn.handle_events(wait(), &mut messages).unwrap_err();
+    let disconnected = err.downcast_ref::<Disconnected>().unwrap();
+    assert_eq!(disconnected.pending, vec![1]);
+    assert_eq!(messages, vec![Hello.into()]);
+    assert_eq!(conn.interest(), 0);
+
+    unsafe { libc::close(epfd) };
+}
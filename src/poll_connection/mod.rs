@@ -1,6 +1,15 @@
-use crate::{encoders::MessageEncoder, serial::Serial, session_connection, types::Message};
+use crate::{
+    disconnected::{Disconnected, PendingCalls},
+    encoders::MessageEncoder,
+    serial::Serial,
+    session_connection,
+    types::Message,
+};
 use anyhow::Result;
-use std::os::fd::AsRawFd;
+use std::os::{fd::AsRawFd, unix::net::UnixStream};
+
+mod epoll_connection;
+pub use epoll_connection::EpollConnection;
 
 mod non_blocking_stream;
 use non_blocking_stream::NonBlockingUnixStream;
@@ -15,6 +24,7 @@ use poll_reader_writer_fsm::PollReaderWriterFSM;
 enum PollFSM {
     Auth(PollAuthFSM),
     ReaderWriter(PollReaderWriterFSM),
+    Disconnected(Disconnected),
     #[default]
     None,
 }
@@ -22,6 +32,7 @@ enum PollFSM {
 pub struct PollConnection {
     serial: Serial,
     fsm: PollFSM,
+    pending: PendingCalls,
 }
 
 impl AsRawFd for PollConnection {
@@ -29,6 +40,8 @@ impl AsRawFd for PollConnection {
         match &self.fsm {
             PollFSM::Auth(auth) => auth.as_raw_fd(),
             PollFSM::ReaderWriter(rw) => rw.as_raw_fd(),
+            // Negative fds are skipped by poll(2)
+            PollFSM::Disconnected(_) => -1,
             PollFSM::None => unreachable!(),
         }
     }
@@ -36,24 +49,29 @@ impl AsRawFd for PollConnection {
 
 impl PollConnection {
     pub fn session() -> Result<Self> {
-        let stream = session_connection()?;
+        Self::from_stream(session_connection()?)
+    }
+
+    pub(crate) fn from_stream(stream: UnixStream) -> Result<Self> {
         stream.set_nonblocking(true)?;
 
         Ok(Self {
             serial: Serial::zero(),
             fsm: PollFSM::Auth(PollAuthFSM::new(NonBlockingUnixStream::new(stream))),
+            pending: PendingCalls::default(),
         })
     }
 
     pub fn enqueue(&mut self, message: &mut Message) -> Result<()> {
         *message.serial_mut() = self.serial.increment_and_get();
-        let buf = MessageEncoder::encode(message)?;
 
         match &mut self.fsm {
-            PollFSM::Auth(auth) => auth.enqueue(buf),
-            PollFSM::ReaderWriter(rw) => rw.enqueue(buf),
+            PollFSM::Auth(auth) => auth.enqueue(MessageEncoder::encode(message)?),
+            PollFSM::ReaderWriter(rw) => rw.enqueue(message)?,
+            PollFSM::Disconnected(disconnected) => return Err(disconnected.clone().into()),
             PollFSM::None => unreachable!(),
         }
+        self.pending.sent(message);
 
         Ok(())
     }
@@ -62,6 +80,7 @@ impl PollConnection {
         match &self.fsm {
             PollFSM::Auth(auth) => auth.events(),
             PollFSM::ReaderWriter(rw) => rw.events(),
+            PollFSM::Disconnected(_) => 0,
             PollFSM::None => unreachable!(),
         }
     }
@@ -71,6 +90,54 @@ impl PollConnection {
     }
 
     pub fn poll(&mut self, readable: bool, writable: bool) -> Result<Vec<Message>> {
+        let mut messages = vec![];
+        match self.poll_into(readable, writable, &mut messages) {
+            // Hand out what was read, the stored `Disconnected` comes back
+            // next time. Anything else would be lost, so it goes out now.
+            Err(err) if !messages.is_empty() && err.downcast_ref::<Disconnected>().is_some() => {
+                Ok(messages)
+            }
+            Err(err) => Err(err),
+            Ok(_) => Ok(messages),
+        }
+    }
+
+    /// Like `poll`, but keeps the messages read before an error. Returns
+    /// whether authentication finished during the call.
+    pub(crate) fn poll_into(
+        &mut self,
+        readable: bool,
+        writable: bool,
+        out: &mut Vec<Message>,
+    ) -> Result<bool> {
+        let read = out.len();
+        let result = self.poll_fsm(readable, writable, out);
+        for message in &out[read..] {
+            self.pending.received(message);
+        }
+        result.map_err(|err| self.disconnect(err))
+    }
+
+    /// Moves to the terminal `Disconnected` state if `err` is a
+    /// `Disconnected`, recording the messages that never went out and the
+    /// calls left without a reply.
+    fn disconnect(&mut self, err: anyhow::Error) -> anyhow::Error {
+        let Some(disconnected) = err.downcast_ref::<Disconnected>() else {
+            return err;
+        };
+        let disconnected = match &self.fsm {
+            PollFSM::Auth(auth) => disconnected
+                .clone()
+                .with_unsent(auth.queue.iter().map(Vec::as_slice)),
+            PollFSM::ReaderWriter(rw) => disconnected.clone().with_unsent(rw.queued()),
+            PollFSM::Disconnected(_) | PollFSM::None => disconnected.clone(),
+        };
+        let disconnected = disconnected.with_pending(&self.pending);
+        self.fsm = PollFSM::Disconnected(disconnected.clone());
+        disconnected.into()
+    }
+
+    fn poll_fsm(&mut self, readable: bool, writable: bool, out: &mut Vec<Message>) -> Result<bool> {
         match &mut self.fsm {
             PollFSM::Auth(auth) => {
                 if auth.poll(readable, writable)? {
@@ -80,13 +147,50 @@ impl PollConnection {
                     };
 
                     self.fsm = PollFSM::ReaderWriter(PollReaderWriterFSM::new(stream, queue));
+                    return Ok(true);
                 }
 
-                Ok(vec![])
+                Ok(false)
             }
-            PollFSM::ReaderWriter(rw) => rw.poll(readable, writable),
+            PollFSM::ReaderWriter(rw) => rw.poll(readable, writable, out).map(|()| false),
+            PollFSM::Disconnected(disconnected) => Err(disconnected.clone().into()),
 
             PollFSM::None => unreachable!(),
         }
     }
 }
+
+#[test]
+fn test_poll_errors() {
+    use crate::messages::Hello;
+    use std::io::Write as _;
+
+    let connected = || {
+        let (ours, mut theirs) = UnixStream::pair().unwrap();
+        let mut conn = PollConnection::from_stream(ours).unwrap();
+        theirs.write_all(b"DATA\r\n").unwrap();
+        theirs
+            .write_all(b"OK 0123456789abcdef0123456789abcdef\r\n")
+            .unwrap();
+        while !conn.poll_into(true, true, &mut vec![]).unwrap() {}
+        (conn, theirs)
+    };
+    let (mut conn, mut theirs) = connected();
+
+    // A message then one too long to read: the decode error isn't dropped
+    let hello = MessageEncoder::encode(&Hello.into()).unwrap();
+    let mut too_long = hello.clone();
+    too_long[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    theirs.write_all(&hello).unwrap();
+    theirs.write_all(&too_long).unwrap();
+    let err = conn.poll(true, false).unwrap_err();
+    assert!(err.downcast_ref::<Disconnected>().is_none(), "{err}");
+
+    // A message then a hangup: the message first, then the error
+    let (mut conn, mut theirs) = connected();
+    theirs.write_all(&hello).unwrap();
+    drop(theirs);
+    assert_eq!(conn.poll(true, false).unwrap(), vec![Hello.into()]);
+    let err = conn.poll(true, false).unwrap_err();
+    assert!(err.downcast_ref::<Disconnected>().is_some(), "{err}");
+}
@@ -1,6 +1,7 @@
+use crate::disconnected::Disconnected;
 use anyhow::Result;
 use std::{
-    io::{ErrorKind, Read as _, Write as _},
+    io::{ErrorKind, IoSlice, Read as _, Write as _},
     os::{fd::AsRawFd, unix::net::UnixStream},
 };
 
@@ -14,18 +15,29 @@ impl NonBlockingUnixStream {
     }
 
     pub(crate) fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>> {
-        match self.s.read(buf) {
-            Ok(len) => Ok(Some(len)),
-            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(None),
-            Err(err) => Err(err.into()),
-        }
+        retry(|| self.s.read(buf))
     }
 
     pub(crate) fn write(&mut self, buf: &[u8]) -> Result<Option<usize>> {
-        match self.s.write(buf) {
-            Ok(len) => Ok(Some(len)),
-            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(None),
-            Err(err) => Err(err.into()),
+        retry(|| self.s.write(buf))
+    }
+
+    pub(crate) fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<Option<usize>> {
+        retry(|| self.s.write_vectored(bufs))
+    }
+}
+
+/// Runs `io` until it isn't interrupted. `None` means the socket would
+/// block: with edge-triggered epoll that is the only point where the
+/// caller may stop, any other pause can strand data no new edge reports.
+fn retry(mut io: impl FnMut() -> std::io::Result<usize>) -> Result<Option<usize>> {
+    loop {
+        match io() {
+            Ok(0) => return Err(Disconnected::eof().into()),
+            Ok(len) => return Ok(Some(len)),
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+            Err(err) => return Err(Disconnected::from(&err).into()),
         }
     }
 }
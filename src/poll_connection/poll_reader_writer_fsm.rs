@@ -1,11 +1,11 @@
 use crate::{
     Message,
-    fsm::{ReaderFSM, WriterFSM},
+    fsm::{MAX_IOVECS, ReaderFSM, WriterFSM},
     poll_connection::non_blocking_stream::NonBlockingUnixStream,
 };
 use anyhow::Result;
 use libc::{POLLIN, POLLOUT};
-use std::os::fd::AsRawFd;
+use std::{io::IoSlice, os::fd::AsRawFd};
 
 pub(crate) struct PollReaderWriterFSM {
     stream: NonBlockingUnixStream,
@@ -26,8 +26,12 @@ impl PollReaderWriterFSM {
         }
     }
 
-    pub(crate) fn enqueue(&mut self, buf: Vec<u8>) {
-        self.writer.enqueue(buf);
+    pub(crate) fn enqueue(&mut self, message: &Message) -> Result<()> {
+        self.writer.enqueue_message(message)
+    }
+
+    pub(crate) fn queued(&self) -> impl Iterator<Item = &[u8]> {
+        self.writer.queued()
     }
 
     pub(crate) fn events(&self) -> i16 {
@@ -38,13 +42,22 @@ impl PollReaderWriterFSM {
         out
     }
 
-    pub(crate) fn poll(&mut self, readable: bool, writable: bool) -> Result<Vec<Message>> {
+    /// Writes and reads until the socket would block, appending the
+    /// messages read to `out` even if it fails halfway.
+    pub(crate) fn poll(
+        &mut self,
+        readable: bool,
+        writable: bool,
+        out: &mut Vec<Message>,
+    ) -> Result<()> {
         if writable {
             loop {
-                let Some(buf) = self.writer.wants() else {
+                let mut slices = [IoSlice::new(&[]); MAX_IOVECS];
+                let count = self.writer.wants_vectored(&mut slices);
+                if count == 0 {
                     break;
-                };
-                let Some(len) = self.stream.write(buf)? else {
+                }
+                let Some(len) = self.stream.write_vectored(&slices[..count])? else {
                     break;
                 };
                 self.writer.satisfy(len)?;
@@ -52,21 +65,20 @@ impl PollReaderWriterFSM {
         }
 
         if readable {
-            let mut messages = vec![];
-
             loop {
                 let buf = self.reader.wants();
                 let Some(len) = self.stream.read(buf)? else {
-                    return Ok(messages);
+                    break;
                 };
+                self.reader.satisfy(len);
 
-                if let Some(message) = self.reader.satisfy(len)? {
-                    messages.push(message);
+                while let Some(message) = self.reader.next_message()? {
+                    out.push(message);
                 }
             }
         }
 
-        Ok(vec![])
+        Ok(())
     }
 }
 
@@ -0,0 +1,163 @@
+use crate::types::{Message, Value};
+use std::fmt;
+
+/// Renders a message the way `dbus-monitor` does, minus the timestamp:
+/// a header line, then the body with one value per line.
+pub struct Pretty<'a>(&'a Message);
+
+impl Message {
+    pub fn pretty(&self) -> Pretty<'_> {
+        Pretty(self)
+    }
+}
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = self.0;
+        let kind = match message {
+            Message::MethodCall { .. } => "method call",
+            Message::MethodReturn { .. } => "method return",
+            Message::Error { .. } => "error",
+            Message::Signal { .. } => "signal",
+        };
+        write!(
+            f,
+            "{kind} sender={} -> destination={} serial={}",
+            message.sender().unwrap_or("(null sender)"),
+            message.destination().unwrap_or("(null destination)"),
+            message.serial()
+        )?;
+        if let Some(error_name) = message.error_name() {
+            write!(f, " error_name={error_name}")?;
+        }
+        if let Some(reply_serial) = message.reply_serial() {
+            write!(f, " reply_serial={reply_serial}")?;
+        }
+        if let (Some(path), Some(member)) = (message.path(), message.member()) {
+            write!(
+                f,
+                " path={path}; interface={}; member={member}",
+                message.interface().unwrap_or("(null)")
+            )?;
+        }
+        writeln!(f)?;
+
+        for value in message.body() {
+            write_value(f, value, 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, depth: usize) -> fmt::Result {
+    write!(f, "{:1$}", "", depth * 3)?;
+    write_inline(f, value, depth)
+}
+
+/// Writes `value` from the current position, nested values go on their own
+/// lines indented by `depth + 1`.
+fn write_inline(f: &mut fmt::Formatter<'_>, value: &Value, depth: usize) -> fmt::Result {
+    let indent = depth * 3;
+    match value {
+        Value::Byte(value) => writeln!(f, "byte {value}"),
+        Value::Bool(value) => writeln!(f, "boolean {value}"),
+        Value::Int16(value) => writeln!(f, "int16 {value}"),
+        Value::UInt16(value) => writeln!(f, "uint16 {value}"),
+        Value::Int32(value) => writeln!(f, "int32 {value}"),
+        Value::UInt32(value) => writeln!(f, "uint32 {value}"),
+        Value::Int64(value) => writeln!(f, "int64 {value}"),
+        Value::UInt64(value) => writeln!(f, "uint64 {value}"),
+        Value::Double(value) => writeln!(f, "double {value}"),
+        Value::UnixFD(value) => writeln!(f, "file descriptor {value}"),
+        Value::String(value) => writeln!(f, "string {value:?}"),
+        Value::ObjectPath(value) => writeln!(f, "object path {value:?}"),
+        Value::Signature(value) => {
+            writeln!(f, "signature {:?}", String::from_utf8_lossy(value))
+        }
+        Value::Variant(value) => {
+            write!(f, "variant ")?;
+            write_inline(f, value, depth)
+        }
+        Value::Array(_, items) => {
+            writeln!(f, "array [")?;
+            for item in items {
+                write_value(f, item, depth + 1)?;
+            }
+            writeln!(f, "{:indent$}]", "")
+        }
+        Value::Struct(fields) => {
+            writeln!(f, "struct {{")?;
+            for field in fields {
+                write_value(f, field, depth + 1)?;
+            }
+            writeln!(f, "{:indent$}}}", "")
+        }
+        Value::DictEntry(key, value) => {
+            writeln!(f, "dict entry(")?;
+            write_value(f, key, depth + 1)?;
+            write_value(f, value, depth + 1)?;
+            writeln!(f, "{:indent$})", "")
+        }
+    }
+}
+
+#[test]
+fn test_pretty() {
+    use crate::types::CompleteType;
+    use std::borrow::Cow;
+
+    let signal = Message::Signal {
+        serial: 4,
+        path: Cow::Borrowed("/org/me"),
+        interface: Cow::Borrowed("org.freedesktop.DBus.Properties"),
+        member: Cow::Borrowed("PropertiesChanged"),
+        destination: None,
+        sender: Some(Cow::Borrowed(":1.5")),
+        unix_fds: None,
+        body: vec![
+            Value::String("org.me".into()),
+            Value::Array(
+                CompleteType::DictEntry(
+                    Box::new(CompleteType::String),
+                    Box::new(CompleteType::Variant),
+                ),
+                vec![Value::DictEntry(
+                    Box::new(Value::String("Size".into())),
+                    Box::new(Value::Variant(Box::new(Value::Struct(vec![
+                        Value::Int32(1),
+                        Value::Bool(true),
+                    ])))),
+                )],
+            ),
+        ],
+    };
+    assert_eq!(
+        signal.pretty().to_string(),
+        r#"signal sender=:1.5 -> destination=(null destination) serial=4 path=/org/me; interface=org.freedesktop.DBus.Properties; member=PropertiesChanged
+   string "org.me"
+   array [
+      dict entry(
+         string "Size"
+         variant struct {
+            int32 1
+            boolean true
+         }
+      )
+   ]
+"#
+    );
+
+    let error = Message::Error {
+        serial: 5,
+        error_name: "org.me.Error".into(),
+        reply_serial: 2,
+        destination: Some(Cow::Borrowed(":1.5")),
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    assert_eq!(
+        error.pretty().to_string(),
+        "error sender=(null sender) -> destination=:1.5 serial=5 error_name=org.me.Error reply_serial=2\n"
+    );
+}
@@ -0,0 +1,340 @@
+use crate::{
+    introspection::{self, Node},
+    match_rule::MatchRule,
+    messages::{GetAllProperties, GetProperty, PROPERTIES, SetProperty},
+    types::{Message, Value},
+    value_is,
+};
+use anyhow::{Context, Result, bail, ensure};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Builds messages for one interface of a remote object and reads their
+/// replies.
+///
+/// Without introspection data anything is sent as is. Once it's set with
+/// `with_introspection` or `load_introspection`, calls, replies and
+/// property access are checked against it before anything is sent.
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    destination: String,
+    path: String,
+    interface: String,
+    introspection: Option<introspection::Interface>,
+}
+
+impl Proxy {
+    pub fn new(
+        destination: impl Into<String>,
+        path: impl Into<String>,
+        interface: impl Into<String>,
+    ) -> Self {
+        Self {
+            destination: destination.into(),
+            path: path.into(),
+            interface: interface.into(),
+            introspection: None,
+        }
+    }
+
+    pub fn with_introspection(mut self, introspection: introspection::Interface) -> Self {
+        self.introspection = Some(introspection);
+        self
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// The `Introspect` call whose reply goes to `load_introspection`.
+    pub fn introspect(&self) -> Message {
+        Message::MethodCall {
+            serial: 0,
+            path: Cow::Owned(self.path.clone()),
+            member: Cow::Borrowed("Introspect"),
+            interface: Some(Cow::Borrowed("org.freedesktop.DBus.Introspectable")),
+            destination: Some(Cow::Owned(self.destination.clone())),
+            sender: None,
+            unix_fds: None,
+            no_reply_expected: false,
+            body: vec![],
+        }
+    }
+
+    /// Picks our interface out of the reply to `introspect`.
+    pub fn load_introspection(&mut self, reply: &Message) -> Result<()> {
+        let node = Node::try_from(reply)?;
+        let interface = node
+            .interface(&self.interface)
+            .with_context(|| format!("{} doesn't implement {}", self.path, self.interface))?;
+        self.introspection = Some(interface.clone());
+        Ok(())
+    }
+
+    /// A call to `member` with `args`.
+    pub fn call(&self, member: &str, args: Vec<Value>) -> Result<Message> {
+        if let Some(introspection) = &self.introspection {
+            let method = introspection
+                .method(member)
+                .with_context(|| format!("{} has no method {member}", self.interface))?;
+            method.validate_call(&args)?;
+        }
+        Ok(Message::MethodCall {
+            serial: 0,
+            path: Cow::Owned(self.path.clone()),
+            member: Cow::Owned(member.to_string()),
+            interface: Some(Cow::Owned(self.interface.clone())),
+            destination: Some(Cow::Owned(self.destination.clone())),
+            sender: None,
+            unix_fds: None,
+            no_reply_expected: false,
+            body: args,
+        })
+    }
+
+    /// The body of the reply to a call to `member`. Errors become `Err`.
+    pub fn reply(&self, member: &str, reply: &Message) -> Result<Vec<Value>> {
+        let body = reply_body(reply)?;
+        if let Some(method) = self
+            .introspection
+            .as_ref()
+            .and_then(|introspection| introspection.method(member))
+        {
+            method.validate_reply(body)?;
+        }
+        Ok(body.to_vec())
+    }
+
+    pub fn get_property(&self, name: &str) -> Result<Message> {
+        if let Some(property) = self.property(name)? {
+            ensure!(property.access.is_readable(), "{name} is not readable");
+        }
+        Ok(GetProperty {
+            destination: Cow::Borrowed(&self.destination),
+            path: Cow::Borrowed(&self.path),
+            interface: Cow::Borrowed(&self.interface),
+            name: Cow::Borrowed(name),
+        }
+        .into())
+    }
+
+    pub fn set_property(&self, name: &str, value: Value) -> Result<Message> {
+        if let Some(property) = self.property(name)? {
+            ensure!(property.access.is_writable(), "{name} is not writable");
+            let actual = value.try_complete_type()?;
+            ensure!(
+                property.complete_type == actual,
+                "{name} is {:?}, got {actual:?}",
+                property.complete_type
+            );
+        }
+        Ok(SetProperty {
+            destination: Cow::Borrowed(&self.destination),
+            path: Cow::Borrowed(&self.path),
+            interface: Cow::Borrowed(&self.interface),
+            name: Cow::Borrowed(name),
+            value,
+        }
+        .into())
+    }
+
+    pub fn get_all_properties(&self) -> Message {
+        GetAllProperties {
+            destination: Cow::Borrowed(&self.destination),
+            path: Cow::Borrowed(&self.path),
+            interface: Cow::Borrowed(&self.interface),
+        }
+        .into()
+    }
+
+    /// The value in the reply to `get_property`.
+    pub fn property_reply(&self, reply: &Message) -> Result<Value> {
+        let [Value::Variant(value)] = reply_body(reply)? else {
+            bail!("expected a variant, got {reply:?}");
+        };
+        Ok((**value).clone())
+    }
+
+    /// The values in the reply to `get_all_properties`.
+    pub fn properties_reply(&self, reply: &Message) -> Result<BTreeMap<String, Value>> {
+        let [Value::Array(_, entries)] = reply_body(reply)? else {
+            bail!("expected a{{sv}}, got {reply:?}");
+        };
+        let mut out = BTreeMap::new();
+        for entry in entries {
+            value_is!(entry, Value::DictEntry(name, value));
+            value_is!(&**name, Value::String(name));
+            value_is!(&**value, Value::Variant(value));
+            out.insert(name.clone(), (**value).clone());
+        }
+        Ok(out)
+    }
+
+    /// Matches every signal of our interface sent by the object.
+    pub fn signals(&self) -> MatchRule {
+        MatchRule::new()
+            .sender(self.destination.clone())
+            .path(self.path.clone())
+            .interface(self.interface.clone())
+    }
+
+    /// Matches `member` only, which must be a known signal if introspected.
+    pub fn signal(&self, member: &str) -> Result<MatchRule> {
+        if let Some(introspection) = &self.introspection {
+            ensure!(
+                introspection.signal(member).is_some(),
+                "{} has no signal {member}",
+                self.interface
+            );
+        }
+        Ok(self.signals().member(member.to_string()))
+    }
+
+    /// Matches `PropertiesChanged` for the object.
+    pub fn properties_changed(&self) -> MatchRule {
+        MatchRule::new()
+            .sender(self.destination.clone())
+            .path(self.path.clone())
+            .interface(PROPERTIES)
+            .member("PropertiesChanged")
+    }
+
+    fn property(&self, name: &str) -> Result<Option<&introspection::Property>> {
+        let Some(introspection) = &self.introspection else {
+            return Ok(None);
+        };
+        let property = introspection
+            .property(name)
+            .with_context(|| format!("{} has no property {name}", self.interface))?;
+        Ok(Some(property))
+    }
+}
+
+fn reply_body(reply: &Message) -> Result<&[Value]> {
+    match reply {
+        Message::MethodReturn { body, .. } => Ok(body),
+        Message::Error {
+            error_name, body, ..
+        } => match body.first() {
+            Some(Value::String(message)) => bail!("{error_name}: {message}"),
+            _ => bail!("{error_name}"),
+        },
+        other => bail!("expected a reply, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_proxy() {
+    use crate::types::CompleteType;
+
+    let xml = r#"<node><interface name="org.me.Calc">
+        <method name="Plus">
+            <arg type="i" direction="in"/><arg type="i" direction="in"/>
+            <arg type="i" direction="out"/>
+        </method>
+        <signal name="Overflowed"/>
+        <property name="Precision" type="u" access="readwrite"/>
+        <property name="Version" type="s" access="read"/>
+    </interface></node>"#;
+    let reply = |body| Message::MethodReturn {
+        serial: 2,
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body,
+    };
+
+    let mut proxy = Proxy::new("org.me", "/org/me/calc", "org.me.Calc");
+    assert!(proxy.call("Minus", vec![]).is_ok());
+    proxy
+        .load_introspection(&reply(vec![Value::String(xml.into())]))
+        .unwrap();
+
+    let call = proxy
+        .call("Plus", vec![Value::Int32(1), Value::Int32(2)])
+        .unwrap();
+    assert_eq!(call.destination(), Some("org.me"));
+    assert_eq!(call.path(), Some("/org/me/calc"));
+    assert_eq!(call.interface(), Some("org.me.Calc"));
+    assert!(proxy.call("Minus", vec![]).is_err());
+    assert!(proxy.call("Plus", vec![Value::Int32(1)]).is_err());
+    let heterogenous = Value::Array(CompleteType::Int32, vec![Value::UInt32(1)]);
+    assert!(heterogenous.try_complete_type().is_err());
+    assert!(
+        proxy
+            .call("Plus", vec![Value::Int32(1), heterogenous.clone()])
+            .is_err()
+    );
+    assert_eq!(
+        proxy.reply("Plus", &reply(vec![Value::Int32(3)])).unwrap(),
+        [Value::Int32(3)]
+    );
+    assert!(proxy.reply("Plus", &reply(vec![])).is_err());
+    let error = Message::Error {
+        serial: 2,
+        error_name: "org.me.Error".into(),
+        reply_serial: 1,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![Value::String("nope".into())],
+    };
+    assert_eq!(
+        proxy.reply("Plus", &error).unwrap_err().to_string(),
+        "org.me.Error: nope"
+    );
+
+    let get = proxy.get_property("Version").unwrap();
+    assert_eq!(get.interface(), Some(PROPERTIES));
+    assert_eq!(
+        get.body(),
+        [
+            Value::String("org.me.Calc".into()),
+            Value::String("Version".into())
+        ]
+    );
+    assert!(proxy.get_property("Nope").is_err());
+    assert!(
+        proxy
+            .set_property("Version", Value::String("2".into()))
+            .is_err()
+    );
+    assert!(proxy.set_property("Precision", Value::Int32(2)).is_err());
+    assert!(proxy.set_property("Precision", heterogenous).is_err());
+    let set = proxy.set_property("Precision", Value::UInt32(2)).unwrap();
+    assert_eq!(set.body()[2], Value::Variant(Box::new(Value::UInt32(2))));
+
+    let value = reply(vec![Value::Variant(Box::new(Value::String("1.0".into())))]);
+    assert_eq!(
+        proxy.property_reply(&value).unwrap(),
+        Value::String("1.0".into())
+    );
+    let all = reply(vec![Value::Array(
+        CompleteType::DictEntry(
+            Box::new(CompleteType::String),
+            Box::new(CompleteType::Variant),
+        ),
+        vec![Value::DictEntry(
+            Box::new(Value::String("Precision".into())),
+            Box::new(Value::Variant(Box::new(Value::UInt32(2)))),
+        )],
+    )]);
+    assert_eq!(
+        proxy.properties_reply(&all).unwrap(),
+        BTreeMap::from([(String::from("Precision"), Value::UInt32(2))])
+    );
+
+    assert_eq!(
+        proxy.signal("Overflowed").unwrap().to_string(),
+        "type='signal',sender='org.me',interface='org.me.Calc',member='Overflowed',path='/org/me/calc'"
+    );
+    assert!(proxy.signal("Underflowed").is_err());
+}
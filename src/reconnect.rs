@@ -0,0 +1,251 @@
+use crate::types::{Message, Value};
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+
+/// Remembers what a connection set up on the bus, so it can be replayed on
+/// a fresh connection once the bus comes back, and schedules the attempts.
+///
+/// Like the rest of the crate it does no I/O and reads no clock. The
+/// application passes every message it sends to `outgoing`, calls
+/// `disconnected` when a backend reports `Disconnected`, opens a new
+/// connection once `should_reconnect` says so and enqueues what `replay`
+/// returns on it, passing those to `outgoing` too. `incoming` tells when
+/// the bus answered the replayed `Hello`.
+#[derive(Debug)]
+pub struct Reconnect {
+    /// Names requested with their `RequestName` flags
+    names: Vec<(String, u32)>,
+    match_rules: Vec<String>,
+
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff: Duration,
+    state: State,
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Connected,
+    WaitingUntil(Instant),
+    Connecting,
+    /// Replayed, waiting for the reply to the `Hello` with this serial
+    Replayed {
+        hello: Option<u32>,
+    },
+}
+
+impl Reconnect {
+    pub fn new(initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            names: vec![],
+            match_rules: vec![],
+            initial_backoff,
+            max_backoff,
+            backoff: initial_backoff,
+            state: State::Connected,
+        }
+    }
+
+    /// Records names and match rules `message` adds or removes, and the
+    /// serial of a replayed `Hello`.
+    pub fn outgoing(&mut self, message: &Message) {
+        let Message::MethodCall {
+            serial,
+            member,
+            interface: Some(interface),
+            destination: Some(destination),
+            body,
+            ..
+        } = message
+        else {
+            return;
+        };
+        if interface != "org.freedesktop.DBus" || destination != "org.freedesktop.DBus" {
+            return;
+        }
+
+        match (member.as_ref(), body.as_slice()) {
+            ("Hello", []) => {
+                if let State::Replayed { hello } = &mut self.state {
+                    *hello = Some(*serial);
+                }
+            }
+            ("RequestName", [Value::String(name), Value::UInt32(flags)]) => {
+                self.names.retain(|(known, _)| known != name);
+                self.names.push((name.clone(), *flags));
+            }
+            ("ReleaseName", [Value::String(name)]) => {
+                self.names.retain(|(known, _)| known != name);
+            }
+            // The bus counts duplicate rules, so they are kept as duplicates
+            ("AddMatch", [Value::String(rule)]) => self.match_rules.push(rule.clone()),
+            ("RemoveMatch", [Value::String(rule)]) => {
+                if let Some(idx) = self.match_rules.iter().position(|known| known == rule) {
+                    self.match_rules.remove(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `true` for the reply to the replayed `Hello`, which means
+    /// the new connection is up and the replayed state is being
+    /// re-established.
+    pub fn incoming(&mut self, message: &Message) -> bool {
+        let State::Replayed { hello: Some(hello) } = self.state else {
+            return false;
+        };
+        let Message::MethodReturn { reply_serial, .. } = message else {
+            return false;
+        };
+        if *reply_serial != hello {
+            return false;
+        }
+        self.state = State::Connected;
+        self.backoff = self.initial_backoff;
+        true
+    }
+
+    /// Schedules the next attempt, each failure in a row doubles the wait.
+    pub fn disconnected(&mut self, now: Instant) -> Instant {
+        if let State::WaitingUntil(deadline) = self.state {
+            return deadline;
+        }
+        let deadline = now + self.backoff;
+        self.backoff = (self.backoff * 2).min(self.max_backoff);
+        self.state = State::WaitingUntil(deadline);
+        deadline
+    }
+
+    /// When the next attempt is due, `None` unless disconnected.
+    pub fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            State::WaitingUntil(deadline) => Some(deadline),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` once, when the application should open a new
+    /// connection.
+    pub fn should_reconnect(&mut self, now: Instant) -> bool {
+        match self.state {
+            State::WaitingUntil(deadline) if now >= deadline => {
+                self.state = State::Connecting;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Messages to enqueue on the new connection, in order: `Hello`, the
+    /// owned names and the match rules. Empty unless `should_reconnect`
+    /// just returned `true`.
+    ///
+    /// The names and rules are recorded again as the replayed messages go
+    /// through `outgoing`, so rules aren't counted twice.
+    pub fn replay(&mut self) -> Vec<Message> {
+        if self.state != State::Connecting {
+            return vec![];
+        }
+        self.state = State::Replayed { hello: None };
+
+        let mut out = vec![bus_call("Hello", vec![])];
+        for (name, flags) in std::mem::take(&mut self.names) {
+            out.push(bus_call(
+                "RequestName",
+                vec![Value::String(name), Value::UInt32(flags)],
+            ));
+        }
+        for rule in std::mem::take(&mut self.match_rules) {
+            out.push(bus_call("AddMatch", vec![Value::String(rule)]));
+        }
+        out
+    }
+}
+
+fn bus_call(member: &'static str, body: Vec<Value>) -> Message {
+    Message::MethodCall {
+        serial: 0,
+        path: Cow::Borrowed("/org/freedesktop/DBus"),
+        member: Cow::Borrowed(member),
+        interface: Some(Cow::Borrowed("org.freedesktop.DBus")),
+        destination: Some(Cow::Borrowed("org.freedesktop.DBus")),
+        sender: None,
+        unix_fds: None,
+        no_reply_expected: false,
+        body,
+    }
+}
+
+#[test]
+fn test_reconnect() {
+    use crate::messages::{AddMatch, Hello, RequestName};
+
+    let mut reconnect = Reconnect::new(Duration::from_secs(1), Duration::from_secs(3));
+    reconnect.outgoing(&Hello.into());
+    reconnect.outgoing(&RequestName::new(Cow::Borrowed("org.me.test")).into());
+    reconnect.outgoing(&AddMatch::new(Cow::Borrowed("/a")).into());
+    reconnect.outgoing(&AddMatch::new(Cow::Borrowed("/b")).into());
+    reconnect.outgoing(&bus_call("ReleaseName", vec![Value::String("x".into())]));
+    let Message::MethodCall { body, .. } = Message::from(AddMatch::new(Cow::Borrowed("/a"))) else {
+        unreachable!()
+    };
+    reconnect.outgoing(&bus_call("RemoveMatch", body));
+
+    let start = Instant::now();
+    assert_eq!(reconnect.deadline(), None);
+    let deadline = reconnect.disconnected(start);
+    assert_eq!(deadline, start + Duration::from_secs(1));
+    assert!(!reconnect.should_reconnect(start));
+    assert!(reconnect.should_reconnect(deadline));
+    assert!(!reconnect.should_reconnect(deadline));
+
+    // The attempt fails, the wait doubles up to the maximum
+    assert_eq!(
+        reconnect.disconnected(deadline),
+        deadline + Duration::from_secs(2)
+    );
+    reconnect.should_reconnect(deadline + Duration::from_secs(2));
+    let now = deadline + Duration::from_secs(2);
+    assert_eq!(reconnect.disconnected(now), now + Duration::from_secs(3));
+    reconnect.should_reconnect(now + Duration::from_secs(3));
+
+    let mut replay = reconnect.replay();
+    assert_eq!(
+        replay,
+        vec![
+            Hello.into(),
+            RequestName::new(Cow::Borrowed("org.me.test")).into(),
+            AddMatch::new(Cow::Borrowed("/b")).into(),
+        ]
+    );
+    assert!(reconnect.replay().is_empty());
+    // The application enqueues them, which numbers them
+    for (serial, message) in (10..).zip(&mut replay) {
+        *message.serial_mut() = serial;
+        reconnect.outgoing(message);
+    }
+
+    let reply = |reply_serial| Message::MethodReturn {
+        serial: 1,
+        reply_serial,
+        destination: None,
+        sender: None,
+        unix_fds: None,
+        body: vec![],
+    };
+    assert!(!reconnect.incoming(&Hello.into()));
+    assert!(!reconnect.incoming(&reply(11)));
+    assert!(reconnect.incoming(&reply(10)));
+    assert!(!reconnect.incoming(&reply(10)));
+    assert!(reconnect.replay().is_empty());
+
+    let now = now + Duration::from_secs(10);
+    assert_eq!(reconnect.disconnected(now), now + Duration::from_secs(1));
+    assert!(reconnect.should_reconnect(now + Duration::from_secs(1)));
+    let again = reconnect.replay();
+    assert_eq!(again.len(), 3);
+    assert_eq!(again[2], AddMatch::new(Cow::Borrowed("/b")).into());
+}
@@ -0,0 +1,258 @@
+use crate::{
+    decoders::{DecodingBuffer, ValueDecoder},
+    serde::{Error, error::mismatch},
+    types::CompleteType,
+};
+use ::serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Reads a serde value of the given D-Bus type out of a `DecodingBuffer`.
+///
+/// Strings and byte arrays are borrowed from the input where the visitor
+/// allows it.
+pub struct Deserializer<'a, 'de> {
+    buf: &'a mut DecodingBuffer<'de>,
+    ty: &'a CompleteType,
+}
+
+impl<'a, 'de> Deserializer<'a, 'de> {
+    pub(crate) fn new(buf: &'a mut DecodingBuffer<'de>, ty: &'a CompleteType) -> Self {
+        Self { buf, ty }
+    }
+
+    fn decode_str(&mut self) -> Result<&'de str> {
+        match self.ty {
+            CompleteType::Signature => {
+                let signature = ValueDecoder::decode_signature(self.buf)?;
+                std::str::from_utf8(signature).map_err(|err| Error::from(anyhow::Error::from(err)))
+            }
+            _ => Ok(ValueDecoder::decode_string(self.buf)?),
+        }
+    }
+
+    /// Reads the byte length of an array and returns the position it ends at.
+    fn begin_array(&mut self, item: &CompleteType) -> Result<usize> {
+        let byte_len = ValueDecoder::decode_u32(self.buf)? as usize;
+        self.buf.align(item.alignment())?;
+        Ok(self.buf.pos() + byte_len)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        match self.ty {
+            CompleteType::Byte => visitor.visit_u8(ValueDecoder::decode_u8(self.buf)?),
+            CompleteType::Bool => visitor.visit_bool(ValueDecoder::decode_bool(self.buf)?),
+            CompleteType::Int16 => visitor.visit_i16(ValueDecoder::decode_i16(self.buf)?),
+            CompleteType::UInt16 => visitor.visit_u16(ValueDecoder::decode_u16(self.buf)?),
+            CompleteType::Int32 => visitor.visit_i32(ValueDecoder::decode_i32(self.buf)?),
+            CompleteType::UInt32 | CompleteType::UnixFD => {
+                visitor.visit_u32(ValueDecoder::decode_u32(self.buf)?)
+            }
+            CompleteType::Int64 => visitor.visit_i64(ValueDecoder::decode_i64(self.buf)?),
+            CompleteType::UInt64 => visitor.visit_u64(ValueDecoder::decode_u64(self.buf)?),
+            CompleteType::Double => visitor.visit_f64(ValueDecoder::decode_f64(self.buf)?),
+            CompleteType::String | CompleteType::ObjectPath | CompleteType::Signature => {
+                visitor.visit_borrowed_str(self.decode_str()?)
+            }
+            CompleteType::Struct(fields) => {
+                self.buf.align(8)?;
+                let mut access = StructAccess {
+                    buf: self.buf,
+                    fields: fields.iter().collect(),
+                    idx: 0,
+                };
+                let value = visitor.visit_seq(&mut access)?;
+                access.finish()?;
+                Ok(value)
+            }
+            CompleteType::DictEntry(key, value) => {
+                self.buf.align(8)?;
+                let mut access = StructAccess {
+                    buf: self.buf,
+                    fields: vec![key, value],
+                    idx: 0,
+                };
+                let value = visitor.visit_seq(&mut access)?;
+                access.finish()?;
+                Ok(value)
+            }
+            CompleteType::Array(item) => {
+                let end = self.begin_array(item)?;
+                let mut access = ArrayAccess {
+                    buf: self.buf,
+                    item,
+                    end,
+                };
+                let value = match &**item {
+                    CompleteType::DictEntry(_, _) => visitor.visit_map(&mut access)?,
+                    _ => visitor.visit_seq(&mut access)?,
+                };
+                access.finish()?;
+                Ok(value)
+            }
+            CompleteType::Variant => {
+                let ty = ValueDecoder::decode_complete_type(self.buf)?;
+                Deserializer::new(self.buf, &ty).deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        match self.ty {
+            CompleteType::Array(item) if **item == CompleteType::Byte => {
+                let end = self.begin_array(item)?;
+                let bytes = self.buf.next_n(end - self.buf.pos())?;
+                visitor.visit_borrowed_bytes(bytes)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        let CompleteType::Array(item) = self.ty else {
+            return Err(mismatch!(self.ty, "an Option"));
+        };
+        let end = self.begin_array(item)?;
+        if self.buf.pos() >= end {
+            return visitor.visit_none();
+        }
+        let value = visitor.visit_some(Deserializer::new(self.buf, item))?;
+        if self.buf.pos() != end {
+            return Err(Error::from(anyhow::anyhow!(
+                "expected at most one item for an Option"
+            )));
+        }
+        Ok(value)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.ty {
+            CompleteType::String => visitor.visit_enum(self.decode_str()?.into_deserializer()),
+            CompleteType::UInt32 => {
+                let idx = ValueDecoder::decode_u32(self.buf)?;
+                visitor.visit_enum(idx.into_deserializer())
+            }
+            other => Err(mismatch!(other, name)),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct StructAccess<'a, 'de> {
+    buf: &'a mut DecodingBuffer<'de>,
+    fields: Vec<&'a CompleteType>,
+    idx: usize,
+}
+
+impl StructAccess<'_, '_> {
+    fn finish(&self) -> Result<()> {
+        if self.idx != self.fields.len() {
+            return Err(Error::from(anyhow::anyhow!(
+                "expected {} struct fields, got {}",
+                self.fields.len(),
+                self.idx
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for StructAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        let Some(&ty) = self.fields.get(self.idx) else {
+            return Ok(None);
+        };
+        self.idx += 1;
+        seed.deserialize(Deserializer::new(self.buf, ty)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len() - self.idx)
+    }
+}
+
+struct ArrayAccess<'a, 'de> {
+    buf: &'a mut DecodingBuffer<'de>,
+    item: &'a CompleteType,
+    end: usize,
+}
+
+impl<'a> ArrayAccess<'a, '_> {
+    fn finish(&self) -> Result<()> {
+        if self.buf.pos() != self.end {
+            return Err(Error::from(anyhow::anyhow!(
+                "array length does not match its items"
+            )));
+        }
+        Ok(())
+    }
+
+    fn entry_types(&self) -> Result<(&'a CompleteType, &'a CompleteType)> {
+        match self.item {
+            CompleteType::DictEntry(key, value) => Ok((key, value)),
+            other => Err(mismatch!(other, "a map entry")),
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for ArrayAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.buf.pos() >= self.end {
+            return Ok(None);
+        }
+        seed.deserialize(Deserializer::new(self.buf, self.item))
+            .map(Some)
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ArrayAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.buf.pos() >= self.end {
+            return Ok(None);
+        }
+        let (key_type, _) = self.entry_types()?;
+        self.buf.align(8)?;
+        seed.deserialize(Deserializer::new(self.buf, key_type))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (_, value_type) = self.entry_types()?;
+        seed.deserialize(Deserializer::new(self.buf, value_type))
+    }
+}
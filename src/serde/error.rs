@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// Error produced while mapping serde types to and from the wire format.
+#[derive(Debug)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        Self(format!("{err:#}"))
+    }
+}
+
+macro_rules! mismatch {
+    ($expected:expr, $got:expr) => {
+        $crate::serde::Error::from(anyhow::anyhow!(
+            "cannot map {} to D-Bus type {}",
+            $got,
+            $expected
+        ))
+    };
+}
+pub(crate) use mismatch;
@@ -0,0 +1,174 @@
+//! Serde support for the D-Bus wire format.
+//!
+//! Values are written and read against an explicit `CompleteType` rather
+//! than through `Value`. The serde data model maps as follows:
+//!
+//! - `bool`, integers and `f64` map to their D-Bus counterparts, `i8` is
+//!   widened to `n` and `f32` to `d`. Integers may be written into any
+//!   integer type they fit in.
+//! - `str` and `char` map to `s`, `o` or `g`, bytes to `ay`.
+//! - Sequences map to arrays, maps to `a{..}`, tuples and structs to `(..)`.
+//! - `Option<T>` maps to `aT` holding zero or one item.
+//! - Unit enum variants map to their index as `u`, or their name as `s`.
+//! - Newtype structs are transparent.
+//! - A `v` slot accepts any value whose type can be inferred from the value
+//!   itself, so it must not contain empty sequences, maps or `None`.
+
+mod de;
+mod error;
+mod ser;
+mod signature;
+mod value_ser;
+
+pub use de::Deserializer;
+pub use error::Error;
+pub(crate) use ser::serialize_into;
+pub use ser::{ArraySerializer, Serializer, StructSerializer};
+
+use crate::{decoders::DecodingBuffer, encoders::EncodingBuffer, types::CompleteType};
+use ::serde::{Deserialize, Serialize};
+use anyhow::{Result, ensure};
+
+/// Returns the D-Bus type `T` (de)serializes as.
+pub fn signature_of<'de, T: Deserialize<'de>>() -> Result<CompleteType> {
+    let mut out = None;
+    T::deserialize(signature::Tracer::new(&mut out))?;
+    out.ok_or_else(|| anyhow::anyhow!("type has no D-Bus representation"))
+}
+
+/// Encodes `value` as `ty`, starting at an 8-aligned offset.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T, ty: &CompleteType) -> Result<Vec<u8>> {
+    let mut buf = EncodingBuffer::new();
+    serialize_into(&mut buf, ty, value)?;
+    Ok(buf.done())
+}
+
+/// Decodes a `T` of type `ty` from `bytes`, which must be consumed entirely.
+pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8], ty: &CompleteType) -> Result<T> {
+    let mut buf = DecodingBuffer::new(bytes);
+    let value = T::deserialize(Deserializer::new(&mut buf, ty))?;
+    ensure!(buf.is_eof(), "trailing bytes after {ty}");
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_bytes, signature_of, to_bytes};
+    use crate::{
+        encoders::{EncodingBuffer, MessageEncoder, ValueEncoder},
+        types::{CompleteType, DBusType, Message, Value},
+    };
+    use ::serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Urgency {
+        Low,
+        Normal,
+        Critical,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Notification<'a> {
+        id: u32,
+        #[serde(borrow)]
+        summary: &'a str,
+        actions: Vec<String>,
+        hints: HashMap<String, u64>,
+        urgency: Urgency,
+        icon: Option<String>,
+        timeout: i8,
+    }
+
+    #[test]
+    fn test_signature_of() {
+        assert_eq!(
+            signature_of::<Notification>().unwrap().to_string(),
+            "(usasa{st}uasn)"
+        );
+        assert_eq!(signature_of::<Vec<u8>>().unwrap().to_string(), "ay");
+        assert_eq!(
+            signature_of::<Option<u32>>().unwrap(),
+            <Option<u32> as DBusType>::complete_type()
+        );
+        assert!(signature_of::<()>().is_err());
+    }
+
+    #[test]
+    fn test_round_trip_matches_value_encoding() {
+        let mut hints = HashMap::new();
+        hints.insert(String::from("x"), 7);
+        let notification = Notification {
+            id: 1,
+            summary: "hello",
+            actions: vec![],
+            hints,
+            urgency: Urgency::Critical,
+            icon: None,
+            timeout: -1,
+        };
+        let ty = signature_of::<Notification>().unwrap();
+        let bytes = to_bytes(&notification, &ty).unwrap();
+
+        let mut buf = EncodingBuffer::new();
+        ValueEncoder::encode_value(
+            &mut buf,
+            &Value::Struct(vec![
+                Value::UInt32(1),
+                Value::String(String::from("hello")),
+                Value::Array(CompleteType::String, vec![]),
+                Value::from(HashMap::from([(String::from("x"), 7_u64)])),
+                Value::UInt32(2),
+                Value::from(None::<String>),
+                Value::Int16(-1),
+            ]),
+        );
+        assert_eq!(bytes, buf.done());
+
+        let decoded: Notification = from_bytes(&bytes, &ty).unwrap();
+        assert_eq!(decoded, notification);
+    }
+
+    #[test]
+    fn test_variant() {
+        let ty = "a{sv}".parse::<CompleteType>().unwrap();
+        let map = HashMap::from([(String::from("k"), (1_u8, String::from("v")))]);
+        let bytes = to_bytes(&map, &ty).unwrap();
+        let decoded: HashMap<String, (u8, String)> = from_bytes(&bytes, &ty).unwrap();
+        assert_eq!(decoded, map);
+
+        let empty = HashMap::from([(String::from("k"), Vec::<u32>::new())]);
+        assert!(to_bytes(&empty, &ty).is_err());
+    }
+
+    #[test]
+    fn test_encode_with_body() {
+        let message = |body| Message::Signal {
+            serial: 3,
+            path: "/a".into(),
+            interface: "a.b".into(),
+            member: "C".into(),
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body,
+        };
+        let expected = MessageEncoder::encode(&message(vec![
+            Value::String(String::from("x")),
+            Value::Array(CompleteType::UInt32, vec![]),
+        ]))
+        .unwrap();
+
+        let body = ("x", Vec::<u32>::new());
+        let ty = signature_of::<(&str, Vec<u32>)>().unwrap();
+        let encoded = MessageEncoder::encode_with_body(&message(vec![]), &body, &ty).unwrap();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        assert!(to_bytes(&300_u32, &CompleteType::Byte).is_err());
+        assert!(to_bytes("s", &CompleteType::UInt32).is_err());
+        assert!(from_bytes::<u32>(&[0, 0, 0, 0, 0], &CompleteType::UInt32).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{
+    encoders::{EncodingBuffer, ValueEncoder},
+    serde::{Error, error::mismatch, value_ser::ValueSerializer},
+    types::CompleteType,
+};
+use ::serde::ser::{self, Serialize};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Writes a serde value into an `EncodingBuffer` as the given D-Bus type.
+///
+/// The target type drives alignment and lets empty arrays be encoded
+/// correctly, which the serde data model alone can't describe.
+pub struct Serializer<'a> {
+    buf: &'a mut EncodingBuffer,
+    ty: &'a CompleteType,
+}
+
+impl<'a> Serializer<'a> {
+    pub(crate) fn new(buf: &'a mut EncodingBuffer, ty: &'a CompleteType) -> Self {
+        Self { buf, ty }
+    }
+
+    fn serialize_integer(self, value: i128) -> Result<()> {
+        let out_of_range =
+            || Error::from(anyhow::anyhow!("{value} is out of range for {}", self.ty));
+        match self.ty {
+            CompleteType::Byte => {
+                ValueEncoder::encode_u8(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::Int16 => {
+                ValueEncoder::encode_i16(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::UInt16 => {
+                ValueEncoder::encode_u16(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::Int32 => {
+                ValueEncoder::encode_i32(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::UInt32 | CompleteType::UnixFD => {
+                ValueEncoder::encode_u32(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::Int64 => {
+                ValueEncoder::encode_i64(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            CompleteType::UInt64 => {
+                ValueEncoder::encode_u64(self.buf, value.try_into().map_err(|_| out_of_range())?)
+            }
+            other => return Err(mismatch!(other, "an integer")),
+        }
+        Ok(())
+    }
+
+    fn begin_array(self) -> Result<ArraySerializer<'a>> {
+        let CompleteType::Array(item) = self.ty else {
+            return Err(mismatch!(self.ty, "a sequence"));
+        };
+        Ok(ArraySerializer::begin(self.buf, item))
+    }
+
+    fn begin_struct(self, len: usize) -> Result<StructSerializer<'a>> {
+        let fields: Vec<&CompleteType> = match self.ty {
+            CompleteType::Struct(fields) => fields.iter().collect(),
+            CompleteType::DictEntry(key, value) => vec![key, value],
+            other => return Err(mismatch!(other, "a struct")),
+        };
+        if fields.len() != len {
+            return Err(mismatch!(
+                self.ty,
+                format_args!("a struct with {len} fields")
+            ));
+        }
+        self.buf.align(8);
+        Ok(StructSerializer {
+            buf: self.buf,
+            fields,
+            idx: 0,
+        })
+    }
+}
+
+/// Variant slots carry their own signature, so the value is first turned
+/// into a `Value` tree to find out its type.
+pub(crate) fn serialize_into<T>(
+    buf: &mut EncodingBuffer,
+    ty: &CompleteType,
+    value: &T,
+) -> Result<()>
+where
+    T: Serialize + ?Sized,
+{
+    if *ty == CompleteType::Variant {
+        let inner = value.serialize(ValueSerializer)?;
+        ValueEncoder::encode_variant(buf, &inner);
+        Ok(())
+    } else {
+        value.serialize(Serializer::new(buf, ty))
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ArraySerializer<'a>;
+    type SerializeTuple = StructSerializer<'a>;
+    type SerializeTupleStruct = StructSerializer<'a>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ArraySerializer<'a>;
+    type SerializeStruct = StructSerializer<'a>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        match self.ty {
+            CompleteType::Bool => {
+                ValueEncoder::encode_bool(self.buf, v);
+                Ok(())
+            }
+            other => Err(mismatch!(other, "bool")),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.serialize_integer(v.into())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        match self.ty {
+            CompleteType::Double => {
+                ValueEncoder::encode_f64(self.buf, v);
+                Ok(())
+            }
+            other => Err(mismatch!(other, "a float")),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        match self.ty {
+            CompleteType::String => ValueEncoder::encode_str(self.buf, v),
+            CompleteType::ObjectPath => ValueEncoder::encode_object_path(self.buf, v),
+            CompleteType::Signature => {
+                let len = u8::try_from(v.len())
+                    .map_err(|_| Error::from(anyhow::anyhow!("signature {v:?} is too long")))?;
+                ValueEncoder::encode_u8(self.buf, len);
+                self.buf.encode_bytes(v.as_bytes());
+                self.buf.encode_u8(0);
+            }
+            other => return Err(mismatch!(other, "a string")),
+        }
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if *self.ty != CompleteType::Array(Box::new(CompleteType::Byte)) {
+            return Err(mismatch!(self.ty, "bytes"));
+        }
+        ValueEncoder::encode_u32(self.buf, v.len() as u32);
+        self.buf.encode_bytes(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        ser::SerializeSeq::end(self.begin_array()?)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        let mut array = self.begin_array()?;
+        ser::SerializeSeq::serialize_element(&mut array, value)?;
+        ser::SerializeSeq::end(array)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(mismatch!(self.ty, "()"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        Err(mismatch!(self.ty, name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        match self.ty {
+            CompleteType::String => self.serialize_str(variant),
+            _ => self.serialize_u32(variant_index),
+        }
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(mismatch!(self.ty, format_args!("{name}::{variant}")))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.begin_array()
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.begin_struct(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.begin_struct(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(mismatch!(self.ty, format_args!("{name}::{variant}")))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        match self.ty {
+            CompleteType::Array(item) if matches!(**item, CompleteType::DictEntry(_, _)) => {
+                self.begin_array()
+            }
+            other => Err(mismatch!(other, "a map")),
+        }
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.begin_struct(len)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(mismatch!(self.ty, format_args!("{name}::{variant}")))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+pub struct ArraySerializer<'a> {
+    buf: &'a mut EncodingBuffer,
+    item: &'a CompleteType,
+    len_pos: usize,
+    data_start: usize,
+}
+
+impl<'a> ArraySerializer<'a> {
+    fn begin(buf: &'a mut EncodingBuffer, item: &'a CompleteType) -> Self {
+        buf.align(4);
+        let len_pos = buf.size();
+        buf.encode_u32(0);
+        buf.align(item.alignment());
+        let data_start = buf.size();
+        Self {
+            buf,
+            item,
+            len_pos,
+            data_start,
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        let byte_len = (self.buf.size() - self.data_start) as u32;
+        self.buf.set_u32(self.len_pos, byte_len)?;
+        Ok(())
+    }
+
+    fn entry_types(&self) -> Result<(&'a CompleteType, &'a CompleteType)> {
+        match self.item {
+            CompleteType::DictEntry(key, value) => Ok((key, value)),
+            other => Err(mismatch!(other, "a map entry")),
+        }
+    }
+}
+
+impl ser::SerializeSeq for ArraySerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        serialize_into(self.buf, self.item, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for ArraySerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let (key_type, _) = self.entry_types()?;
+        self.buf.align(8);
+        serialize_into(self.buf, key_type, key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let (_, value_type) = self.entry_types()?;
+        serialize_into(self.buf, value_type, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+pub struct StructSerializer<'a> {
+    buf: &'a mut EncodingBuffer,
+    fields: Vec<&'a CompleteType>,
+    idx: usize,
+}
+
+impl StructSerializer<'_> {
+    fn field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let ty = self
+            .fields
+            .get(self.idx)
+            .ok_or_else(|| Error::from(anyhow::anyhow!("too many struct fields")))?;
+        self.idx += 1;
+        serialize_into(self.buf, ty, value)
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.idx != self.fields.len() {
+            return Err(Error::from(anyhow::anyhow!(
+                "expected {} struct fields, got {}",
+                self.fields.len(),
+                self.idx
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for StructSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.field(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for StructSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.field(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for StructSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.field(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
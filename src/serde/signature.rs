@@ -0,0 +1,298 @@
+use crate::{serde::Error, types::CompleteType};
+use ::serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Derives a D-Bus type by walking a `Deserialize` impl: every sequence and
+/// map is fed exactly one element and every leaf a default value, and the
+/// requested shapes are recorded along the way.
+pub(crate) struct Tracer<'a> {
+    out: &'a mut Option<CompleteType>,
+}
+
+impl<'a> Tracer<'a> {
+    pub(crate) fn new(out: &'a mut Option<CompleteType>) -> Self {
+        Self { out }
+    }
+
+    fn leaf(self, ty: CompleteType) {
+        *self.out = Some(ty);
+    }
+}
+
+fn untraceable(what: &str) -> Error {
+    Error::from(anyhow::anyhow!("{what} has no D-Bus type"))
+}
+
+fn trace<'de, T: DeserializeSeed<'de>>(seed: T) -> Result<(T::Value, CompleteType)> {
+    let mut out = None;
+    let value = seed.deserialize(Tracer::new(&mut out))?;
+    let ty = out.ok_or_else(|| untraceable("an element"))?;
+    Ok((value, ty))
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(untraceable("a self-describing type"))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Bool);
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Int16);
+        visitor.visit_i8(0)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Int16);
+        visitor.visit_i16(0)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Int32);
+        visitor.visit_i32(0)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Int64);
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Byte);
+        visitor.visit_u8(0)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::UInt16);
+        visitor.visit_u16(0)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::UInt32);
+        visitor.visit_u32(0)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::UInt64);
+        visitor.visit_u64(0)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Double);
+        visitor.visit_f32(0.0)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Double);
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::String);
+        visitor.visit_char('\0')
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::String);
+        visitor.visit_borrowed_str("")
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.leaf(CompleteType::Array(Box::new(CompleteType::Byte)));
+        visitor.visit_borrowed_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let (value, item) = trace(OptionSeed(visitor))?;
+        self.leaf(CompleteType::Array(Box::new(item)));
+        Ok(value)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(untraceable("()"))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(untraceable(name))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut access = OneElement { item: None };
+        let value = visitor.visit_seq(&mut access)?;
+        let item = access.item.ok_or_else(|| untraceable("a sequence"))?;
+        self.leaf(CompleteType::Array(Box::new(item)));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let mut access = Fields {
+            remaining: len,
+            types: vec![],
+        };
+        let value = visitor.visit_seq(&mut access)?;
+        if access.types.is_empty() {
+            return Err(untraceable("an empty struct"));
+        }
+        self.leaf(CompleteType::Struct(access.types));
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut access = OneEntry {
+            key: None,
+            value: None,
+        };
+        let value = visitor.visit_map(&mut access)?;
+        let (Some(key), Some(entry_value)) = (access.key, access.value) else {
+            return Err(untraceable("a map"));
+        };
+        self.leaf(CompleteType::Array(Box::new(CompleteType::DictEntry(
+            Box::new(key),
+            Box::new(entry_value),
+        ))));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.leaf(CompleteType::UInt32);
+        visitor.visit_enum(0_u32.into_deserializer())
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(untraceable("an identifier"))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(untraceable("an ignored value"))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct OptionSeed<V>(V);
+
+impl<'de, V: Visitor<'de>> DeserializeSeed<'de> for OptionSeed<V> {
+    type Value = V::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.visit_some(deserializer)
+    }
+}
+
+struct OneElement {
+    item: Option<CompleteType>,
+}
+
+impl<'de> de::SeqAccess<'de> for OneElement {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.item.is_some() {
+            return Ok(None);
+        }
+        let (value, ty) = trace(seed)?;
+        self.item = Some(ty);
+        Ok(Some(value))
+    }
+}
+
+struct Fields {
+    remaining: usize,
+    types: Vec<CompleteType>,
+}
+
+impl<'de> de::SeqAccess<'de> for Fields {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let (value, ty) = trace(seed)?;
+        self.types.push(ty);
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct OneEntry {
+    key: Option<CompleteType>,
+    value: Option<CompleteType>,
+}
+
+impl<'de> de::MapAccess<'de> for OneEntry {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.key.is_some() {
+            return Ok(None);
+        }
+        let (value, ty) = trace(seed)?;
+        self.key = Some(ty);
+        Ok(Some(value))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (value, ty) = trace(seed)?;
+        self.value = Some(ty);
+        Ok(value)
+    }
+}
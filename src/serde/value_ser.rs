@@ -0,0 +1,291 @@
+use crate::{
+    serde::Error,
+    types::{CompleteType, Value},
+};
+use ::serde::ser::{self, Serialize};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Builds a `Value` from the serde data model alone, used for variant slots
+/// where there's no signature to follow. Empty sequences and maps have no
+/// inferable item type and are rejected.
+pub(crate) struct ValueSerializer;
+
+fn untyped(what: &str) -> Error {
+    Error::from(anyhow::anyhow!(
+        "cannot infer the D-Bus type of {what} inside a variant"
+    ))
+}
+
+fn array(items: Vec<Value>) -> Result<Value> {
+    let item_type = items
+        .first()
+        .ok_or_else(|| untyped("an empty sequence"))?
+        .complete_type();
+    if items.iter().any(|item| item.complete_type() != item_type) {
+        return Err(Error::from(anyhow::anyhow!(
+            "sequence items inside a variant have different types"
+        )));
+    }
+    Ok(Value::Array(item_type, items))
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = StructBuilder;
+    type SerializeTupleStruct = StructBuilder;
+    type SerializeTupleVariant = ser::Impossible<Value, Error>;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = StructBuilder;
+    type SerializeStructVariant = ser::Impossible<Value, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::Int16(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::Int16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::Int32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::Byte(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::UInt16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::UInt32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok(Value::UInt64(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::Double(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Array(
+            CompleteType::Byte,
+            v.iter().copied().map(Value::Byte).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Err(untyped("None"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        let inner = value.serialize(self)?;
+        Ok(Value::Array(inner.complete_type(), vec![inner]))
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Err(untyped("()"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Value> {
+        Err(untyped(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::UInt32(variant_index))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value> {
+        Err(untyped(name))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder> {
+        Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<StructBuilder> {
+        Ok(StructBuilder(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<StructBuilder> {
+        Ok(StructBuilder(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(untyped(name))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder> {
+        Ok(MapBuilder {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<StructBuilder> {
+        Ok(StructBuilder(Vec::with_capacity(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(untyped(name))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) struct SeqBuilder(Vec<Value>);
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        array(self.0)
+    }
+}
+
+pub(crate) struct StructBuilder(Vec<Value>);
+
+impl ser::SerializeTuple for StructBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Struct(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for StructBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Struct(self.0))
+    }
+}
+
+impl ser::SerializeStruct for StructBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Struct(self.0))
+    }
+}
+
+pub(crate) struct MapBuilder {
+    items: Vec<Value>,
+    key: Option<Value>,
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::from(anyhow::anyhow!("map value without a key")))?;
+        let value = value.serialize(ValueSerializer)?;
+        self.items
+            .push(Value::DictEntry(Box::new(key), Box::new(value)));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        if self.items.is_empty() {
+            return Err(untyped("an empty map"));
+        }
+        array(self.items)
+    }
+}
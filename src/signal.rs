@@ -0,0 +1,101 @@
+use crate::{
+    introspection,
+    types::{Message, Value},
+};
+use anyhow::Result;
+use std::borrow::Cow;
+
+/// Builds an outgoing signal. Signals are broadcast to every matching rule
+/// unless a `destination` is set, in which case only that peer gets them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalBuilder {
+    path: Cow<'static, str>,
+    interface: Cow<'static, str>,
+    member: Cow<'static, str>,
+    destination: Option<Cow<'static, str>>,
+    args: Vec<Value>,
+}
+
+impl SignalBuilder {
+    pub fn new(
+        path: impl Into<Cow<'static, str>>,
+        interface: impl Into<Cow<'static, str>>,
+        member: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            interface: interface.into(),
+            member: member.into(),
+            destination: None,
+            args: vec![],
+        }
+    }
+
+    pub fn destination(mut self, destination: impl Into<Cow<'static, str>>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<Value>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args(mut self, args: impl IntoIterator<Item = Value>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    /// Checks the arguments against the introspected `signal`.
+    pub fn validate(&self, signal: &introspection::Signal) -> Result<()> {
+        signal.validate(&self.args)
+    }
+}
+
+impl From<SignalBuilder> for Message {
+    fn from(value: SignalBuilder) -> Message {
+        Message::Signal {
+            serial: 0,
+            path: value.path,
+            interface: value.interface,
+            member: value.member,
+            destination: value.destination,
+            sender: None,
+            unix_fds: None,
+            body: value.args,
+        }
+    }
+}
+
+#[test]
+fn test_signal_builder() {
+    use crate::introspection::Node;
+
+    let signal = SignalBuilder::new("/org/me/calc", "org.me.Calc", "Computed")
+        .arg(3)
+        .arg("three");
+    assert_eq!(
+        Message::from(signal.clone()),
+        Message::Signal {
+            serial: 0,
+            path: Cow::Borrowed("/org/me/calc"),
+            interface: Cow::Borrowed("org.me.Calc"),
+            member: Cow::Borrowed("Computed"),
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![Value::Int32(3), Value::String("three".into())],
+        }
+    );
+
+    let unicast = Message::from(signal.clone().destination(":1.5"));
+    assert_eq!(unicast.destination(), Some(":1.5"));
+
+    let xml = r#"<node><interface name="org.me.Calc">
+        <signal name="Computed"><arg type="i"/><arg type="s"/></signal>
+    </interface></node>"#;
+    let node = Node::parse(xml).unwrap();
+    let computed = node.interfaces[0].signal("Computed").unwrap();
+    assert!(signal.validate(computed).is_ok());
+    assert!(signal.arg(true).validate(computed).is_err());
+}
@@ -0,0 +1,268 @@
+use crate::{
+    disconnected::Disconnected,
+    fsm::{AuthFSM, AuthWants, MAX_IOVECS, ReaderFSM, WriterFSM},
+    serial::Serial,
+    session_connection,
+    types::Message,
+};
+use anyhow::Result;
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    io::{ErrorKind, IoSlice},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use tokio::net::UnixStream;
+
+/// A connection for the tokio runtime.
+///
+/// There is no background task: reads and writes only make progress while
+/// a future returned by the connection, or its `Stream` of incoming
+/// messages, is being polled. Messages read while waiting for a reply are
+/// kept for the stream.
+pub struct AsyncConnection {
+    stream: UnixStream,
+    serial: Serial,
+
+    reader: ReaderFSM,
+    writer: WriterFSM,
+    incoming: VecDeque<Message>,
+
+    disconnected: Option<Disconnected>,
+}
+
+impl AsyncConnection {
+    /// Connects to the session bus and authenticates.
+    pub async fn session() -> Result<Self> {
+        Self::from_std(session_connection()?).await
+    }
+
+    /// Authenticates over an already connected `stream`.
+    pub async fn from_std(stream: std::os::unix::net::UnixStream) -> Result<Self> {
+        stream.set_nonblocking(true)?;
+        let mut out = Self {
+            stream: UnixStream::from_std(stream)?,
+            serial: Serial::zero(),
+
+            reader: ReaderFSM::new(),
+            writer: WriterFSM::new(),
+            incoming: VecDeque::new(),
+
+            disconnected: None,
+        };
+        out.auth().await?;
+        Ok(out)
+    }
+
+    async fn auth(&mut self) -> Result<()> {
+        let mut auth = AuthFSM::new();
+        loop {
+            match auth.wants() {
+                AuthWants::Read(buf) => {
+                    self.stream.readable().await?;
+                    let read = self.stream.try_read(buf);
+                    if let Some(len) = self.check_io(read)? {
+                        auth.satisfy_read(len)?;
+                    }
+                }
+
+                AuthWants::Write(bytes) => {
+                    self.stream.writable().await?;
+                    let written = self.stream.try_write(bytes);
+                    if let Some(len) = self.check_io(written)?
+                        && let Some(_guid) = auth.satisfy_write(len)?
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Queues `message` without waiting for it to be written.
+    pub fn enqueue(&mut self, message: &mut Message) -> Result<()> {
+        self.check_connected()?;
+        *message.serial_mut() = self.serial.increment_and_get();
+        self.writer.enqueue_message(message)
+    }
+
+    /// Writes out everything queued so far.
+    pub async fn flush(&mut self) -> Result<()> {
+        poll_fn(|cx| self.poll_flush(cx)).await
+    }
+
+    pub async fn send(&mut self, message: &mut Message) -> Result<()> {
+        self.enqueue(message)?;
+        self.flush().await
+    }
+
+    /// Sends a method call and waits for its reply or error.
+    pub async fn call(&mut self, message: &mut Message) -> Result<Message> {
+        self.enqueue(message)?;
+        let serial = message.serial();
+        poll_fn(|cx| self.poll_reply(serial, cx)).await
+    }
+
+    fn poll_reply(&mut self, serial: u32, cx: &mut Context<'_>) -> Poll<Result<Message>> {
+        if let Poll::Ready(Err(err)) = self.poll_flush(cx) {
+            return Poll::Ready(Err(err));
+        }
+        loop {
+            let message = ready!(self.poll_read_message(cx))?;
+            if message.reply_serial() == Some(serial) {
+                return Poll::Ready(Ok(message));
+            }
+            self.incoming.push_back(message);
+        }
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.check_connected()?;
+        while self.writer.wants().is_some() {
+            ready!(self.stream.poll_write_ready(cx))?;
+            let written = {
+                let mut slices = [IoSlice::new(&[]); MAX_IOVECS];
+                let count = self.writer.wants_vectored(&mut slices);
+                self.stream.try_write_vectored(&slices[..count])
+            };
+            if let Some(len) = self.check_io(written)? {
+                self.writer.satisfy(len)?;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_read_message(&mut self, cx: &mut Context<'_>) -> Poll<Result<Message>> {
+        self.check_connected()?;
+        loop {
+            if let Some(message) = self.reader.next_message()? {
+                return Poll::Ready(Ok(message));
+            }
+            ready!(self.stream.poll_read_ready(cx))?;
+            let read = self.stream.try_read(self.reader.wants());
+            if let Some(len) = self.check_io(read)? {
+                self.reader.satisfy(len);
+            }
+        }
+    }
+
+    fn check_connected(&self) -> Result<()> {
+        match &self.disconnected {
+            Some(disconnected) => Err(disconnected.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns hangups and socket errors into `Disconnected`, `None` means the
+    /// socket wasn't ready after all.
+    fn check_io(&mut self, result: std::io::Result<usize>) -> Result<Option<usize>> {
+        let disconnected = match result {
+            Ok(0) => Disconnected::eof(),
+            Ok(len) => return Ok(Some(len)),
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {
+                return Ok(None);
+            }
+            Err(err) => Disconnected::from(&err),
+        };
+        let disconnected = disconnected.with_unsent(self.writer.queued());
+        self.disconnected = Some(disconnected.clone());
+        Err(disconnected.into())
+    }
+}
+
+/// Incoming messages that aren't replies to `call`. Yields the
+/// `Disconnected` error once, then ends.
+impl Stream for AsyncConnection {
+    type Item = Result<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(message) = this.incoming.pop_front() {
+            return Poll::Ready(Some(Ok(message)));
+        }
+        if this.disconnected.is_some() {
+            return Poll::Ready(None);
+        }
+        // Replies queued with `enqueue` go out while the stream is polled
+        if let Poll::Ready(Err(err)) = this.poll_flush(cx) {
+            return Poll::Ready(Some(Err(err)));
+        }
+        this.poll_read_message(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncConnection;
+    use crate::{
+        disconnected::Disconnected, encoders::MessageEncoder, fsm::ReaderFSM, messages::Hello,
+        types::Message,
+    };
+    use anyhow::Result;
+    use futures_core::Stream;
+    use std::{future::poll_fn, pin::Pin};
+
+    #[tokio::test]
+    async fn test_async_connection() {
+        use std::io::{Read as _, Write as _};
+
+        let signal = || Message::Signal {
+            serial: 1,
+            path: "/a".into(),
+            interface: "a.b".into(),
+            member: "C".into(),
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![],
+        };
+        let reply = || Message::MethodReturn {
+            serial: 2,
+            reply_serial: 1,
+            destination: None,
+            sender: None,
+            unix_fds: None,
+            body: vec![],
+        };
+
+        let (ours, mut theirs) = std::os::unix::net::UnixStream::pair().unwrap();
+        let bus = std::thread::spawn(move || {
+            theirs.write_all(b"DATA\r\n").unwrap();
+            theirs
+                .write_all(b"OK 0123456789abcdef0123456789abcdef\r\n")
+                .unwrap();
+            let mut handshake = [0; 29];
+            theirs.read_exact(&mut handshake).unwrap();
+
+            let mut reader = ReaderFSM::new();
+            let hello = loop {
+                if let Some(message) = reader.next_message().unwrap() {
+                    break message;
+                }
+                let len = theirs.read(reader.wants()).unwrap();
+                reader.satisfy(len);
+            };
+            assert_eq!(hello.serial(), 1);
+            theirs
+                .write_all(&MessageEncoder::encode(&signal()).unwrap())
+                .unwrap();
+            theirs
+                .write_all(&MessageEncoder::encode(&reply()).unwrap())
+                .unwrap();
+        });
+
+        let mut conn = AsyncConnection::from_std(ours).await.unwrap();
+        assert_eq!(conn.call(&mut Hello.into()).await.unwrap(), reply());
+        bus.join().unwrap();
+
+        async fn next(conn: &mut AsyncConnection) -> Option<Result<Message>> {
+            poll_fn(|cx| Pin::new(&mut *conn).poll_next(cx)).await
+        }
+        assert_eq!(next(&mut conn).await.unwrap().unwrap(), signal());
+        let err = next(&mut conn).await.unwrap().unwrap_err();
+        assert!(err.downcast_ref::<Disconnected>().is_some());
+        assert!(next(&mut conn).await.is_none());
+    }
+}
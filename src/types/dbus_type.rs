@@ -0,0 +1,340 @@
+use crate::types::{CompleteType, Value};
+use anyhow::{Result, bail, ensure};
+use std::{collections::HashMap, hash::Hash};
+
+/// A Rust type with a statically known D-Bus type.
+pub trait DBusType {
+    fn complete_type() -> CompleteType;
+}
+
+macro_rules! impl_basic_type {
+    ($t:ty, $variant:ident) => {
+        impl DBusType for $t {
+            fn complete_type() -> CompleteType {
+                CompleteType::$variant
+            }
+        }
+
+        impl From<$t> for Value {
+            fn from(value: $t) -> Value {
+                Value::$variant(value)
+            }
+        }
+
+        impl TryFrom<Value> for $t {
+            type Error = anyhow::Error;
+
+            fn try_from(value: Value) -> Result<Self> {
+                match value {
+                    Value::$variant(value) => Ok(value),
+                    other => bail!("expected {}, got {:?}", stringify!($variant), other),
+                }
+            }
+        }
+
+        impl TryFrom<&Value> for $t {
+            type Error = anyhow::Error;
+
+            fn try_from(value: &Value) -> Result<Self> {
+                match value {
+                    Value::$variant(value) => Ok(value.clone()),
+                    other => bail!("expected {}, got {:?}", stringify!($variant), other),
+                }
+            }
+        }
+    };
+}
+
+impl_basic_type!(u8, Byte);
+impl_basic_type!(bool, Bool);
+impl_basic_type!(i16, Int16);
+impl_basic_type!(u16, UInt16);
+impl_basic_type!(i32, Int32);
+impl_basic_type!(u32, UInt32);
+impl_basic_type!(i64, Int64);
+impl_basic_type!(u64, UInt64);
+impl_basic_type!(f64, Double);
+impl_basic_type!(String, String);
+
+impl DBusType for &str {
+    fn complete_type() -> CompleteType {
+        CompleteType::String
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+}
+
+/// A value of any type, encoded as `v`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant(pub Value);
+
+impl DBusType for Variant {
+    fn complete_type() -> CompleteType {
+        CompleteType::Variant
+    }
+}
+
+impl From<Variant> for Value {
+    fn from(variant: Variant) -> Value {
+        Value::Variant(Box::new(variant.0))
+    }
+}
+
+impl TryFrom<Value> for Variant {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Variant(inner) => Ok(Self(*inner)),
+            other => bail!("expected Variant, got {other:?}"),
+        }
+    }
+}
+
+impl<T: DBusType> DBusType for Vec<T> {
+    fn complete_type() -> CompleteType {
+        CompleteType::Array(Box::new(T::complete_type()))
+    }
+}
+
+impl<T> From<Vec<T>> for Value
+where
+    T: DBusType + Into<Value>,
+{
+    fn from(items: Vec<T>) -> Value {
+        Value::Array(
+            T::complete_type(),
+            items.into_iter().map(Into::into).collect(),
+        )
+    }
+}
+
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value>,
+    T::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        let Value::Array(_, items) = value else {
+            bail!("expected Array, got {value:?}");
+        };
+        items
+            .into_iter()
+            .map(|item| T::try_from(item).map_err(Into::into))
+            .collect()
+    }
+}
+
+impl<K: DBusType, V: DBusType> DBusType for HashMap<K, V> {
+    fn complete_type() -> CompleteType {
+        CompleteType::Array(Box::new(CompleteType::DictEntry(
+            Box::new(K::complete_type()),
+            Box::new(V::complete_type()),
+        )))
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for Value
+where
+    K: DBusType + Into<Value>,
+    V: DBusType + Into<Value>,
+{
+    fn from(map: HashMap<K, V>) -> Value {
+        Value::Array(
+            CompleteType::DictEntry(Box::new(K::complete_type()), Box::new(V::complete_type())),
+            map.into_iter()
+                .map(|(k, v)| Value::DictEntry(Box::new(k.into()), Box::new(v.into())))
+                .collect(),
+        )
+    }
+}
+
+impl<K, V> TryFrom<Value> for HashMap<K, V>
+where
+    K: TryFrom<Value> + Eq + Hash,
+    K::Error: Into<anyhow::Error>,
+    V: TryFrom<Value>,
+    V::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        let Value::Array(_, items) = value else {
+            bail!("expected Array, got {value:?}");
+        };
+        let mut map = HashMap::with_capacity(items.len());
+        for item in items {
+            let Value::DictEntry(k, v) = item else {
+                bail!("expected DictEntry, got {item:?}");
+            };
+            let k = K::try_from(*k).map_err(Into::into)?;
+            let v = V::try_from(*v).map_err(Into::into)?;
+            map.insert(k, v);
+        }
+        Ok(map)
+    }
+}
+
+/// `Option<T>` is encoded as `aT` holding zero or one item, the mapping the
+/// serde support uses too.
+///
+/// This deliberately isn't the variant first asked for: a variant holding
+/// either the value or an empty array can't tell `None` from `Some` of an
+/// empty array, and its signature says nothing about `T`. The change was
+/// agreed on in review.
+impl<T: DBusType> DBusType for Option<T> {
+    fn complete_type() -> CompleteType {
+        CompleteType::Array(Box::new(T::complete_type()))
+    }
+}
+
+impl<T> From<Option<T>> for Value
+where
+    T: DBusType + Into<Value>,
+{
+    fn from(option: Option<T>) -> Value {
+        Value::Array(
+            T::complete_type(),
+            option.into_iter().map(Into::into).collect(),
+        )
+    }
+}
+
+impl<T> TryFrom<Value> for Option<T>
+where
+    T: DBusType + TryFrom<Value>,
+    T::Error: Into<anyhow::Error>,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        let Value::Array(_, items) = value else {
+            bail!("expected Array, got {value:?}");
+        };
+        let mut items = items.into_iter();
+        let item = items.next();
+        ensure!(
+            items.next().is_none(),
+            "expected at most one item for an Option"
+        );
+        item.map(T::try_from).transpose().map_err(Into::into)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($len:literal, $($t:ident),+) => {
+        impl<$($t: DBusType),+> DBusType for ($($t,)+) {
+            fn complete_type() -> CompleteType {
+                CompleteType::Struct(vec![$($t::complete_type()),+])
+            }
+        }
+
+        impl<$($t: Into<Value>),+> From<($($t,)+)> for Value {
+            #[allow(non_snake_case)]
+            fn from(($($t,)+): ($($t,)+)) -> Value {
+                Value::Struct(vec![$($t.into()),+])
+            }
+        }
+
+        impl<$($t),+> TryFrom<Value> for ($($t,)+)
+        where
+            $($t: TryFrom<Value>, $t::Error: Into<anyhow::Error>),+
+        {
+            type Error = anyhow::Error;
+
+            #[allow(non_snake_case)]
+            fn try_from(value: Value) -> Result<Self> {
+                let Value::Struct(fields) = value else {
+                    bail!("expected Struct, got {value:?}");
+                };
+                let [$($t),+]: [Value; $len] = fields.try_into().map_err(|fields: Vec<Value>| {
+                    anyhow::anyhow!("expected {} fields, got {}", $len, fields.len())
+                })?;
+                Ok(($($t::try_from($t).map_err(Into::into)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(1, A);
+impl_tuple!(2, A, B);
+impl_tuple!(3, A, B, C);
+impl_tuple!(4, A, B, C, D);
+impl_tuple!(5, A, B, C, D, E);
+impl_tuple!(6, A, B, C, D, E, F);
+impl_tuple!(7, A, B, C, D, E, F, G);
+impl_tuple!(8, A, B, C, D, E, F, G, H);
+impl_tuple!(9, A, B, C, D, E, F, G, H, I);
+impl_tuple!(10, A, B, C, D, E, F, G, H, I, J);
+impl_tuple!(11, A, B, C, D, E, F, G, H, I, J, K);
+impl_tuple!(12, A, B, C, D, E, F, G, H, I, J, K, L);
+impl_tuple!(13, A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_tuple!(14, A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_tuple!(15, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+impl_tuple!(16, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+
+#[cfg(test)]
+mod tests {
+    use super::Variant;
+    use crate::types::{CompleteType, DBusType, Value};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_empty_containers_carry_types() {
+        assert_eq!(
+            Value::from(Vec::<String>::new()),
+            Value::Array(CompleteType::String, vec![])
+        );
+        assert_eq!(
+            Value::from(HashMap::<String, Variant>::new()).complete_type(),
+            HashMap::<String, Variant>::complete_type()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Value::from((1_i32, "a", vec![1_u8, 2], Some(7_u64)));
+        assert_eq!(
+            value.complete_type(),
+            <(i32, &str, Vec<u8>, Option<u64>)>::complete_type()
+        );
+        let (n, s, bytes, opt): (i32, String, Vec<u8>, Option<u64>) = value.try_into().unwrap();
+        assert_eq!((n, s.as_str(), bytes, opt), (1, "a", vec![1, 2], Some(7)));
+
+        let mut map = HashMap::new();
+        map.insert(String::from("k"), Variant(Value::Bool(true)));
+        let decoded: HashMap<String, Variant> = Value::from(map.clone()).try_into().unwrap();
+        assert_eq!(decoded, map);
+    }
+
+    #[test]
+    fn test_option() {
+        let none = Value::from(None::<Vec<i32>>);
+        assert_eq!(Option::<Vec<i32>>::try_from(none).unwrap(), None);
+
+        let empty = Value::from(Some(Vec::<i32>::new()));
+        assert_eq!(empty.complete_type(), Option::<Vec<i32>>::complete_type());
+        assert_eq!(Option::<Vec<i32>>::try_from(empty).unwrap(), Some(vec![]));
+
+        assert_eq!(
+            Value::from(Some(7_u64)),
+            Value::Array(CompleteType::UInt64, vec![Value::UInt64(7)])
+        );
+        assert!(Option::<i32>::try_from(Value::Variant(Box::new(Value::Bool(true)))).is_err());
+        let two = Value::Array(CompleteType::Int32, vec![Value::Int32(1), Value::Int32(2)]);
+        assert!(Option::<i32>::try_from(two).is_err());
+    }
+
+    #[test]
+    fn test_try_from_ref() {
+        let value = Value::String(String::from("abc"));
+        assert_eq!(String::try_from(&value).unwrap(), "abc");
+        assert!(u32::try_from(&value).is_err());
+    }
+}
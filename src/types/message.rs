@@ -1,7 +1,7 @@
-use crate::types::{MessageType, Value};
+use crate::types::{Flags, MessageType, Value};
 use std::borrow::Cow;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Message {
     MethodCall {
         serial: u32,
@@ -11,6 +11,8 @@ pub enum Message {
         destination: Option<Cow<'static, str>>,
         sender: Option<Cow<'static, str>>,
         unix_fds: Option<u32>,
+        /// `NO_REPLY_EXPECTED` is set in the header
+        no_reply_expected: bool,
         body: Vec<Value>,
     },
     MethodReturn {
@@ -52,6 +54,14 @@ impl Message {
         }
     }
 
+    #[cfg(any(
+        test,
+        feature = "blocking",
+        feature = "poll",
+        feature = "io-uring",
+        feature = "tokio",
+        feature = "futures"
+    ))]
     pub(crate) fn serial_mut(&mut self) -> &mut u32 {
         match self {
             Self::MethodCall { serial, .. }
@@ -70,9 +80,21 @@ impl Message {
         }
     }
 
-    pub(crate) fn path(&self) -> Option<Cow<'static, str>> {
+    pub(crate) fn flags(&self) -> Flags {
         match self {
-            Self::MethodCall { path, .. } | Self::Signal { path, .. } => Some(path.clone()),
+            Self::MethodCall {
+                no_reply_expected: true,
+                ..
+            } => Flags {
+                byte: Flags::NO_REPLY_EXPECTED,
+            },
+            _ => Flags::default(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> Option<&str> {
+        match self {
+            Self::MethodCall { path, .. } | Self::Signal { path, .. } => Some(path),
             _ => None,
         }
     }
@@ -108,12 +130,12 @@ impl Message {
         }
     }
 
-    pub(crate) fn destination(&self) -> Option<Cow<'static, str>> {
+    pub(crate) fn destination(&self) -> Option<&str> {
         match self {
             Self::MethodCall { destination, .. }
             | Self::MethodReturn { destination, .. }
             | Self::Error { destination, .. }
-            | Self::Signal { destination, .. } => destination.clone(),
+            | Self::Signal { destination, .. } => destination.as_deref(),
         }
     }
 
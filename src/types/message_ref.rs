@@ -0,0 +1,191 @@
+use crate::{
+    decoders::{BodyReader, DecodingBuffer, MessageDecoder, SignatureDecoder},
+    types::{Message, Value, ValueRef},
+};
+use anyhow::{Context as _, Result};
+
+/// A `Message` borrowing its header fields and body from the buffer it was
+/// read into. The body is only decoded when asked for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MessageRef<'buf> {
+    MethodCall {
+        serial: u32,
+        path: &'buf str,
+        member: &'buf str,
+        interface: Option<&'buf str>,
+        destination: Option<&'buf str>,
+        sender: Option<&'buf str>,
+        unix_fds: Option<u32>,
+        /// `NO_REPLY_EXPECTED` is set in the header
+        no_reply_expected: bool,
+        body: BodyRef<'buf>,
+    },
+    MethodReturn {
+        serial: u32,
+        reply_serial: u32,
+        destination: Option<&'buf str>,
+        sender: Option<&'buf str>,
+        unix_fds: Option<u32>,
+        body: BodyRef<'buf>,
+    },
+    Error {
+        serial: u32,
+        error_name: &'buf str,
+        reply_serial: u32,
+        destination: Option<&'buf str>,
+        sender: Option<&'buf str>,
+        unix_fds: Option<u32>,
+        body: BodyRef<'buf>,
+    },
+    Signal {
+        serial: u32,
+        path: &'buf str,
+        interface: &'buf str,
+        member: &'buf str,
+        destination: Option<&'buf str>,
+        sender: Option<&'buf str>,
+        unix_fds: Option<u32>,
+        body: BodyRef<'buf>,
+    },
+}
+
+/// The still-encoded body of a `MessageRef`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BodyRef<'buf> {
+    pub(crate) signature: &'buf [u8],
+    pub(crate) bytes: &'buf [u8],
+}
+
+impl<'buf> BodyRef<'buf> {
+    pub fn signature(&self) -> &'buf [u8] {
+        self.signature
+    }
+
+    pub fn bytes(&self) -> &'buf [u8] {
+        self.bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signature.is_empty()
+    }
+
+    /// Returns a cursor that decodes the arguments one at a time.
+    pub fn reader(&self) -> Result<BodyReader<'buf>> {
+        let signature =
+            SignatureDecoder::decode_signature(&mut DecodingBuffer::new(self.signature))?;
+        Ok(BodyReader::new(self.bytes, signature.items))
+    }
+
+    pub fn values(&self) -> Result<Vec<ValueRef<'buf>>> {
+        self.reader()?.collect()
+    }
+
+    pub fn to_values(&self) -> Result<Vec<Value>> {
+        Ok(self.values()?.iter().map(ValueRef::to_value).collect())
+    }
+}
+
+impl<'buf> MessageRef<'buf> {
+    /// Parses the header of a complete message in `bytes`.
+    pub fn decode(bytes: &'buf [u8]) -> Result<Self> {
+        MessageDecoder::decode_ref(bytes)
+    }
+
+    pub fn serial(&self) -> u32 {
+        match self {
+            Self::MethodCall { serial, .. }
+            | Self::MethodReturn { serial, .. }
+            | Self::Error { serial, .. }
+            | Self::Signal { serial, .. } => *serial,
+        }
+    }
+
+    pub fn body(&self) -> BodyRef<'buf> {
+        match self {
+            Self::MethodCall { body, .. }
+            | Self::MethodReturn { body, .. }
+            | Self::Error { body, .. }
+            | Self::Signal { body, .. } => *body,
+        }
+    }
+
+    /// Copies the message out of the buffer, decoding its body.
+    pub fn to_message(&self) -> Result<Message> {
+        let owned = |s: &str| s.to_string().into();
+        let body = self.body().to_values().context("malformed body")?;
+        Ok(match *self {
+            Self::MethodCall {
+                serial,
+                path,
+                member,
+                interface,
+                destination,
+                sender,
+                unix_fds,
+                no_reply_expected,
+                ..
+            } => Message::MethodCall {
+                serial,
+                path: owned(path),
+                member: owned(member),
+                interface: interface.map(owned),
+                destination: destination.map(owned),
+                sender: sender.map(owned),
+                unix_fds,
+                no_reply_expected,
+                body,
+            },
+            Self::MethodReturn {
+                serial,
+                reply_serial,
+                destination,
+                sender,
+                unix_fds,
+                ..
+            } => Message::MethodReturn {
+                serial,
+                reply_serial,
+                destination: destination.map(owned),
+                sender: sender.map(owned),
+                unix_fds,
+                body,
+            },
+            Self::Error {
+                serial,
+                error_name,
+                reply_serial,
+                destination,
+                sender,
+                unix_fds,
+                ..
+            } => Message::Error {
+                serial,
+                error_name: error_name.to_string(),
+                reply_serial,
+                destination: destination.map(owned),
+                sender: sender.map(owned),
+                unix_fds,
+                body,
+            },
+            Self::Signal {
+                serial,
+                path,
+                interface,
+                member,
+                destination,
+                sender,
+                unix_fds,
+                ..
+            } => Message::Signal {
+                serial,
+                path: owned(path),
+                interface: owned(interface),
+                member: owned(member),
+                destination: destination.map(owned),
+                sender: sender.map(owned),
+                unix_fds,
+                body,
+            },
+        })
+    }
+}
@@ -4,6 +4,9 @@ pub(crate) use header_field_name::HeaderFieldName;
 mod message;
 pub use message::Message;
 
+mod message_ref;
+pub use message_ref::{BodyRef, MessageRef};
+
 mod message_type;
 pub(crate) use message_type::MessageType;
 
@@ -17,6 +20,14 @@ pub(crate) use signature::Signature;
 mod value;
 pub use value::Value;
 
+mod text;
+
+mod value_ref;
+pub use value_ref::ValueRef;
+
+mod dbus_type;
+pub use dbus_type::{DBusType, Variant};
+
 mod guid;
 pub(crate) use guid::Guid;
 
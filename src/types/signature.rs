@@ -44,6 +44,30 @@ impl CompleteType {
     }
 }
 
+impl std::str::FromStr for CompleteType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        use crate::decoders::{DecodingBuffer, SignatureDecoder};
+
+        let mut buf = DecodingBuffer::new(s.as_bytes());
+        let complete_type = SignatureDecoder::decode_complete_type(&mut buf)?;
+        anyhow::ensure!(buf.is_eof(), "{s:?} is not a single complete type");
+        Ok(complete_type)
+    }
+}
+
+impl std::fmt::Display for CompleteType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use crate::encoders::{EncodingBuffer, SignatureEncoder};
+
+        let mut buf = EncodingBuffer::new();
+        SignatureEncoder::encode_complete_type(&mut buf, self);
+        let bytes = buf.done();
+        f.write_str(std::str::from_utf8(&bytes).map_err(|_| std::fmt::Error)?)
+    }
+}
+
 #[derive(PartialEq, Eq)]
 pub(crate) struct Signature {
     pub(crate) items: Vec<CompleteType>,
@@ -0,0 +1,666 @@
+use crate::{
+    decoders::{DecodingBuffer, SignatureDecoder},
+    types::{CompleteType, Value},
+};
+use anyhow::{Context, Result, bail, ensure};
+use std::fmt::{self, Write as _};
+
+impl Value {
+    /// Prints the value in GVariant text format, e.g. `{'key': <int32 5>}`.
+    ///
+    /// Types are annotated wherever the text alone wouldn't give them back,
+    /// so `parse_text` returns the same value with or without knowing its
+    /// type up front.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, true).expect("writing to a String can't fail");
+        out
+    }
+
+    /// Parses the output of `to_text`, or any GVariant text of
+    /// `complete_type`. Type annotations have to agree with it.
+    pub fn parse_text(text: &str, complete_type: &CompleteType) -> Result<Self> {
+        let mut parser = Parser { text, pos: 0 };
+        let value = parser.value(Some(complete_type))?;
+        parser.skip_whitespace();
+        ensure!(
+            parser.rest().is_empty(),
+            "unexpected {:?} after the value",
+            parser.rest()
+        );
+        Ok(value)
+    }
+}
+
+/// `annotate` is set when the type can't be known from the context: at
+/// the top, inside variants and for the first item of arrays, which gives
+/// the type of the others.
+fn write_value(out: &mut String, value: &Value, annotate: bool) -> fmt::Result {
+    let prefix = |out: &mut String, keyword: &str| {
+        if annotate {
+            write!(out, "{keyword} ")
+        } else {
+            Ok(())
+        }
+    };
+    match value {
+        Value::Bool(value) => write!(out, "{value}"),
+        Value::Byte(value) => {
+            prefix(out, "byte")?;
+            write!(out, "0x{value:02x}")
+        }
+        Value::Int16(value) => {
+            prefix(out, "int16")?;
+            write!(out, "{value}")
+        }
+        Value::UInt16(value) => {
+            prefix(out, "uint16")?;
+            write!(out, "{value}")
+        }
+        // Integers are int32 unless annotated
+        Value::Int32(value) => write!(out, "{value}"),
+        Value::UInt32(value) => {
+            prefix(out, "uint32")?;
+            write!(out, "{value}")
+        }
+        Value::Int64(value) => {
+            prefix(out, "int64")?;
+            write!(out, "{value}")
+        }
+        Value::UInt64(value) => {
+            prefix(out, "uint64")?;
+            write!(out, "{value}")
+        }
+        Value::UnixFD(value) => {
+            prefix(out, "handle")?;
+            write!(out, "{value}")
+        }
+        Value::Double(value) if value.is_nan() => write!(out, "nan"),
+        Value::Double(value) if value.is_infinite() => {
+            write!(out, "{}inf", if *value < 0.0 { "-" } else { "" })
+        }
+        // Debug always has a decimal point or an exponent
+        Value::Double(value) => write!(out, "{value:?}"),
+        Value::String(value) => write_string(out, value),
+        Value::ObjectPath(value) => {
+            prefix(out, "objectpath")?;
+            write_string(out, value)
+        }
+        Value::Signature(value) => {
+            prefix(out, "signature")?;
+            write_string(out, &String::from_utf8_lossy(value))
+        }
+        Value::Variant(value) => {
+            out.push('<');
+            write_value(out, value, true)?;
+            out.push('>');
+            Ok(())
+        }
+        Value::Array(item_type, items) => {
+            let dict = matches!(item_type, CompleteType::DictEntry(..));
+            if items.is_empty() {
+                if annotate {
+                    write!(out, "@{} ", value.complete_type())?;
+                }
+                return write!(out, "{}", if dict { "{}" } else { "[]" });
+            }
+
+            out.push(if dict { '{' } else { '[' });
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                match item {
+                    Value::DictEntry(key, value) if dict => {
+                        write_value(out, key, annotate && idx == 0)?;
+                        out.push_str(": ");
+                        write_value(out, value, annotate && idx == 0)?;
+                    }
+                    item => write_value(out, item, annotate && idx == 0)?,
+                }
+            }
+            out.push(if dict { '}' } else { ']' });
+            Ok(())
+        }
+        Value::Struct(fields) => {
+            out.push('(');
+            for (idx, field) in fields.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, field, annotate)?;
+            }
+            if fields.len() == 1 {
+                out.push(',');
+            }
+            out.push(')');
+            Ok(())
+        }
+        Value::DictEntry(key, value) => {
+            out.push('{');
+            write_value(out, key, annotate)?;
+            out.push_str(", ");
+            write_value(out, value, annotate)?;
+            out.push('}');
+            Ok(())
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) -> fmt::Result {
+    out.push('\'');
+    for c in value.chars() {
+        match c {
+            '\'' => out.push_str(r"\'"),
+            '\\' => out.push_str(r"\\"),
+            '\n' => out.push_str(r"\n"),
+            '\t' => out.push_str(r"\t"),
+            '\r' => out.push_str(r"\r"),
+            c if c.is_control() => write!(out, r"\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    Ok(())
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        ensure!(
+            self.eat(c),
+            "expected {c:?} at offset {}, got {:?}",
+            self.pos,
+            self.rest()
+        );
+        Ok(())
+    }
+
+    /// Takes characters while `f` holds.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    /// Parses a value of `expected`, or of the type its text gives if
+    /// `None`.
+    fn value(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek().context("unexpected end of text")? {
+            '@' => {
+                self.pos += 1;
+                let annotated = self
+                    .take_while(|c| !c.is_whitespace())
+                    .parse::<CompleteType>()?;
+                check_type(expected, &annotated)?;
+                self.value(Some(&annotated))
+            }
+            '<' => {
+                check_type(expected, &CompleteType::Variant)?;
+                self.pos += 1;
+                let value = self.value(None)?;
+                self.expect('>')?;
+                Ok(Value::Variant(Box::new(value)))
+            }
+            '(' => self.tuple(expected),
+            '[' => self.array(expected),
+            '{' => self.braces(expected),
+            '\'' | '"' => self.string(expected),
+            _ => self.word(expected),
+        }
+    }
+
+    fn tuple(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        let fields = match expected {
+            Some(CompleteType::Struct(fields)) => Some(fields),
+            None => None,
+            Some(other) => bail!("expected {other}, got a tuple"),
+        };
+        self.pos += 1;
+        let mut values = vec![];
+        self.list(')', |parser| {
+            let field = match fields {
+                Some(fields) => Some(
+                    fields
+                        .get(values.len())
+                        .context("too many fields in tuple")?,
+                ),
+                None => None,
+            };
+            values.push(parser.value(field)?);
+            Ok(())
+        })?;
+        if let Some(fields) = fields {
+            ensure!(fields.len() == values.len(), "too few fields in tuple");
+        }
+        Ok(Value::Struct(values))
+    }
+
+    fn array(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        let mut item_type = match expected {
+            Some(CompleteType::Array(item)) => Some((**item).clone()),
+            None => None,
+            Some(other) => bail!("expected {other}, got an array"),
+        };
+        self.pos += 1;
+        let mut items = vec![];
+        self.list(']', |parser| {
+            let item = parser.value(item_type.as_ref())?;
+            item_type.get_or_insert_with(|| item.complete_type());
+            items.push(item);
+            Ok(())
+        })?;
+        let item_type = item_type.context("the type of an empty array must be annotated")?;
+        Ok(Value::Array(item_type, items))
+    }
+
+    /// A dictionary `{k: v, ...}`, or a single dict entry `{k, v}`.
+    fn braces(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        let (key_type, value_type) = match expected {
+            Some(CompleteType::DictEntry(key, value)) => {
+                self.pos += 1;
+                let key = self.value(Some(key))?;
+                self.expect(',')?;
+                let value = self.value(Some(value))?;
+                self.expect('}')?;
+                return Ok(Value::DictEntry(Box::new(key), Box::new(value)));
+            }
+            Some(CompleteType::Array(item)) => {
+                let CompleteType::DictEntry(key, value) = &**item else {
+                    bail!("expected a{item}, got a dictionary");
+                };
+                ((**key).clone(), (**value).clone())
+            }
+            Some(other) => bail!("expected {other}, got a dictionary"),
+            None => return self.untyped_braces(),
+        };
+        self.pos += 1;
+        let mut entries = vec![];
+        self.entries(&key_type, &value_type, &mut entries)?;
+        Ok(dictionary(key_type, value_type, entries))
+    }
+
+    /// Like `braces`, typed by the first entry.
+    fn untyped_braces(&mut self) -> Result<Value> {
+        self.pos += 1;
+        ensure!(
+            !self.eat('}'),
+            "the type of an empty dictionary must be annotated"
+        );
+        let key = self.value(None)?;
+        if self.eat(',') {
+            let value = self.value(None)?;
+            self.expect('}')?;
+            return Ok(Value::DictEntry(Box::new(key), Box::new(value)));
+        }
+
+        self.expect(':')?;
+        let value = self.value(None)?;
+        let (key_type, value_type) = (key.complete_type(), value.complete_type());
+        let mut entries = vec![Value::DictEntry(Box::new(key), Box::new(value))];
+        if self.eat(',') {
+            self.entries(&key_type, &value_type, &mut entries)?;
+        } else {
+            self.expect('}')?;
+        }
+        Ok(dictionary(key_type, value_type, entries))
+    }
+
+    /// The remaining `k: v` entries of a dictionary and its closing brace.
+    fn entries(
+        &mut self,
+        key_type: &CompleteType,
+        value_type: &CompleteType,
+        entries: &mut Vec<Value>,
+    ) -> Result<()> {
+        self.list('}', |parser| {
+            let key = parser.value(Some(key_type))?;
+            parser.expect(':')?;
+            let value = parser.value(Some(value_type))?;
+            entries.push(Value::DictEntry(Box::new(key), Box::new(value)));
+            Ok(())
+        })
+    }
+
+    /// Items separated by commas up to `close`, a trailing comma is allowed.
+    fn list(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<()>) -> Result<()> {
+        let mut first = true;
+        loop {
+            if self.eat(close) {
+                return Ok(());
+            }
+            if !first {
+                self.expect(',')?;
+                if self.eat(close) {
+                    return Ok(());
+                }
+            }
+            item(self)?;
+            first = false;
+        }
+    }
+
+    fn string(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        let quote = self.peek().context("unexpected end of text")?;
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        loop {
+            let (idx, c) = chars.next().context("unterminated string")?;
+            match c {
+                c if c == quote => {
+                    self.pos += idx + 1;
+                    break;
+                }
+                '\\' => {
+                    let (_, escaped) = chars.next().context("unterminated string")?;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'u' | 'U' => {
+                            let len = if escaped == 'u' { 4 } else { 8 };
+                            let hex = (0..len)
+                                .map(|_| chars.next().map(|(_, c)| c))
+                                .collect::<Option<String>>()
+                                .context("unterminated escape")?;
+                            ensure!(
+                                hex.chars().all(|c| c.is_ascii_hexdigit()),
+                                "invalid escape \\{escaped}{hex}"
+                            );
+                            let code = u32::from_str_radix(&hex, 16)?;
+                            out.push(char::from_u32(code).context("invalid code point")?);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+
+        match expected {
+            None | Some(CompleteType::String) => Ok(Value::String(out)),
+            Some(CompleteType::ObjectPath) => {
+                ensure!(out.starts_with('/'), "invalid object path {out:?}");
+                Ok(Value::ObjectPath(out.into()))
+            }
+            Some(CompleteType::Signature) => {
+                SignatureDecoder::decode_signature(&mut DecodingBuffer::new(out.as_bytes()))?;
+                Ok(Value::Signature(out.into_bytes()))
+            }
+            Some(other) => bail!("expected {other}, got a string"),
+        }
+    }
+
+    /// Numbers, booleans, and type keywords followed by a value.
+    fn word(&mut self, expected: Option<&CompleteType>) -> Result<Value> {
+        let word = self
+            .take_while(|c| c.is_ascii_alphanumeric() || "+-._".contains(c))
+            .to_string();
+        ensure!(!word.is_empty(), "unexpected {:?}", self.rest());
+
+        if let Some(keyword) = keyword(&word) {
+            check_type(expected, &keyword)?;
+            return self.value(Some(&keyword));
+        }
+        if word == "true" || word == "false" {
+            check_type(expected, &CompleteType::Bool)?;
+            return Ok(Value::Bool(word == "true"));
+        }
+
+        let is_double = !word.starts_with("0x")
+            && !word.starts_with("-0x")
+            && (word.contains(['.', 'e', 'E']) || word.ends_with("inf") || word == "nan");
+        let default = if is_double {
+            CompleteType::Double
+        } else {
+            CompleteType::Int32
+        };
+        let value = match expected.unwrap_or(&default) {
+            CompleteType::Byte => Value::Byte(integer(&word)?),
+            CompleteType::Int16 => Value::Int16(integer(&word)?),
+            CompleteType::UInt16 => Value::UInt16(integer(&word)?),
+            CompleteType::Int32 => Value::Int32(integer(&word)?),
+            CompleteType::UInt32 => Value::UInt32(integer(&word)?),
+            CompleteType::Int64 => Value::Int64(integer(&word)?),
+            CompleteType::UInt64 => Value::UInt64(integer(&word)?),
+            CompleteType::UnixFD => Value::UnixFD(integer(&word)?),
+            CompleteType::Double => Value::Double(
+                word.parse()
+                    .with_context(|| format!("invalid number {word:?}"))?,
+            ),
+            other => bail!("expected {other}, got {word:?}"),
+        };
+        Ok(value)
+    }
+}
+
+fn dictionary(key_type: CompleteType, value_type: CompleteType, entries: Vec<Value>) -> Value {
+    Value::Array(
+        CompleteType::DictEntry(Box::new(key_type), Box::new(value_type)),
+        entries,
+    )
+}
+
+fn keyword(word: &str) -> Option<CompleteType> {
+    let keyword = match word {
+        "byte" => CompleteType::Byte,
+        "int16" => CompleteType::Int16,
+        "uint16" => CompleteType::UInt16,
+        "int32" => CompleteType::Int32,
+        "uint32" => CompleteType::UInt32,
+        "int64" => CompleteType::Int64,
+        "uint64" => CompleteType::UInt64,
+        "double" => CompleteType::Double,
+        "handle" => CompleteType::UnixFD,
+        "objectpath" => CompleteType::ObjectPath,
+        "signature" => CompleteType::Signature,
+        _ => return None,
+    };
+    Some(keyword)
+}
+
+fn check_type(expected: Option<&CompleteType>, found: &CompleteType) -> Result<()> {
+    if let Some(expected) = expected {
+        ensure!(expected == found, "expected {expected}, got {found}");
+    }
+    Ok(())
+}
+
+/// Parses decimal or `0x` hex into any integer type, checking its range.
+fn integer<T: TryFrom<i128>>(word: &str) -> Result<T> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word.strip_prefix('+').unwrap_or(word)),
+    };
+    let (radix, digits) = match digits.strip_prefix("0x") {
+        Some(hex) => (16, hex),
+        None => (10, digits),
+    };
+    // from_str_radix takes a sign of its own, only the one above is allowed.
+    ensure!(
+        digits.chars().all(|c| c.is_digit(radix)),
+        "invalid number {word:?}"
+    );
+    let magnitude =
+        i128::from_str_radix(digits, radix).with_context(|| format!("invalid number {word:?}"))?;
+    let value = if negative { -magnitude } else { magnitude };
+    T::try_from(value)
+        .ok()
+        .with_context(|| format!("{word} is out of range"))
+}
+
+#[test]
+fn test_text_round_trip() {
+    use std::borrow::Cow;
+
+    let dict = |entries: Vec<(Value, Value)>, key: CompleteType, value: CompleteType| {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| Value::DictEntry(Box::new(key), Box::new(value)))
+            .collect();
+        Value::Array(
+            CompleteType::DictEntry(Box::new(key), Box::new(value)),
+            entries,
+        )
+    };
+    let variant = |value| Value::Variant(Box::new(value));
+    let basics = vec![
+        Value::Byte(0xff),
+        Value::Bool(true),
+        Value::Int16(i16::MIN),
+        Value::UInt16(u16::MAX),
+        Value::Int32(-5),
+        Value::UInt32(u32::MAX),
+        Value::Int64(i64::MIN),
+        Value::UInt64(u64::MAX),
+        Value::Double(1.5),
+        Value::Double(-1e300),
+        Value::Double(f64::INFINITY),
+        Value::Double(100.0),
+        Value::UnixFD(3),
+        Value::String("it's a \"test\"\\\n\t\u{1}ünï".into()),
+        Value::ObjectPath(Cow::Borrowed("/org/me")),
+        Value::Signature(b"a{sv}".to_vec()),
+    ];
+
+    let mut values = basics.clone();
+    values.extend(basics.iter().cloned().map(variant));
+    values.extend([
+        Value::Struct(basics.clone()),
+        Value::Struct(vec![Value::Int32(1)]),
+        Value::Array(CompleteType::String, vec![]),
+        Value::Array(
+            CompleteType::Array(Box::new(CompleteType::Byte)),
+            vec![
+                Value::Array(CompleteType::Byte, vec![]),
+                Value::Array(CompleteType::Byte, vec![Value::Byte(1), Value::Byte(2)]),
+            ],
+        ),
+        Value::Array(
+            CompleteType::Variant,
+            vec![variant(Value::Int32(1)), variant(Value::UInt64(2))],
+        ),
+        dict(vec![], CompleteType::String, CompleteType::Variant),
+        dict(
+            vec![
+                (Value::String("key".into()), variant(Value::Int32(5))),
+                (
+                    Value::String("nested".into()),
+                    variant(dict(
+                        vec![(Value::UInt16(1), Value::ObjectPath("/a".into()))],
+                        CompleteType::UInt16,
+                        CompleteType::ObjectPath,
+                    )),
+                ),
+                (
+                    Value::String("empty".into()),
+                    variant(dict(vec![], CompleteType::Int64, CompleteType::Variant)),
+                ),
+            ],
+            CompleteType::String,
+            CompleteType::Variant,
+        ),
+        Value::DictEntry(
+            Box::new(Value::Byte(1)),
+            Box::new(variant(variant(Value::Bool(false)))),
+        ),
+    ]);
+
+    for value in values {
+        let text = value.to_text();
+        let parsed = Value::parse_text(&text, &value.complete_type());
+        assert_eq!(parsed.ok().as_ref(), Some(&value), "{text}");
+        let untyped = Value::parse_text(&format!("<{text}>"), &CompleteType::Variant);
+        assert_eq!(untyped.ok(), Some(variant(value.clone())), "{text}");
+    }
+}
+
+#[test]
+fn test_parse_text() {
+    let parse = |text: &str, signature: &str| Value::parse_text(text, &signature.parse().unwrap());
+
+    assert_eq!(
+        parse("{'key': <int32 5>}", "a{sv}").unwrap().to_text(),
+        "{'key': <5>}"
+    );
+    assert_eq!(
+        parse("[1, 2, 3,]", "ai").unwrap(),
+        Value::Array(
+            CompleteType::Int32,
+            vec![Value::Int32(1), Value::Int32(2), Value::Int32(3)]
+        )
+    );
+    assert_eq!(
+        parse(r#"("a", @as [])"#, "(sas)").unwrap(),
+        Value::Struct(vec![
+            Value::String("a".into()),
+            Value::Array(CompleteType::String, vec![])
+        ])
+    );
+    assert_eq!(
+        parse("objectpath '/foo'", "o").unwrap(),
+        Value::ObjectPath("/foo".into())
+    );
+    assert_eq!(parse("0x10", "y").unwrap(), Value::Byte(16));
+    assert_eq!(parse(r"'é'", "s").unwrap(), Value::String("é".into()));
+    assert_eq!(parse("<2.0>", "v").unwrap().to_text(), "<2.0>");
+    assert_eq!(
+        Value::UInt32(7).to_text(),
+        "uint32 7",
+        "annotated at the top"
+    );
+
+    for (text, signature) in [
+        ("uint32 5", "i"),
+        ("256", "y"),
+        ("-1", "u"),
+        ("'a'", "o"),
+        ("'a{'", "g"),
+        ("[1, 'a']", "ai"),
+        ("<[]>", "v"),
+        ("<{}>", "v"),
+        ("(1, 2)", "(i)"),
+        ("(1)", "(ii)"),
+        ("'unterminated", "s"),
+        ("1 2", "i"),
+        ("{1: 2}", "ai"),
+        ("true", "i"),
+        ("--5", "i"),
+        ("-+5", "i"),
+        ("0x-5", "i"),
+        ("0x+5", "i"),
+        (r"'\u+041'", "s"),
+        (r"'\U+0000041'", "s"),
+    ] {
+        assert!(parse(text, signature).is_err(), "{text} as {signature}");
+    }
+}
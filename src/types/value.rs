@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 
 use crate::types::signature::CompleteType;
+use anyhow::{Result, ensure};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
@@ -34,8 +35,21 @@ impl Value {
         Self::Array(item_type, items)
     }
 
-    pub(crate) fn complete_type(&self) -> CompleteType {
-        match self {
+    /// The type of this value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an array holds an item of another type than its own, see
+    /// `try_complete_type` for a fallible version.
+    pub fn complete_type(&self) -> CompleteType {
+        self.try_complete_type().expect("heterogenous array")
+    }
+
+    /// The type of this value, or an error if an array, here or nested in a
+    /// struct, dict entry or variant, holds an item of another type than its
+    /// own.
+    pub fn try_complete_type(&self) -> Result<CompleteType> {
+        let complete_type = match self {
             Self::Byte(_) => CompleteType::Byte,
             Self::Bool(_) => CompleteType::Bool,
             Self::Int16(_) => CompleteType::Int16,
@@ -52,23 +66,29 @@ impl Value {
             Self::Struct(values) => {
                 let mut types = vec![];
                 for value in values {
-                    types.push(value.complete_type());
+                    types.push(value.try_complete_type()?);
                 }
                 CompleteType::Struct(types)
             }
             Self::Array(item_type, items) => {
                 for item in items {
-                    if item.complete_type() != *item_type {
-                        panic!("heterogenous array")
-                    }
+                    let actual = item.try_complete_type()?;
+                    ensure!(
+                        actual == *item_type,
+                        "heterogenous array: expected {item_type} items, got {actual}"
+                    );
                 }
                 CompleteType::Array(Box::new(item_type.clone()))
             }
             Self::DictEntry(key, value) => CompleteType::DictEntry(
-                Box::new(key.complete_type()),
-                Box::new(value.complete_type()),
+                Box::new(key.try_complete_type()?),
+                Box::new(value.try_complete_type()?),
             ),
-            Self::Variant(_value) => CompleteType::Variant,
-        }
+            Self::Variant(value) => {
+                value.try_complete_type()?;
+                CompleteType::Variant
+            }
+        };
+        Ok(complete_type)
     }
 }
@@ -0,0 +1,87 @@
+use crate::types::{CompleteType, Value};
+
+/// A `Value` whose strings and signatures borrow from the buffer it was
+/// decoded from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ValueRef<'buf> {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    UnixFD(u32),
+
+    String(&'buf str),
+    ObjectPath(&'buf str),
+    Signature(&'buf [u8]),
+    Struct(Vec<ValueRef<'buf>>),
+    Array(CompleteType, Vec<ValueRef<'buf>>),
+    DictEntry(Box<ValueRef<'buf>>, Box<ValueRef<'buf>>),
+    Variant(Box<ValueRef<'buf>>),
+}
+
+impl ValueRef<'_> {
+    pub fn complete_type(&self) -> CompleteType {
+        match self {
+            Self::Byte(_) => CompleteType::Byte,
+            Self::Bool(_) => CompleteType::Bool,
+            Self::Int16(_) => CompleteType::Int16,
+            Self::UInt16(_) => CompleteType::UInt16,
+            Self::Int32(_) => CompleteType::Int32,
+            Self::UInt32(_) => CompleteType::UInt32,
+            Self::Int64(_) => CompleteType::Int64,
+            Self::UInt64(_) => CompleteType::UInt64,
+            Self::Double(_) => CompleteType::Double,
+            Self::UnixFD(_) => CompleteType::UnixFD,
+            Self::String(_) => CompleteType::String,
+            Self::ObjectPath(_) => CompleteType::ObjectPath,
+            Self::Signature(_) => CompleteType::Signature,
+            Self::Struct(fields) => {
+                CompleteType::Struct(fields.iter().map(Self::complete_type).collect())
+            }
+            Self::Array(item_type, _) => CompleteType::Array(Box::new(item_type.clone())),
+            Self::DictEntry(key, value) => CompleteType::DictEntry(
+                Box::new(key.complete_type()),
+                Box::new(value.complete_type()),
+            ),
+            Self::Variant(_) => CompleteType::Variant,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Byte(v) => Value::Byte(*v),
+            Self::Bool(v) => Value::Bool(*v),
+            Self::Int16(v) => Value::Int16(*v),
+            Self::UInt16(v) => Value::UInt16(*v),
+            Self::Int32(v) => Value::Int32(*v),
+            Self::UInt32(v) => Value::UInt32(*v),
+            Self::Int64(v) => Value::Int64(*v),
+            Self::UInt64(v) => Value::UInt64(*v),
+            Self::Double(v) => Value::Double(*v),
+            Self::UnixFD(v) => Value::UnixFD(*v),
+            Self::String(s) => Value::String(s.to_string()),
+            Self::ObjectPath(path) => Value::ObjectPath(path.to_string().into()),
+            Self::Signature(sig) => Value::Signature(sig.to_vec()),
+            Self::Struct(fields) => Value::Struct(fields.iter().map(Self::to_value).collect()),
+            Self::Array(item_type, items) => Value::Array(
+                item_type.clone(),
+                items.iter().map(Self::to_value).collect(),
+            ),
+            Self::DictEntry(key, value) => {
+                Value::DictEntry(Box::new(key.to_value()), Box::new(value.to_value()))
+            }
+            Self::Variant(inner) => Value::Variant(Box::new(inner.to_value())),
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    fn from(value: ValueRef<'_>) -> Value {
+        value.to_value()
+    }
+}